@@ -10,4 +10,4 @@ pub mod storage;
 
 pub use seed::{DnaSeed, LowRankIdentity, MutationRules, ReplicationPolicy};
 pub use zk::{ZkCommitment, CapabilityProof};
-pub use storage::SeedStore;
+pub use storage::{SeedStore, StoreOutcome};
@@ -4,4 +4,6 @@
 //! High-fitness seeds spread; low-fitness seeds are pruned.
 
 pub mod sync;
+pub mod tsqr;
 pub use sync::SeedNetwork;
+pub use tsqr::{GlobalBasis, ShardPeer, ShardSummary, TsqrHarness};
@@ -2,7 +2,8 @@
 //!
 //! Gossip protocol for distributing DNA seeds across peers.
 
-use crate::seed::DnaSeed;
+use crate::seed::{DnaSeed, QuantBits, QuantizedSeed};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -31,7 +32,7 @@ impl From<&DnaSeed> for SeedFingerprint {
             seed_id: seed.id.clone(),
             name: seed.name.clone(),
             rank: seed.lrim.rank,
-            fitness: seed.fitness,
+            fitness: seed.effective_fitness(Utc::now()),
             epoch: seed.epoch,
             domains: seed.domains.clone(),
             commitment_hash: seed.commitment.matrix_hash.clone(),
@@ -77,15 +78,20 @@ impl SeedNetwork {
             .collect()
     }
 
+    /// Store a peer's seed if it is fit, healthy and its commitment opens
+    /// to its factors
     pub fn accept_seed(&mut self, seed: DnaSeed) -> bool {
-        if seed.fitness < self.min_fitness_threshold { return false; }
-        if !seed.commitment.verify(&seed.lrim) { return false; }
+        let now = Utc::now();
+        let fitness = seed.effective_fitness(now);
+        if fitness < self.min_fitness_threshold { return false; }
+        if !seed.lrim.validate().is_repairable() { return false; }
+        if !seed.verify_commitment() { return false; }
         if self.seeds.len() >= self.max_seeds {
-            if let Some(worst_id) = self.seeds.values()
-                .min_by(|a, b| a.fitness.partial_cmp(&b.fitness).unwrap())
-                .map(|s| s.id.clone())
+            if let Some((worst_id, worst_fitness)) = self.seeds.values()
+                .map(|s| (s.id.clone(), s.effective_fitness(now)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
             {
-                if self.seeds[&worst_id].fitness < seed.fitness {
+                if worst_fitness < fitness {
                     self.seeds.remove(&worst_id);
                 } else { return false; }
             }
@@ -94,13 +100,34 @@ impl SeedNetwork {
         true
     }
 
+    /// Quantized copy of a local seed for transmission
+    pub fn export_quantized(&self, seed_id: &str, bits: QuantBits) -> Option<QuantizedSeed> {
+        self.seeds.get(seed_id).map(|s| s.quantize(bits))
+    }
+
+    /// Accept a quantized seed whose commitment matches its payload
+    pub fn accept_quantized(&mut self, seed: QuantizedSeed) -> bool {
+        seed.into_seed().is_some_and(|seed| self.accept_seed(seed))
+    }
+
     pub fn top_seeds(&self, domain: &str, limit: usize) -> Vec<&DnaSeed> {
-        let mut domain_seeds: Vec<&DnaSeed> = self.seeds.values()
+        let now = Utc::now();
+        let mut domain_seeds: Vec<(f64, &DnaSeed)> = self.seeds.values()
             .filter(|s| s.domains.iter().any(|d| d == domain))
+            .map(|s| (s.effective_fitness(now), s))
             .collect();
-        domain_seeds.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap());
+        domain_seeds.sort_by(|a, b| b.0.total_cmp(&a.0));
         domain_seeds.truncate(limit);
-        domain_seeds
+        domain_seeds.into_iter().map(|(_, s)| s).collect()
+    }
+
+    /// Drop seeds whose decayed fitness fell below the threshold; returns how many
+    pub fn prune_stale(&mut self) -> usize {
+        let now = Utc::now();
+        let before = self.seeds.len();
+        let threshold = self.min_fitness_threshold;
+        self.seeds.retain(|_, s| s.effective_fitness(now) >= threshold);
+        before - self.seeds.len()
     }
 
     pub fn stats(&self) -> String {
@@ -110,3 +137,33 @@ impl SeedNetwork {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn test_accept_seed_checks_commitment() {
+        let mut network = SeedNetwork::new(vec!["test".into()]);
+        let seed = DnaSeed::new("shared", &DMatrix::new_random(8, 6), 3, vec!["test".into()]);
+        let wire = serde_json::to_string(&seed).unwrap();
+        let received: DnaSeed = serde_json::from_str(&wire).unwrap();
+        assert!(network.accept_seed(received.clone()));
+
+        // Factors the commitment does not open to are refused
+        let other = DnaSeed::new("other", &DMatrix::new_random(8, 6), 3, vec!["test".into()]);
+        let mut swapped = received.clone();
+        swapped.id = "swapped".into();
+        swapped.lrim = other.lrim.clone();
+        swapped.lrim.sigma *= 100.0;
+        assert!(!network.accept_seed(swapped));
+        let mut stripped = received;
+        stripped.id = "stripped".into();
+        stripped.opening = None;
+        assert!(!network.accept_seed(stripped));
+
+        assert!(network.accept_quantized(seed.quantize(QuantBits::Int8)));
+        assert_eq!(network.seeds.len(), 1);
+    }
+}
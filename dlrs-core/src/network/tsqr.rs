@@ -0,0 +1,161 @@
+//! Distributed tall-skinny SVD over peers holding row shards
+//!
+//! Each peer holds rows A_i of a global matrix A = [A_1; …; A_p] and never
+//! sends them. Instead it publishes a summary S_i = diag(σ_i) · V_iᵀ of its
+//! local truncated SVD (k × n, with S_iᵀS_i ≈ A_iᵀA_i). Every peer stacks the
+//! summaries it receives, in peer-id order, and factorises the stack into
+//! the global Σ and V; all peers therefore learn the same Σ and V. Each
+//! peer then computes its own rows of U as A_i · V · Σ⁻¹ locally.
+//!
+//! With k ≥ rank(A_i) this is exact (a TSQR reduction, since
+//! AᵀA = Σ S_iᵀS_i); with smaller k it is the usual truncated merge.
+//! Summaries reveal each shard's dominant row-space Gram, not its rows.
+
+use crate::seed::LowRankIdentity;
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+/// What a peer broadcasts: its local k × n summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardSummary {
+    pub peer_id: String,
+    /// Rows held by the peer
+    pub rows: usize,
+    /// diag(σ_i) · V_iᵀ
+    pub summary: DMatrix<f64>,
+}
+
+/// Global Σ and V, identical on every peer that combined the same summaries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalBasis {
+    pub sigma: DVector<f64>,
+    pub v: DMatrix<f64>,
+    /// Contributing peers in the order their rows stack
+    pub peers: Vec<(String, usize)>,
+}
+
+impl GlobalBasis {
+    /// Factor the stacked summaries; `None` if there are none or their
+    /// column counts disagree
+    pub fn combine(summaries: &[ShardSummary], target_rank: usize) -> Option<Self> {
+        let n = summaries.first()?.summary.ncols();
+        if summaries.iter().any(|s| s.summary.ncols() != n) { return None; }
+        let mut ordered: Vec<&ShardSummary> = summaries.iter().collect();
+        ordered.sort_by(|a, b| a.peer_id.cmp(&b.peer_id));
+
+        let height = ordered.iter().map(|s| s.summary.nrows()).sum();
+        let mut stacked = DMatrix::zeros(height, n);
+        let mut start = 0;
+        for s in &ordered {
+            stacked.rows_mut(start, s.summary.nrows()).copy_from(&s.summary);
+            start += s.summary.nrows();
+        }
+        let lrim = LowRankIdentity::from_factor_pair(&DMatrix::identity(height, height), &stacked.transpose(), 1e-12);
+        let keep: Vec<usize> = (0..target_rank.min(lrim.rank)).collect();
+        let lrim = lrim.select_components(&keep).canonical();
+        Some(Self {
+            sigma: lrim.sigma,
+            v: lrim.v,
+            peers: ordered.iter().map(|s| (s.peer_id.clone(), s.rows)).collect(),
+        })
+    }
+
+    pub fn rank(&self) -> usize {
+        self.sigma.len()
+    }
+}
+
+/// A peer's private rows
+#[derive(Debug, Clone)]
+pub struct ShardPeer {
+    pub id: String,
+    pub shard: DMatrix<f64>,
+}
+
+impl ShardPeer {
+    pub fn new(id: impl Into<String>, shard: DMatrix<f64>) -> Self {
+        Self { id: id.into(), shard }
+    }
+
+    /// Local truncated SVD summary at rank `k`
+    pub fn summarize(&self, k: usize) -> ShardSummary {
+        // Shards are often rank-deficient; factor through QR rather than a
+        // dense SVD of the shard
+        let n = self.shard.ncols();
+        let local = LowRankIdentity::from_factor_pair(&self.shard, &DMatrix::identity(n, n), 1e-12);
+        let local = local.select_components(&(0..k.min(local.rank)).collect::<Vec<_>>());
+        ShardSummary {
+            peer_id: self.id.clone(),
+            rows: self.shard.nrows(),
+            summary: DMatrix::from_diagonal(&local.sigma) * local.v.transpose(),
+        }
+    }
+
+    /// This peer's rows of the global identity: U_i = A_i · V · Σ⁻¹ with the
+    /// shared Σ and V. `None` if the basis does not match the shard's width.
+    pub fn local_identity(&self, basis: &GlobalBasis) -> Option<LowRankIdentity> {
+        if basis.v.nrows() != self.shard.ncols() { return None; }
+        let inv = basis.sigma.map(|s| if s > 0.0 { 1.0 / s } else { 0.0 });
+        let u = &self.shard * &basis.v * DMatrix::from_diagonal(&inv);
+        Some(LowRankIdentity::new(u, basis.sigma.clone(), basis.v.clone()))
+    }
+}
+
+/// In-process simulation of the protocol: every summary is serialised and
+/// delivered to every peer, and each peer combines independently
+pub struct TsqrHarness {
+    pub peers: Vec<ShardPeer>,
+}
+
+impl TsqrHarness {
+    pub fn new(peers: Vec<ShardPeer>) -> Self {
+        Self { peers }
+    }
+
+    /// Run one round; returns each peer's global basis and local identity
+    pub fn run(&self, summary_rank: usize, target_rank: usize) -> Option<Vec<(GlobalBasis, LowRankIdentity)>> {
+        let wire: Vec<Vec<u8>> = self.peers.iter()
+            .map(|p| serde_json::to_vec(&p.summarize(summary_rank)).expect("summary serialises"))
+            .collect();
+        self.peers.iter()
+            .map(|peer| {
+                let received: Vec<ShardSummary> = wire.iter()
+                    .map(|bytes| serde_json::from_slice(bytes).ok())
+                    .collect::<Option<_>>()?;
+                let basis = GlobalBasis::combine(&received, target_rank)?;
+                let local = peer.local_identity(&basis)?;
+                Some((basis, local))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distributed_tsqr() {
+        let k = DMatrix::<f64>::new_random(90, 5) * DMatrix::<f64>::new_random(5, 40);
+        let shards = [(0, 25), (25, 40), (65, 25)];
+        let peers = shards.iter().enumerate()
+            .map(|(i, &(start, len))| ShardPeer::new(format!("team-{}", 2 - i), k.rows(start, len).into_owned()))
+            .collect();
+        let results = TsqrHarness::new(peers).run(5, 5).unwrap();
+
+        let (first, _) = &results[0];
+        for (basis, _) in &results {
+            assert_eq!(basis.v, first.v);
+            assert_eq!(basis.peers, first.peers);
+        }
+        // Stacking every peer's private U rows gives an orthonormal U that
+        // recovers the global matrix
+        let mut gram_u = DMatrix::zeros(5, 5);
+        for ((start, len), (_, local)) in shards.iter().zip(&results) {
+            let rows = k.rows(*start, *len);
+            assert!((local.reconstruct() - rows).norm() < 1e-9 * k.norm());
+            gram_u += local.u.transpose() * &local.u;
+        }
+        assert!((gram_u - DMatrix::identity(5, 5)).amax() < 1e-9);
+    }
+}
@@ -0,0 +1,164 @@
+//! Distillation — shrink a seed to a lower rank without losing named capabilities
+//!
+//! Plain truncation keeps the strongest components, but a weak component can
+//! carry most of a seed's capability in one domain. Distillation instead picks
+//! components greedily so that `capability_in_domain` on a set of reference
+//! domain vectors moves as little as possible.
+
+use super::{DnaSeed, LowRankIdentity};
+use chrono::Utc;
+use nalgebra::DVector;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// One point on the rank / capability trade-off curve
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistillationPoint {
+    pub rank: usize,
+    /// Worst relative capability deviation over the reference domains
+    pub max_deviation: f64,
+    /// Fraction of ‖Σ‖² kept at this rank
+    pub energy_retained: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistillationReport {
+    pub from_rank: usize,
+    pub to_rank: usize,
+    /// Components of the parent kept in the child, in selection order
+    pub kept_components: Vec<usize>,
+    pub max_deviation: f64,
+    pub tolerance: f64,
+    /// Trade-off curve for every rank from 1 to the parent's rank
+    pub curve: Vec<DistillationPoint>,
+}
+
+impl LowRankIdentity {
+    /// Greedy component order that best preserves capability in `domains`,
+    /// with the trade-off curve for each prefix of that order
+    pub fn distillation_order(
+        &self,
+        domains: &[DVector<f64>],
+    ) -> (Vec<usize>, Vec<DistillationPoint>) {
+        // terms[k][i] = σᵢ · (vᵢ · d_k), so capability_k = |Σᵢ terms[k][i]|
+        let terms: Vec<Vec<f64>> = domains.iter()
+            .map(|d| {
+                let projection = self.v.transpose() * d;
+                projection.iter().zip(self.sigma.iter()).map(|(p, s)| p * s).collect()
+            })
+            .collect();
+        let full: Vec<f64> = terms.iter().map(|t| t.iter().sum::<f64>().abs()).collect();
+        let total_energy: f64 = self.sigma.iter().map(|s| s * s).sum();
+
+        let mut partial = vec![0.0; domains.len()];
+        let mut remaining: Vec<usize> = (0..self.rank).collect();
+        let mut order = Vec::with_capacity(self.rank);
+        let mut curve = Vec::with_capacity(self.rank);
+        let mut kept_energy = 0.0;
+
+        let deviation = |k: usize, value: f64| (value.abs() - full[k]).abs() / full[k].max(1e-12);
+
+        while !remaining.is_empty() {
+            let (slot, dev) = remaining.iter().enumerate()
+                .map(|(slot, &i)| {
+                    let dev = (0..domains.len())
+                        .map(|k| deviation(k, partial[k] + terms[k][i]))
+                        .fold(0.0, f64::max);
+                    (slot, dev)
+                })
+                .min_by(|(sa, da), (sb, db)| {
+                    da.total_cmp(db)
+                        .then(self.sigma[remaining[*sb]].total_cmp(&self.sigma[remaining[*sa]]))
+                })
+                .expect("remaining is non-empty");
+            let chosen = remaining.remove(slot);
+            for (k, p) in partial.iter_mut().enumerate() {
+                *p += terms[k][chosen];
+            }
+            kept_energy += self.sigma[chosen] * self.sigma[chosen];
+            order.push(chosen);
+            curve.push(DistillationPoint {
+                rank: order.len(),
+                max_deviation: dev,
+                energy_retained: if total_energy > 0.0 { kept_energy / total_energy } else { 1.0 },
+            });
+        }
+        (order, curve)
+    }
+}
+
+impl DnaSeed {
+    /// Distill into a child of `target_rank` whose capability on every domain
+    /// vector stays within `tolerance` (relative) of the parent's.
+    ///
+    /// Returns `None` if `target_rank` is not below the current rank or the
+    /// tolerance cannot be met at that rank.
+    pub fn distill(
+        &self,
+        target_rank: usize,
+        domains: &[DVector<f64>],
+        tolerance: f64,
+    ) -> Option<(DnaSeed, DistillationReport)> {
+        if target_rank == 0 || target_rank >= self.lrim.rank { return None; }
+        let (order, curve) = self.lrim.distillation_order(domains);
+        let max_deviation = curve[target_rank - 1].max_deviation;
+        if max_deviation > tolerance { return None; }
+
+        let kept_components = order[..target_rank].to_vec();
+        let mut child = self.clone();
+        child.id = Uuid::new_v4().to_string();
+        child.name = format!("{}_r{}", self.name, target_rank);
+        child.lrim = self.lrim.select_components(&kept_components);
+        child.recommit();
+        child.lineage.record_distillation(
+            self.epoch, &self.id, self.lrim.rank, target_rank, max_deviation,
+        );
+        child.epoch = 0;
+        child.created_at = Utc::now();
+        child.mutated_at = None;
+
+        let report = DistillationReport {
+            from_rank: self.lrim.rank,
+            to_rank: target_rank,
+            kept_components,
+            max_deviation,
+            tolerance,
+            curve,
+        };
+        Some((child, report))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed::lineage::LineageEventType;
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn test_distill_keeps_weak_domain() {
+        let k = DMatrix::new_random(20, 12);
+        let seed = DnaSeed::new("wide", &k, 5, vec!["test".into()]);
+        // A domain carried entirely by the weakest component: truncation loses it
+        let weak: DVector<f64> = seed.lrim.v.column(4).into_owned();
+        let strong: DVector<f64> = seed.lrim.v.column(0).into_owned();
+        let domains = vec![weak.clone(), strong.clone()];
+
+        let (child, report) = seed.distill(2, &domains, 1e-6).expect("should distill");
+        assert_eq!(child.lrim.rank, 2);
+        assert_eq!(report.curve.len(), 5);
+        for d in &domains {
+            let before = seed.lrim.capability_in_domain(d);
+            let after = child.lrim.capability_in_domain(d);
+            assert!((before - after).abs() <= 1e-6 * before);
+        }
+        assert!(matches!(
+            child.lineage.events.last().map(|e| &e.event_type),
+            Some(LineageEventType::Distillation { to_rank: 2, .. })
+        ));
+
+        let truncated = seed.lrim.select_components(&[0, 1]);
+        assert!(truncated.capability_in_domain(&weak) < 1e-9);
+        assert!(seed.distill(1, &domains, 1e-6).is_none());
+    }
+}
@@ -3,8 +3,9 @@
 //! A seed = compressed knowledge + program + proof + lineage.
 //! It is simultaneously data, code, and verification.
 
-use super::{LowRankIdentity, MutationRules, ReplicationPolicy, Lineage};
-use crate::zk::ZkCommitment;
+use super::{LowRankIdentity, LrimScalar, MutationRules, ReplicationPolicy, Lineage, MergeRule, DriftMonitor, TensorLayout};
+use super::lineage::LineageEventType;
+use crate::zk::{ColumnOpening, CommittedVector, PedersenOpening, ZkCommitment};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -23,21 +24,39 @@ pub enum Instruction {
 }
 
 /// The DNA Seed — minimal self-contained unit of DLRS
+///
+/// Evolution, replication and training need f64 factors; seeds stored in
+/// f32 or `Fixed` can be expressed, committed and converted.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct DnaSeed {
+#[serde(bound = "T: LrimScalar")]
+pub struct DnaSeed<T: LrimScalar = f64> {
     pub id: String,
     pub name: String,
-    pub lrim: LowRankIdentity,
+    pub lrim: LowRankIdentity<T>,
     pub express: Vec<Instruction>,
     pub mutation: MutationRules,
     pub replication: ReplicationPolicy,
     pub commitment: ZkCommitment,
+    /// Blindings of `commitment`. Sent with the seed: the factors travel
+    /// in the clear, so peers need it to check them against the commitment.
+    #[serde(default)]
+    pub opening: Option<PedersenOpening>,
     pub lineage: Lineage,
     pub epoch: u64,
     pub fitness: f64,
     pub domains: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub mutated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub drift: Option<DriftMonitor>,
+    /// Layout of three-way knowledge; `lrim` then holds its matricized
+    /// shadow, from which the tensor payload is refitted
+    #[serde(default)]
+    pub tensor: Option<TensorLayout>,
+    /// The hash binding the seed arrived under as a `QuantizedSeed`, kept
+    /// across local re-commits so the seed still matches what was sent
+    #[serde(default)]
+    pub received_commitment: Option<ZkCommitment>,
 }
 
 impl DnaSeed {
@@ -47,25 +66,27 @@ impl DnaSeed {
         rank: usize,
         domains: Vec<String>,
     ) -> Self {
-        let lrim = LowRankIdentity::from_matrix(knowledge, rank);
-        let commitment = ZkCommitment::from_lrim(&lrim);
+        Self::from_lrim(name, LowRankIdentity::from_matrix(knowledge, rank), domains)
+    }
+
+    /// Genesis seed around an existing factorization
+    pub fn from_lrim(name: impl Into<String>, lrim: LowRankIdentity, domains: Vec<String>) -> Self {
+        let (commitment, opening) = ZkCommitment::commit(&lrim);
         Self {
             id: Uuid::new_v4().to_string(),
             name: name.into(),
             lrim, express: Vec::new(),
             mutation: MutationRules::default(),
             replication: ReplicationPolicy::default(),
-            commitment, lineage: Lineage::genesis(),
+            commitment, opening: Some(opening), lineage: Lineage::genesis(),
             epoch: 0, fitness: 0.5,
             domains, created_at: Utc::now(), mutated_at: None,
+            drift: None,
+            tensor: None,
+            received_commitment: None,
         }
     }
 
-    pub fn express_on(&self, input: &nalgebra::DVector<f64>) -> nalgebra::DVector<f64> {
-        let k = self.lrim.reconstruct();
-        &k * input
-    }
-
     pub fn evolve(&mut self, feedback: &nalgebra::DMatrix<f64>, learning_rate: f64) {
         if !self.mutation.can_mutate(self.fitness) { return; }
         let current = self.lrim.reconstruct();
@@ -90,27 +111,49 @@ impl DnaSeed {
         }
         let new_error = self.lrim.reconstruction_error(feedback);
         let old_error = (feedback - current).norm();
+        self.record_epoch(old_error, new_error);
+    }
+
+    /// Bookkeeping shared by every evolution path: adjust fitness by whether
+    /// the error improved, re-commit, append the mutation to the lineage and
+    /// check for drift
+    pub(crate) fn record_epoch(&mut self, old_error: f64, new_error: f64) {
         if new_error < old_error {
             self.fitness = (self.fitness + 0.01).min(1.0);
         } else {
             self.fitness = (self.fitness - 0.005).max(0.0);
         }
-        self.commitment = ZkCommitment::from_lrim(&self.lrim);
+        self.recommit();
         self.mutated_at = Some(Utc::now());
         self.epoch += 1;
         self.lineage.record_mutation(self.epoch, new_error);
+        self.check_drift();
     }
 
-    pub fn replicate(&self) -> Option<DnaSeed> {
+    /// Produce a child if the replication policy allows it.
+    ///
+    /// Counts the child against `max_children` and records the event in both
+    /// lineages. With `mutate`, the child's factors are perturbed according to
+    /// the parent's `MutationRules`.
+    pub fn replicate(&mut self, mutate: bool) -> Option<DnaSeed> {
         if !self.replication.should_replicate(self.fitness, self.epoch) { return None; }
+        let child_id = Uuid::new_v4().to_string();
         let mut child = self.clone();
-        child.id = Uuid::new_v4().to_string();
+        child.id = child_id.clone();
         child.name = format!("{}_gen{}", self.name, self.epoch + 1);
-        child.lineage = self.lineage.spawn_child(&self.id);
+        child.lineage = self.lineage.spawn_child(&self.id, &child_id);
+        child.replication.children_produced = 0;
         child.epoch = 0;
-        child.fitness = self.fitness * 0.9;
+        child.fitness = self.fitness * self.replication.inheritance.discount;
         child.created_at = Utc::now();
         child.mutated_at = None;
+        if mutate {
+            child.lrim = self.mutation.perturb(&child.lrim);
+            child.recommit();
+        }
+
+        self.replication.children_produced += 1;
+        self.lineage.record_replication(self.epoch, &self.id, &child_id);
         Some(child)
     }
 
@@ -125,16 +168,108 @@ impl DnaSeed {
             name: format!("{}⊕{}", self.name, other.name),
             lrim: merged_lrim, express: Vec::new(),
             mutation: MutationRules::default(),
-            replication: ReplicationPolicy::default(),
+            replication: ReplicationPolicy {
+                inheritance: self.replication.inheritance.clone(),
+                decay: self.replication.decay.clone(),
+                ..Default::default()
+            },
             commitment: ZkCommitment::default(),
+            opening: None,
             lineage: Lineage::merge_lineages(&self.lineage, &other.lineage),
-            epoch: 0, fitness: (self.fitness + other.fitness) / 2.0,
+            epoch: 0, fitness: self.merged_fitness(other),
             domains, created_at: Utc::now(), mutated_at: None,
+            drift: None,
+            tensor: None,
+            received_commitment: None,
+        };
+        seed.recommit();
+        seed
+    }
+
+    fn merged_fitness(&self, other: &DnaSeed) -> f64 {
+        let inheritance = &self.replication.inheritance;
+        let weight = |s: &DnaSeed| match inheritance.merge_rule {
+            MergeRule::Average => 1.0,
+            MergeRule::RankWeighted => s.lrim.rank as f64,
+            MergeRule::EnergyWeighted => s.lrim.energy(),
+        };
+        inheritance.merge_fitness((self.fitness, weight(self)), (other.fitness, weight(other)))
+    }
+}
+
+impl<T: LrimScalar> DnaSeed<T> {
+    pub fn express_on(&self, input: &nalgebra::DVector<T>) -> nalgebra::DVector<T> {
+        let k = self.lrim.reconstruct();
+        &k * input
+    }
+
+    /// Refresh the commitment after the factors change
+    pub fn recommit(&mut self) {
+        let (commitment, opening) = ZkCommitment::commit(&self.lrim);
+        self.commitment = commitment;
+        self.opening = Some(opening);
+    }
+
+    /// `recommit`, recording the replaced and new commitment in the lineage
+    pub fn recommit_recorded(&mut self, reason: &str) {
+        let previous = self.commitment.matrix_hash.clone();
+        self.recommit();
+        self.lineage.record_recommit(self.epoch, reason, &previous, &self.commitment.matrix_hash);
+    }
+
+    /// The commitment opens to the current factors
+    pub fn verify_commitment(&self) -> bool {
+        self.opening.as_ref().is_some_and(|o| self.commitment.verify_opening(&self.lrim, o))
+    }
+
+    /// Reveal one committed column (or Σ) without the rest of the factors
+    pub fn open_column(&self, vector: CommittedVector) -> Option<ColumnOpening> {
+        self.opening.as_ref()?.open(&self.lrim, vector)
+    }
+
+    /// The same seed with its factors stored as `S` (see
+    /// `LowRankIdentity::cast`). The commitment is refreshed only if the
+    /// converted factors no longer open it.
+    pub fn cast<S: LrimScalar>(&self) -> DnaSeed<S> {
+        let mut seed = DnaSeed {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            lrim: self.lrim.cast(),
+            express: self.express.clone(),
+            mutation: self.mutation.clone(),
+            replication: self.replication.clone(),
+            commitment: self.commitment.clone(),
+            opening: self.opening.clone(),
+            lineage: self.lineage.clone(),
+            epoch: self.epoch,
+            fitness: self.fitness,
+            domains: self.domains.clone(),
+            created_at: self.created_at,
+            mutated_at: self.mutated_at,
+            drift: self.drift.clone(),
+            tensor: self.tensor.clone(),
+            received_commitment: self.received_commitment.clone(),
         };
-        seed.commitment = ZkCommitment::from_lrim(&seed.lrim);
+        if !seed.verify_commitment() { seed.recommit(); }
         seed
     }
 
+    /// Fitness as seen by ranking and gossip at time `now`: stored fitness
+    /// after time decay and, for replicated or merged seeds, probation
+    pub fn effective_fitness(&self, now: DateTime<Utc>) -> f64 {
+        let last_active = self.mutated_at.unwrap_or(self.created_at);
+        let mut fitness = self.fitness * self.replication.decay.factor(last_active, now);
+        let inheritance = &self.replication.inheritance;
+        let derived = matches!(
+            self.lineage.events.first().map(|e| &e.event_type),
+            Some(LineageEventType::Replication { .. } | LineageEventType::Merge { .. })
+        );
+        if derived && self.epoch < inheritance.probation_epochs {
+            fitness *= inheritance.probation_factor;
+        }
+        fitness
+    }
+
     pub fn summary(&self) -> String {
         format!(
             "DnaSeed '{}' | rank={} | dims={}x{} | fitness={:.3} | epoch={} | domains={:?} | compression={:.1}x",
@@ -147,6 +282,7 @@ impl DnaSeed {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::seed::{FitnessDecay, FitnessInheritance};
     use nalgebra::DMatrix;
 
     #[test]
@@ -168,4 +304,64 @@ mod tests {
         println!("Fitness: {:.3} -> {:.3}, Epoch: {}", initial_fitness, seed.fitness, seed.epoch);
         assert_eq!(seed.epoch, 10);
     }
+
+    #[test]
+    fn test_replicate_tracks_children() {
+        let k = DMatrix::new_random(12, 10);
+        let mut parent = DnaSeed::new("parent", &k, 3, vec!["test".into()]);
+        parent.fitness = 0.9;
+        parent.epoch = parent.replication.min_epochs;
+
+        let children: Vec<DnaSeed> = (0..parent.replication.max_children)
+            .map(|_| parent.replicate(true).expect("within max_children"))
+            .collect();
+        assert!(parent.replicate(false).is_none());
+        assert_eq!(parent.replication.children_produced, children.len());
+
+        let child = &children[0];
+        assert_eq!(child.replication.children_produced, 0);
+        assert_eq!(child.lrim.rank, 3);
+        assert!(child.verify_commitment());
+        match &child.lineage.events[0].event_type {
+            LineageEventType::Replication { parent_id, child_id } => {
+                assert_eq!(parent_id, &parent.id);
+                assert_eq!(child_id, &child.id);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_fitness_decay_and_probation() {
+        let k = DMatrix::new_random(10, 8);
+        let mut parent = DnaSeed::new("stale", &k, 2, vec!["test".into()]);
+        parent.replication = ReplicationPolicy {
+            inheritance: FitnessInheritance {
+                discount: 0.8,
+                probation_epochs: 3,
+                probation_factor: 0.5,
+                merge_rule: MergeRule::EnergyWeighted,
+            },
+            decay: FitnessDecay::new(24.0 * 7.0, 0.1).unwrap(),
+            ..ReplicationPolicy::viral()
+        };
+        parent.fitness = 0.8;
+        parent.epoch = parent.replication.min_epochs;
+
+        let week_later = parent.created_at + chrono::Duration::hours(24 * 7);
+        assert!((parent.effective_fitness(week_later) - 0.4).abs() < 1e-9);
+
+        let child = parent.replicate(false).unwrap();
+        assert!((child.fitness - 0.64).abs() < 1e-9);
+        assert!((child.effective_fitness(child.created_at) - 0.32).abs() < 1e-9);
+
+        // Half-lives that would grow or poison fitness are refused, built or loaded
+        for hours in [-24.0, 0.0, f64::NAN, f64::INFINITY] {
+            assert!(FitnessDecay::new(hours, 0.1).is_none());
+        }
+        assert!(FitnessDecay::new(24.0, 1.5).is_none());
+        let mut saved = serde_json::to_value(&parent.replication).unwrap();
+        saved["decay"]["half_life_hours"] = serde_json::json!(-24.0);
+        assert!(serde_json::from_value::<ReplicationPolicy>(saved).is_err());
+    }
 }
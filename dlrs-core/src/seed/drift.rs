@@ -0,0 +1,192 @@
+//! Concept drift — how far a seed has wandered from a reference subspace
+//!
+//! A seed advertises its `domains` based on what it knew when they were set.
+//! After many epochs its V subspace can rotate away from those domains while
+//! fitness still looks fine. A `DriftMonitor` keeps a snapshot of the
+//! reference subspaces (genesis or the last certified state) and compares
+//! every epoch against it.
+
+use super::{DnaSeed, LowRankIdentity};
+use chrono::{DateTime, Utc};
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum DriftLevel {
+    Stable,
+    Warning,
+    Critical,
+}
+
+/// Orthonormal bases of a seed's U and V subspaces at some epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubspaceSnapshot {
+    pub epoch: u64,
+    pub u_basis: DMatrix<f64>,
+    pub v_basis: DMatrix<f64>,
+    pub sigma: DVector<f64>,
+    pub taken_at: DateTime<Utc>,
+}
+
+impl SubspaceSnapshot {
+    pub fn of(lrim: &LowRankIdentity, epoch: u64) -> Self {
+        Self {
+            epoch,
+            u_basis: lrim.u.clone().qr().q(),
+            v_basis: lrim.v.clone().qr().q(),
+            sigma: lrim.sigma.clone(),
+            taken_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftMetrics {
+    /// Normalised chordal distance between reference and current V subspaces, in [0, 1]
+    pub v_distance: f64,
+    /// Same for U subspaces
+    pub u_distance: f64,
+    /// ‖σ − σ_ref‖ / ‖σ_ref‖
+    pub spectrum_shift: f64,
+}
+
+impl DriftMetrics {
+    /// Domain drift drives the level: the V subspace is what `domains` describes
+    pub fn level(&self, thresholds: &DriftThresholds) -> DriftLevel {
+        if self.v_distance >= thresholds.critical {
+            DriftLevel::Critical
+        } else if self.v_distance >= thresholds.warning {
+            DriftLevel::Warning
+        } else {
+            DriftLevel::Stable
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftThresholds {
+    pub warning: f64,
+    pub critical: f64,
+    /// Set `MutationRules::frozen` when drift turns critical
+    pub freeze_on_critical: bool,
+}
+
+impl Default for DriftThresholds {
+    fn default() -> Self {
+        Self { warning: 0.3, critical: 0.6, freeze_on_critical: false }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftMonitor {
+    pub reference: SubspaceSnapshot,
+    pub thresholds: DriftThresholds,
+    pub last: Option<DriftMetrics>,
+    /// Highest level already raised since the reference was taken; events
+    /// are only raised when drift escalates past it
+    pub raised: DriftLevel,
+}
+
+impl DriftMonitor {
+    pub fn new(lrim: &LowRankIdentity, epoch: u64, thresholds: DriftThresholds) -> Self {
+        Self {
+            reference: SubspaceSnapshot::of(lrim, epoch),
+            thresholds,
+            last: None,
+            raised: DriftLevel::Stable,
+        }
+    }
+
+    /// Accept the current state as the new reference
+    pub fn certify(&mut self, lrim: &LowRankIdentity, epoch: u64) {
+        self.reference = SubspaceSnapshot::of(lrim, epoch);
+        self.last = None;
+        self.raised = DriftLevel::Stable;
+    }
+
+    pub fn measure(&self, lrim: &LowRankIdentity) -> DriftMetrics {
+        let current = SubspaceSnapshot::of(lrim, 0);
+        let r = &self.reference;
+        let sigma_ref = r.sigma.norm();
+        let len = r.sigma.len().max(current.sigma.len());
+        let pad = |s: &DVector<f64>| DVector::from_fn(len, |i, _| s.get(i).copied().unwrap_or(0.0));
+        DriftMetrics {
+            v_distance: chordal_distance(&r.v_basis, &current.v_basis),
+            u_distance: chordal_distance(&r.u_basis, &current.u_basis),
+            spectrum_shift: if sigma_ref > 0.0 {
+                (pad(&current.sigma) - pad(&r.sigma)).norm() / sigma_ref
+            } else { 0.0 },
+        }
+    }
+}
+
+/// √(k − Σ cos²θᵢ) / √k over the principal angles θᵢ between two subspaces
+/// with orthonormal bases, where k is the larger dimension
+fn chordal_distance(a: &DMatrix<f64>, b: &DMatrix<f64>) -> f64 {
+    let k = a.ncols().max(b.ncols());
+    if k == 0 { return 0.0; }
+    let overlap = (a.transpose() * b).norm_squared();
+    ((k as f64 - overlap).max(0.0) / k as f64).sqrt()
+}
+
+impl DnaSeed {
+    /// Start monitoring drift against the current state
+    pub fn monitor_drift(&mut self, thresholds: DriftThresholds) {
+        self.drift = Some(DriftMonitor::new(&self.lrim, self.epoch, thresholds));
+    }
+
+    /// Accept the current state as the drift reference
+    pub fn certify_drift_reference(&mut self) {
+        if let Some(monitor) = self.drift.as_mut() {
+            monitor.certify(&self.lrim, self.epoch);
+        }
+    }
+
+    /// Compare against the reference; raises a lineage event when drift
+    /// escalates past a threshold and freezes the seed if configured to.
+    /// Called after every evolution epoch; `None` when unmonitored.
+    pub fn check_drift(&mut self) -> Option<DriftLevel> {
+        let monitor = self.drift.as_mut()?;
+        let metrics = monitor.measure(&self.lrim);
+        let level = metrics.level(&monitor.thresholds);
+        if level > monitor.raised {
+            monitor.raised = level;
+            self.lineage.record_drift(self.epoch, level, &metrics);
+            if level == DriftLevel::Critical && monitor.thresholds.freeze_on_critical {
+                self.mutation.frozen = true;
+            }
+        }
+        monitor.last = Some(metrics);
+        Some(level)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed::lineage::LineageEventType;
+
+    #[test]
+    fn test_drift_raises_and_freezes() {
+        let k = DMatrix::new_random(10, 8);
+        let mut seed = DnaSeed::new("drifter", &k, 2, vec!["test".into()]);
+        seed.monitor_drift(DriftThresholds { freeze_on_critical: true, ..Default::default() });
+        assert_eq!(seed.check_drift(), Some(DriftLevel::Stable));
+
+        // Swap in the next two singular directions: orthogonal to the original domains
+        let rest = &k - seed.lrim.reconstruct();
+        seed.lrim = LowRankIdentity::from_matrix(&rest, 2);
+        assert_eq!(seed.check_drift(), Some(DriftLevel::Critical));
+        assert!(seed.mutation.frozen);
+        let drift_events = seed.lineage.events.iter()
+            .filter(|e| matches!(e.event_type, LineageEventType::Drift { .. }))
+            .count();
+        assert_eq!(drift_events, 1);
+
+        // Already raised: no duplicate event until re-certified
+        seed.check_drift();
+        assert_eq!(seed.lineage.events.len(), 2);
+        seed.certify_drift_reference();
+        assert_eq!(seed.check_drift(), Some(DriftLevel::Stable));
+    }
+}
@@ -0,0 +1,138 @@
+//! Canonical byte encoding of a LowRankIdentity
+//!
+//! The same matrix has many factorizations: SVD routines pick column signs
+//! freely and −0.0 and 0.0 differ in their bits. Fingerprints and
+//! commitments are therefore computed over a canonical form, encoded as
+//! follows (integers little-endian):
+//!
+//! ```text
+//! tag    13 bytes  "DLRS/LRIM/v1" followed by a 0 byte (domain separator)
+//! m      u64
+//! n      u64
+//! r      u64
+//! frac   u8        fractional bits F of the fixed-point values
+//! σ      r   × i64 round(σᵢ · 2^F)
+//! U      m·r × i64 column-major, round(uₖᵢ · 2^F)
+//! V      n·r × i64 column-major, round(vₖᵢ · 2^F)
+//! ```
+//!
+//! Canonicalisation before encoding:
+//! 1. every negative σᵢ is made positive by negating uᵢ;
+//! 2. components are sorted by descending σ (stable, so ties keep their order);
+//! 3. each pair (uᵢ, vᵢ) is negated if needed so that the largest-magnitude
+//!    fixed-point entry of uᵢ (the first one on ties) is positive; if uᵢ
+//!    rounds to all zeros, vᵢ decides instead.
+//!
+//! Guarantee: identical f64 factors, up to the sign of each (uᵢ, vᵢ) pair
+//! and the sign of zero, always give identical bytes. Nothing stronger
+//! holds. Factors that differ by rounding noise usually encode equally, but
+//! not when a value straddles a rounding boundary of 2^-F. Tied or nearly
+//! tied σ can sort, and near-tied entries of uᵢ can pick signs, differently
+//! for noisy copies of the same seed; components with equal σ span a
+//! subspace in which any rotation is valid and are not canonicalised. Values
+//! beyond ±2^(63-F) saturate.
+
+use super::LowRankIdentity;
+use nalgebra::DMatrix;
+
+/// Domain-separation tag opening every canonical LRIM encoding
+pub const LRIM_ENCODING_TAG: &[u8; 13] = b"DLRS/LRIM/v1\0";
+/// Fractional bits of the fixed-point values in the canonical encoding
+pub const FIXED_POINT_FRAC_BITS: u32 = 32;
+
+/// round(x · 2^F) with saturation; −0.0 becomes 0
+pub fn to_fixed(x: f64, frac_bits: u32) -> i64 {
+    (x * (frac_bits as f64).exp2()).round() as i64
+}
+
+pub fn from_fixed(x: i64, frac_bits: u32) -> f64 {
+    x as f64 / (frac_bits as f64).exp2()
+}
+
+impl LowRankIdentity {
+    /// The canonical factorization: σ non-negative and descending, each
+    /// component's sign fixed as described in the module docs
+    pub fn canonical(&self) -> Self {
+        let mut u = self.u.clone();
+        let mut sigma = self.sigma.clone();
+        for i in 0..self.rank {
+            if sigma[i] < 0.0 {
+                sigma[i] = -sigma[i];
+                u.column_mut(i).neg_mut();
+            }
+        }
+        let mut order: Vec<usize> = (0..self.rank).collect();
+        order.sort_by(|&a, &b| sigma[b].total_cmp(&sigma[a]));
+        let mut u = u.select_columns(order.iter());
+        let sigma = sigma.select_rows(order.iter());
+        let mut v = self.v.select_columns(order.iter());
+
+        for i in 0..self.rank {
+            let sign = pivot_sign(&u, i).or_else(|| pivot_sign(&v, i)).unwrap_or(1);
+            if sign < 0 {
+                u.column_mut(i).neg_mut();
+                v.column_mut(i).neg_mut();
+            }
+        }
+        Self::new(u, sigma, v)
+    }
+
+    /// Canonical byte encoding (see module docs for the layout)
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let c = self.canonical();
+        let values = c.rank * (1 + c.m + c.n);
+        let mut out = Vec::with_capacity(LRIM_ENCODING_TAG.len() + 25 + 8 * values);
+        out.extend_from_slice(LRIM_ENCODING_TAG);
+        for dim in [c.m, c.n, c.rank] {
+            out.extend_from_slice(&(dim as u64).to_le_bytes());
+        }
+        out.push(FIXED_POINT_FRAC_BITS as u8);
+        for x in c.sigma.iter().chain(c.u.iter()).chain(c.v.iter()) {
+            out.extend_from_slice(&to_fixed(*x, FIXED_POINT_FRAC_BITS).to_le_bytes());
+        }
+        out
+    }
+}
+
+/// Sign of the largest-magnitude fixed-point entry of column `i`, if any is non-zero
+fn pivot_sign(factor: &DMatrix<f64>, i: usize) -> Option<i64> {
+    let mut best = 0i64;
+    for x in factor.column(i).iter() {
+        let q = to_fixed(*x, FIXED_POINT_FRAC_BITS);
+        if q.unsigned_abs() > best.unsigned_abs() { best = q; }
+    }
+    (best != 0).then(|| best.signum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_ignores_sign_and_order() {
+        let k = DMatrix::new_random(9, 6);
+        let lrim = LowRankIdentity::from_matrix(&k, 3);
+
+        let mut flipped = lrim.clone();
+        flipped.u.column_mut(1).neg_mut();
+        flipped.v.column_mut(1).neg_mut();
+        flipped.sigma.swap_rows(0, 2);
+        flipped.u.swap_columns(0, 2);
+        flipped.v.swap_columns(0, 2);
+        flipped.sigma[0] = -flipped.sigma[0];
+        flipped.u.column_mut(0).neg_mut();
+        flipped.v[(0, 0)] += 1e-14;
+        assert_eq!(lrim.fingerprint(), flipped.fingerprint());
+
+        let mut zeroed = lrim.clone();
+        zeroed.v[(1, 1)] = 0.0;
+        let mut negative_zero = zeroed.clone();
+        negative_zero.v[(1, 1)] = -0.0;
+        assert_eq!(zeroed.fingerprint(), negative_zero.fingerprint());
+
+        let mut changed = lrim.clone();
+        changed.sigma[0] *= 1.001;
+        assert_ne!(lrim.fingerprint(), changed.fingerprint());
+        assert!(lrim.canonical_bytes().starts_with(LRIM_ENCODING_TAG));
+    }
+}
@@ -0,0 +1,206 @@
+//! Numerical health of a LowRankIdentity
+//!
+//! Evolution can leave factors non-finite, σ negative or unsorted, or U and V
+//! far from orthonormal. `validate` reports such issues; `repair` rebuilds a
+//! canonical factorization of the same matrix. Non-finite values and
+//! inconsistent shapes are fatal: no repair can recover the matrix.
+
+use super::{FactorSpace, LowRankIdentity, LrimScalar};
+use nalgebra::{DMatrix, DVector};
+use serde::Deserialize;
+use std::fmt;
+
+/// Largest tolerated |XᵀX − I| entry for a factor to count as orthonormal
+pub const ORTHOGONALITY_TOLERANCE: f64 = 1e-6;
+/// Largest tolerated σ_max / σ_min
+pub const MAX_CONDITION_NUMBER: f64 = 1e12;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthIssue {
+    /// Factor shapes disagree with each other or with the stored rank / dims
+    ShapeMismatch(String),
+    NonFinite { factor: &'static str, count: usize },
+    NegativeSigma { index: usize, value: f64 },
+    UnsortedSigma { index: usize },
+    OrthogonalityDefect { space: FactorSpace, defect: f64 },
+    IllConditioned { condition: f64 },
+}
+
+impl HealthIssue {
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, HealthIssue::ShapeMismatch(_) | HealthIssue::NonFinite { .. })
+    }
+}
+
+impl fmt::Display for HealthIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthIssue::ShapeMismatch(what) => write!(f, "shape mismatch: {what}"),
+            HealthIssue::NonFinite { factor, count } => write!(f, "{count} non-finite values in {factor}"),
+            HealthIssue::NegativeSigma { index, value } => write!(f, "σ[{index}] = {value} is negative"),
+            HealthIssue::UnsortedSigma { index } => write!(f, "σ[{index}] exceeds σ[{}]", index - 1),
+            HealthIssue::OrthogonalityDefect { space, defect } => {
+                write!(f, "{space:?} orthogonality defect {defect:.3e}")
+            }
+            HealthIssue::IllConditioned { condition } => write!(f, "condition number {condition:.3e}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub issues: Vec<HealthIssue>,
+    /// max |UᵀU − I| (NaN if not computable)
+    pub u_defect: f64,
+    /// max |VᵀV − I| (NaN if not computable)
+    pub v_defect: f64,
+    /// σ_max / σ_min (infinite if some σ is zero)
+    pub condition: f64,
+}
+
+impl HealthReport {
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// No fatal issues: `repair` will succeed
+    pub fn is_repairable(&self) -> bool {
+        !self.issues.iter().any(HealthIssue::is_fatal)
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.issues.is_empty() { return write!(f, "healthy"); }
+        let issues: Vec<String> = self.issues.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", issues.join("; "))
+    }
+}
+
+impl std::error::Error for HealthReport {}
+
+fn orthogonality_defect(x: &DMatrix<f64>) -> f64 {
+    (x.transpose() * x - DMatrix::identity(x.ncols(), x.ncols())).amax()
+}
+
+impl<T: LrimScalar> LowRankIdentity<T> {
+    /// Checks run in f64, whatever the scalar the factors are stored in
+    pub fn validate(&self) -> HealthReport {
+        let lrim = self.to_f64();
+        let mut issues = Vec::new();
+        let r = lrim.sigma.len();
+        if lrim.u.ncols() != r || lrim.v.ncols() != r || lrim.rank != r
+            || lrim.u.nrows() != lrim.m || lrim.v.nrows() != lrim.n
+        {
+            issues.push(HealthIssue::ShapeMismatch(format!(
+                "U {}x{}, σ {}, V {}x{}, stored rank {} dims {}x{}",
+                lrim.u.nrows(), lrim.u.ncols(), r, lrim.v.nrows(), lrim.v.ncols(),
+                lrim.rank, lrim.m, lrim.n,
+            )));
+            return HealthReport { issues, u_defect: f64::NAN, v_defect: f64::NAN, condition: f64::NAN };
+        }
+
+        let mut finite = true;
+        for (factor, values) in [("U", lrim.u.as_slice()), ("Σ", lrim.sigma.as_slice()), ("V", lrim.v.as_slice())] {
+            let count = values.iter().filter(|x| !x.is_finite()).count();
+            if count > 0 {
+                finite = false;
+                issues.push(HealthIssue::NonFinite { factor, count });
+            }
+        }
+        if !finite {
+            return HealthReport { issues, u_defect: f64::NAN, v_defect: f64::NAN, condition: f64::NAN };
+        }
+
+        for (index, &value) in lrim.sigma.iter().enumerate() {
+            if value < 0.0 { issues.push(HealthIssue::NegativeSigma { index, value }); }
+            if index > 0 && value > lrim.sigma[index - 1] {
+                issues.push(HealthIssue::UnsortedSigma { index });
+            }
+        }
+        let u_defect = orthogonality_defect(&lrim.u);
+        let v_defect = orthogonality_defect(&lrim.v);
+        for (space, defect) in [(FactorSpace::U, u_defect), (FactorSpace::V, v_defect)] {
+            if defect > ORTHOGONALITY_TOLERANCE {
+                issues.push(HealthIssue::OrthogonalityDefect { space, defect });
+            }
+        }
+        let condition = if r == 0 { 1.0 } else {
+            let abs = lrim.sigma.map(f64::abs);
+            abs.max() / abs.min()
+        };
+        if condition > MAX_CONDITION_NUMBER {
+            issues.push(HealthIssue::IllConditioned { condition });
+        }
+        HealthReport { issues, u_defect, v_defect, condition }
+    }
+}
+
+impl LowRankIdentity {
+    /// Re-canonicalise through QR/SVD: orthonormal U and V, sorted
+    /// non-negative σ, and components too weak to condition well dropped.
+    /// Returns `None` if the identity has fatal issues.
+    pub fn repair(&self) -> Option<Self> {
+        if !self.validate().is_repairable() { return None; }
+        let us = &self.u * DMatrix::from_diagonal(&self.sigma);
+        Some(Self::from_factor_pair(&us, &self.v, 1.0 / MAX_CONDITION_NUMBER))
+    }
+}
+
+/// Wire form of a LowRankIdentity, checked before it becomes one
+#[derive(Deserialize)]
+#[serde(bound = "T: LrimScalar")]
+pub(super) struct LrimRepr<T: LrimScalar> {
+    u: DMatrix<T>,
+    sigma: DVector<T>,
+    v: DMatrix<T>,
+    rank: usize,
+    m: usize,
+    n: usize,
+}
+
+impl<T: LrimScalar> TryFrom<LrimRepr<T>> for LowRankIdentity<T> {
+    type Error = HealthReport;
+
+    /// Reject identities with fatal issues; repairable ones are let through
+    /// so that their commitments still verify
+    fn try_from(repr: LrimRepr<T>) -> Result<Self, Self::Error> {
+        let LrimRepr { u, sigma, v, rank, m, n } = repr;
+        let lrim = LowRankIdentity { u, sigma, v, rank, m, n };
+        let report = lrim.validate();
+        if report.is_repairable() { Ok(lrim) } else { Err(report) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_and_repair() {
+        let k = DMatrix::new_random(10, 7);
+        let lrim = LowRankIdentity::from_matrix(&k, 3);
+        assert!(lrim.validate().is_healthy(), "{}", lrim.validate());
+
+        let mut skewed = lrim.clone();
+        skewed.u *= 2.0;
+        skewed.sigma[2] = -skewed.sigma[2];
+        skewed.sigma.swap_rows(0, 2);
+        skewed.u.swap_columns(0, 2);
+        skewed.v.swap_columns(0, 2);
+        let report = skewed.validate();
+        assert!(report.is_repairable() && !report.is_healthy());
+        let repaired = skewed.repair().unwrap();
+        assert!(repaired.validate().is_healthy(), "{}", repaired.validate());
+        assert!((repaired.reconstruct() - skewed.reconstruct()).norm() < 1e-9);
+
+        let mut broken = lrim.clone();
+        broken.v[(0, 0)] = f64::NAN;
+        assert!(!broken.validate().is_repairable());
+        assert!(broken.repair().is_none());
+
+        let mut json: serde_json::Value = serde_json::to_value(&lrim).unwrap();
+        json["rank"] = 2.into();
+        assert!(serde_json::from_value::<LowRankIdentity>(json).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 //! Lineage — Merkle-tree ancestry tracking for DNA seeds
 
+use super::{DriftLevel, DriftMetrics, FactorSpace};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -16,8 +17,13 @@ pub struct LineageEvent {
 pub enum LineageEventType {
     Genesis,
     Mutation { error_after: f64 },
-    Replication { child_id: String },
+    Replication { parent_id: String, child_id: String },
     Merge { parent_a: String, parent_b: String },
+    Distillation { parent_id: String, from_rank: usize, to_rank: usize, max_deviation: f64 },
+    Unlearn { space: FactorSpace, subspace_hash: String, removed_dims: usize, energy_lost: f64 },
+    Drift { level: DriftLevel, metrics: DriftMetrics },
+    /// The commitment was replaced without a change of knowledge
+    Recommit { reason: String, previous: String, current: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,12 +55,116 @@ impl Lineage {
         self.events.push(event);
     }
 
-    pub fn spawn_child(&self, parent_id: &str) -> Self {
+    pub fn record_distillation(
+        &mut self,
+        epoch: u64,
+        parent_id: &str,
+        from_rank: usize,
+        to_rank: usize,
+        max_deviation: f64,
+    ) {
+        let event = LineageEvent {
+            epoch,
+            event_type: LineageEventType::Distillation {
+                parent_id: parent_id.to_string(),
+                from_rank,
+                to_rank,
+                max_deviation,
+            },
+            timestamp: Utc::now(),
+            hash: Self::hash_chain(
+                &self.root_hash,
+                &format!("distill:{parent_id}:{from_rank}:{to_rank}:{max_deviation}"),
+            ),
+        };
+        self.root_hash = event.hash.clone();
+        self.events.push(event);
+    }
+
+    pub fn record_unlearn(
+        &mut self,
+        epoch: u64,
+        space: FactorSpace,
+        subspace_hash: &str,
+        removed_dims: usize,
+        energy_lost: f64,
+    ) {
+        let event = LineageEvent {
+            epoch,
+            event_type: LineageEventType::Unlearn {
+                space,
+                subspace_hash: subspace_hash.to_string(),
+                removed_dims,
+                energy_lost,
+            },
+            timestamp: Utc::now(),
+            hash: Self::hash_chain(
+                &self.root_hash,
+                &format!("unlearn:{space:?}:{subspace_hash}:{removed_dims}:{energy_lost}"),
+            ),
+        };
+        self.root_hash = event.hash.clone();
+        self.events.push(event);
+    }
+
+    /// Parent-side record of having produced `child_id`
+    pub fn record_replication(&mut self, epoch: u64, parent_id: &str, child_id: &str) {
+        let event = LineageEvent {
+            epoch,
+            event_type: LineageEventType::Replication {
+                parent_id: parent_id.to_string(),
+                child_id: child_id.to_string(),
+            },
+            timestamp: Utc::now(),
+            hash: Self::hash_chain(&self.root_hash, &format!("replicate:{parent_id}:{child_id}")),
+        };
+        self.root_hash = event.hash.clone();
+        self.events.push(event);
+    }
+
+    /// Record drift escalating past a threshold
+    pub fn record_drift(&mut self, epoch: u64, level: DriftLevel, metrics: &DriftMetrics) {
+        let event = LineageEvent {
+            epoch,
+            event_type: LineageEventType::Drift { level, metrics: metrics.clone() },
+            timestamp: Utc::now(),
+            hash: Self::hash_chain(
+                &self.root_hash,
+                &format!(
+                    "drift:{epoch}:{level:?}:{}:{}:{}",
+                    metrics.v_distance, metrics.u_distance, metrics.spectrum_shift
+                ),
+            ),
+        };
+        self.root_hash = event.hash.clone();
+        self.events.push(event);
+    }
+
+    /// Record a new commitment, by matrix hash
+    pub fn record_recommit(&mut self, epoch: u64, reason: &str, previous: &str, current: &str) {
+        let event = LineageEvent {
+            epoch,
+            event_type: LineageEventType::Recommit {
+                reason: reason.to_string(),
+                previous: previous.to_string(),
+                current: current.to_string(),
+            },
+            timestamp: Utc::now(),
+            hash: Self::hash_chain(&self.root_hash, &format!("recommit:{epoch}:{reason}:{previous}:{current}")),
+        };
+        self.root_hash = event.hash.clone();
+        self.events.push(event);
+    }
+
+    /// Child-side lineage, chained to the parent's current root
+    pub fn spawn_child(&self, parent_id: &str, child_id: &str) -> Self {
         let mut child = Self::genesis();
         child.events[0].event_type = LineageEventType::Replication {
-            child_id: parent_id.to_string(),
+            parent_id: parent_id.to_string(),
+            child_id: child_id.to_string(),
         };
-        child.root_hash = Self::hash_chain(&self.root_hash, &format!("spawn:{parent_id}"));
+        child.root_hash = Self::hash_chain(&self.root_hash, &format!("spawn:{parent_id}:{child_id}"));
+        child.events[0].hash = child.root_hash.clone();
         child
     }
 
@@ -3,19 +3,34 @@
 //! Every entity in DLRS is represented by: Identity = U · Σ · Vᵀ
 //! where U = capability basis, Σ = strength, V = domain projection
 
+use super::LrimScalar;
 use nalgebra::{DMatrix, DVector};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+/// Which factor space a direction or subspace lives in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FactorSpace {
+    /// Capability space (ℝᵐ, columns of U)
+    U,
+    /// Domain space (ℝⁿ, columns of V)
+    V,
+}
+
 /// Low-Rank Identity Matrix — the mathematical core of every entity
+///
+/// Generic over the scalar the factors are stored in (see `scalar`);
+/// factorisation and training work in f64.
+/// Deserialisation rejects identities with fatal health issues (see `validate`).
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LowRankIdentity {
+#[serde(try_from = "super::health::LrimRepr<T>", bound(deserialize = "T: LrimScalar"))]
+pub struct LowRankIdentity<T: LrimScalar = f64> {
     /// Capability basis vectors (m × r)
-    pub u: DMatrix<f64>,
+    pub u: DMatrix<T>,
     /// Capability strengths (diagonal, length r)
-    pub sigma: DVector<f64>,
+    pub sigma: DVector<T>,
     /// Domain projections (n × r)
-    pub v: DMatrix<f64>,
+    pub v: DMatrix<T>,
     /// Intrinsic rank
     pub rank: usize,
     /// Dimensions
@@ -23,9 +38,9 @@ pub struct LowRankIdentity {
     pub n: usize,
 }
 
-impl LowRankIdentity {
+impl<T: LrimScalar> LowRankIdentity<T> {
     /// Create a new LRIM from raw factors
-    pub fn new(u: DMatrix<f64>, sigma: DVector<f64>, v: DMatrix<f64>) -> Self {
+    pub fn new(u: DMatrix<T>, sigma: DVector<T>, v: DMatrix<T>) -> Self {
         let rank = sigma.len();
         let m = u.nrows();
         let n = v.nrows();
@@ -34,6 +49,86 @@ impl LowRankIdentity {
         Self { u, sigma, v, rank, m, n }
     }
 
+    /// Reconstruct the approximate matrix K ≈ U · diag(Σ) · Vᵀ
+    pub fn reconstruct(&self) -> DMatrix<T> {
+        let sigma_mat = DMatrix::from_diagonal(&self.sigma);
+        &self.u * sigma_mat * self.v.transpose()
+    }
+
+    /// Compute reconstruction error (Frobenius norm)
+    pub fn reconstruction_error(&self, original: &DMatrix<T>) -> f64 {
+        let diff = original - self.reconstruct();
+        diff.map(T::to_f64).norm()
+    }
+
+    /// Compression ratio: original_params / low_rank_params
+    pub fn compression_ratio(&self) -> f64 {
+        let original = (self.m * self.n) as f64;
+        let compressed = ((self.m + self.n) * self.rank + self.rank) as f64;
+        original / compressed
+    }
+
+    /// Fingerprint: SHA256 of the canonical encoding (for commitments).
+    /// Factorizations of the same matrix that differ only in sign
+    /// conventions, component order or last-ulp rounding agree, and so do
+    /// a Q32.32 `Fixed` cast and its f64 source.
+    pub fn fingerprint(&self) -> String {
+        hex::encode(Sha256::digest(self.to_f64().canonical_bytes()))
+    }
+
+    /// Compute capability score in a given domain direction
+    pub fn capability_in_domain(&self, domain_vector: &DVector<T>) -> f64 {
+        // Project domain onto V space, weight by sigma
+        let projection = self.v.transpose() * domain_vector;
+        let mut weighted = T::zero();
+        for (p, s) in projection.iter().zip(self.sigma.iter()) {
+            weighted += *p * *s;
+        }
+        weighted.to_f64().abs()
+    }
+
+    /// Convert every factor entry to another scalar type.
+    ///
+    /// Shapes and stored dimensions are kept as they are, so this never
+    /// panics; see `cast_error_bound` for how far the result can move.
+    pub fn cast<S: LrimScalar>(&self) -> LowRankIdentity<S> {
+        let convert = |x: T| S::from_f64(x.to_f64());
+        LowRankIdentity {
+            u: self.u.map(convert),
+            sigma: self.sigma.map(convert),
+            v: self.v.map(convert),
+            rank: self.rank,
+            m: self.m,
+            n: self.n,
+        }
+    }
+
+    /// Widen to f64 (lossless for f32 and for `Fixed` below 2^21)
+    pub fn to_f64(&self) -> LowRankIdentity {
+        self.cast()
+    }
+
+    /// Upper bound on ‖K − K'‖_F, where K' is the matrix represented by
+    /// `self.cast::<S>()` (evaluated exactly, before any arithmetic in S).
+    ///
+    /// Per component, the rounded factors (σ + a)(u + b)(v + c)ᵀ differ from
+    /// σuvᵀ by at most (|σ| + |a|)(‖u‖ + ‖b‖)(‖v‖ + ‖c‖) − |σ|‖u‖‖v‖.
+    pub fn cast_error_bound<S: LrimScalar>(&self) -> f64 {
+        let lrim = self.to_f64();
+        let factor_error = |x: f64| S::rounding_error(x.abs());
+        (0..lrim.rank.min(lrim.sigma.len()))
+            .map(|i| {
+                let (u, v, s) = (lrim.u.column(i), lrim.v.column(i), lrim.sigma[i].abs());
+                let du = u.iter().map(|&x| factor_error(x).powi(2)).sum::<f64>().sqrt();
+                let dv = v.iter().map(|&x| factor_error(x).powi(2)).sum::<f64>().sqrt();
+                let ds = factor_error(s);
+                (s + ds) * (u.norm() + du) * (v.norm() + dv) - s * u.norm() * v.norm()
+            })
+            .sum()
+    }
+}
+
+impl LowRankIdentity {
     /// Create LRIM from a full matrix via truncated SVD
     pub fn from_matrix(k: &DMatrix<f64>, target_rank: usize) -> Self {
         let svd = k.svd(true, true);
@@ -50,50 +145,65 @@ impl LowRankIdentity {
         Self::new(u, sigma, v)
     }
 
-    /// Reconstruct the approximate matrix K ≈ U · diag(Σ) · Vᵀ
-    pub fn reconstruct(&self) -> DMatrix<f64> {
-        let sigma_mat = DMatrix::from_diagonal(&self.sigma);
-        &self.u * sigma_mat * self.v.transpose()
-    }
+    /// Canonical LRIM of K = A · Bᵀ for arbitrary (non-orthonormal) factors
+    ///
+    /// QR-decomposes both factors, takes the SVD of the small core Ra · Rbᵀ and
+    /// drops components below `tol · σ_max`.
+    pub fn from_factor_pair(a: &DMatrix<f64>, b: &DMatrix<f64>, tol: f64) -> Self {
+        assert_eq!(a.ncols(), b.ncols(), "Factor pair must share inner dimension");
+        let qr_a = a.clone().qr();
+        let qr_b = b.clone().qr();
+        let core = qr_a.r() * qr_b.r().transpose();
+        let (core_u, s, core_v) = jacobi_svd(&core);
 
-    /// Compute reconstruction error (Frobenius norm)
-    pub fn reconstruction_error(&self, original: &DMatrix<f64>) -> f64 {
-        let approx = self.reconstruct();
-        let diff = original - approx;
-        diff.norm()
+        let cutoff = tol * s.iter().cloned().fold(0.0, f64::max);
+        let keep: Vec<usize> = (0..s.len()).filter(|&i| s[i] > cutoff).collect();
+
+        let u = qr_a.q() * core_u.select_columns(keep.iter());
+        let sigma = s.select_rows(keep.iter());
+        let v = qr_b.q() * core_v.select_columns(keep.iter());
+        Self::new(u, sigma, v)
     }
 
-    /// Compression ratio: original_params / low_rank_params
-    pub fn compression_ratio(&self) -> f64 {
-        let original = (self.m * self.n) as f64;
-        let compressed = ((self.m + self.n) * self.rank + self.rank) as f64;
-        original / compressed
+    /// Remove the span of `basis` (columns, in the given factor space) from the
+    /// identity and re-orthonormalise. Returns the result and the dimension of
+    /// the span actually removed, which is less than `basis.ncols()` when
+    /// columns are (nearly) dependent.
+    pub fn project_out(&self, basis: &DMatrix<f64>, space: FactorSpace) -> (Self, usize) {
+        let q = orthonormal_span(basis, SPAN_TOLERANCE);
+        let us = &self.u * DMatrix::from_diagonal(&self.sigma);
+        let projected = match space {
+            FactorSpace::U => {
+                assert_eq!(basis.nrows(), self.m, "Basis must live in ℝᵐ");
+                let a = &us - &q * (q.transpose() * &us);
+                Self::from_factor_pair(&a, &self.v, 1e-10)
+            }
+            FactorSpace::V => {
+                assert_eq!(basis.nrows(), self.n, "Basis must live in ℝⁿ");
+                let b = &self.v - &q * (q.transpose() * &self.v);
+                Self::from_factor_pair(&us, &b, 1e-10)
+            }
+        };
+        (projected, q.ncols())
     }
 
-    /// Fingerprint: SHA256 hash of the factorization (for commitments)
-    pub fn fingerprint(&self) -> String {
-        let mut hasher = Sha256::new();
-        for val in self.u.iter() {
-            hasher.update(val.to_le_bytes());
-        }
-        for val in self.sigma.iter() {
-            hasher.update(val.to_le_bytes());
-        }
-        for val in self.v.iter() {
-            hasher.update(val.to_le_bytes());
-        }
-        hex::encode(hasher.finalize())
+    /// Squared Frobenius norm of the reconstruction, computed from the factors
+    pub fn energy(&self) -> f64 {
+        let sigma_mat = DMatrix::from_diagonal(&self.sigma);
+        let gram_u = self.u.transpose() * &self.u;
+        let gram_v = self.v.transpose() * &self.v;
+        (&sigma_mat * gram_u * &sigma_mat).component_mul(&gram_v).sum()
     }
 
-    /// Compute capability score in a given domain direction
-    pub fn capability_in_domain(&self, domain_vector: &DVector<f64>) -> f64 {
-        // Project domain onto V space, weight by sigma
-        let projection = self.v.transpose() * domain_vector;
-        let weighted: f64 = projection.iter()
-            .zip(self.sigma.iter())
-            .map(|(p, s)| p * s)
-            .sum();
-        weighted.abs()
+    /// Keep only the given components (columns of U/V and entries of Σ),
+    /// ordered by descending strength
+    pub fn select_components(&self, indices: &[usize]) -> Self {
+        let mut keep = indices.to_vec();
+        keep.sort_by(|&a, &b| self.sigma[b].total_cmp(&self.sigma[a]));
+        let u = self.u.select_columns(keep.iter());
+        let sigma = self.sigma.select_rows(keep.iter());
+        let v = self.v.select_columns(keep.iter());
+        Self::new(u, sigma, v)
     }
 
     /// Merge two LRIMs into a higher-rank composite
@@ -108,6 +218,67 @@ impl LowRankIdentity {
     }
 }
 
+/// Singular values below this fraction of the largest count as zero when
+/// taking the span of a basis
+pub(super) const SPAN_TOLERANCE: f64 = 1e-10;
+
+/// Orthonormal basis for the column span of `basis`, keeping only the
+/// directions whose singular value exceeds `rel_tol` times the largest.
+/// Unlike a thin QR, dependent columns contribute no extra directions.
+pub fn orthonormal_span(basis: &DMatrix<f64>, rel_tol: f64) -> DMatrix<f64> {
+    if basis.ncols() == 0 { return DMatrix::zeros(basis.nrows(), 0); }
+    let (u, s, _) = jacobi_svd(basis);
+    let cutoff = s[0] * rel_tol;
+    let keep = s.iter().take_while(|&&x| x > cutoff && x > 0.0).count();
+    u.columns(0, keep).into_owned()
+}
+
+/// SVD of a small matrix by one-sided Jacobi rotations, sorted by
+/// descending singular value.
+///
+/// nalgebra's bidiagonal SVD can lose several digits on rank-deficient
+/// square inputs, which are exactly the cores produced by projections.
+/// Columns of U belonging to zero singular values are left as zero.
+fn jacobi_svd(a: &DMatrix<f64>) -> (DMatrix<f64>, DVector<f64>, DMatrix<f64>) {
+    let mut w = a.clone();
+    let q = w.ncols();
+    let mut v = DMatrix::identity(q, q);
+    for _sweep in 0..60 {
+        let mut rotated = false;
+        for i in 0..q {
+            for j in (i + 1)..q {
+                let alpha = w.column(i).norm_squared();
+                let beta = w.column(j).norm_squared();
+                let gamma = w.column(i).dot(&w.column(j));
+                if gamma == 0.0 || gamma.abs() <= f64::EPSILON * (alpha * beta).sqrt() { continue; }
+                rotated = true;
+                let zeta = (beta - alpha) / (2.0 * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+                for m in [&mut w, &mut v] {
+                    for row in 0..m.nrows() {
+                        let (x, y) = (m[(row, i)], m[(row, j)]);
+                        m[(row, i)] = c * x - s * y;
+                        m[(row, j)] = s * x + c * y;
+                    }
+                }
+            }
+        }
+        if !rotated { break; }
+    }
+
+    let norms: Vec<f64> = w.column_iter().map(|c| c.norm()).collect();
+    let mut order: Vec<usize> = (0..q).collect();
+    order.sort_by(|&x, &y| norms[y].total_cmp(&norms[x]));
+    let sigma = DVector::from_iterator(q, order.iter().map(|&k| norms[k]));
+    let mut u = w.select_columns(order.iter());
+    for (k, mut col) in u.column_iter_mut().enumerate() {
+        if sigma[k] > 0.0 { col /= sigma[k]; }
+    }
+    (u, sigma, v.select_columns(order.iter()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,4 +319,51 @@ mod tests {
         let merged = LowRankIdentity::merge(&lrim_a, &lrim_b);
         assert!(merged.rank <= 6);
     }
+
+    #[test]
+    fn test_project_out() {
+        let k = DMatrix::new_random(12, 9);
+        let lrim = LowRankIdentity::from_matrix(&k, 4);
+        let direction = DMatrix::from_column_slice(9, 1, lrim.v.column(0).as_slice());
+        let (pruned, removed) = lrim.project_out(&direction, FactorSpace::V);
+
+        assert_eq!((pruned.rank, removed), (3, 1));
+        let d = DVector::from_column_slice(direction.as_slice());
+        assert!(pruned.capability_in_domain(&d) < 1e-9);
+        let projector = DMatrix::identity(9, 9) - &d * d.transpose();
+        let expected = lrim.reconstruct() * projector;
+        assert!((pruned.reconstruct() - &expected).norm() < 1e-9 * expected.norm());
+        assert!((pruned.energy() - expected.norm_squared()).abs() < 1e-9 * expected.norm_squared());
+
+        // Dependent columns add no directions: only the one span is removed
+        let mut redundant = DMatrix::zeros(9, 3);
+        redundant.set_column(0, &d);
+        redundant.set_column(1, &(&d * 2.0));
+        redundant.set_column(2, &(&d * -0.5 + DVector::from_element(9, 1e-14)));
+        let (again, removed) = lrim.project_out(&redundant, FactorSpace::V);
+        assert_eq!((again.rank, removed), (3, 1));
+        assert!((again.reconstruct() - &expected).norm() < 1e-9 * expected.norm());
+    }
+
+    #[test]
+    fn test_cast_between_scalars() {
+        use crate::seed::Fixed;
+        let k = DMatrix::new_random(12, 9);
+        let lrim = LowRankIdentity::from_matrix(&k, 4);
+        let exact = lrim.reconstruct();
+
+        let single = lrim.cast::<f32>();
+        let drift = (single.to_f64().reconstruct() - &exact).norm();
+        assert!(drift <= lrim.cast_error_bound::<f32>(), "{drift}");
+        assert!(single.validate().is_healthy());
+
+        let fixed = lrim.cast::<Fixed>();
+        let drift = (fixed.to_f64().reconstruct() - &exact).norm();
+        assert!(drift <= lrim.cast_error_bound::<Fixed>(), "{drift}");
+        assert_eq!(fixed.fingerprint(), lrim.fingerprint());
+        assert_eq!(fixed.to_f64().cast::<Fixed>().u, fixed.u);
+        let fixed_k = fixed.reconstruct().map(|x: Fixed| x.to_f64());
+        assert!((fixed_k - &exact).amax() < 1e-8);
+        assert_eq!(lrim.cast_error_bound::<f64>(), 0.0);
+    }
 }
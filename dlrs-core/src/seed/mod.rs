@@ -8,9 +8,33 @@ mod dna;
 mod mutation;
 mod replication;
 mod lineage;
+mod distill;
+mod unlearn;
+mod optim;
+mod online;
+mod drift;
+mod health;
+mod encoding;
+mod scalar;
+mod quant;
+mod sparse;
+mod stream;
+mod tensor;
 
-pub use lrim::LowRankIdentity;
+pub use lrim::{orthonormal_span, FactorSpace, LowRankIdentity};
 pub use dna::DnaSeed;
 pub use mutation::MutationRules;
-pub use replication::ReplicationPolicy;
-pub use lineage::Lineage;
+pub use replication::{FitnessDecay, FitnessInheritance, MergeRule, ReplicationPolicy};
+pub use lineage::{Lineage, LineageEvent, LineageEventType};
+pub use distill::{DistillationPoint, DistillationReport};
+pub use unlearn::UnlearnReport;
+pub use optim::{EarlyStopping, FactorGrads, LrSchedule, Optimizer, OptimizerKind, Trainer, TrainReport};
+pub use online::{batch_loss, Example, OnlineEvolver, OnlineReport};
+pub use drift::{DriftLevel, DriftMetrics, DriftMonitor, DriftThresholds, SubspaceSnapshot};
+pub use health::{HealthIssue, HealthReport, MAX_CONDITION_NUMBER, ORTHOGONALITY_TOLERANCE};
+pub use encoding::{from_fixed, to_fixed, FIXED_POINT_FRAC_BITS, LRIM_ENCODING_TAG};
+pub use scalar::{Fixed, LrimScalar};
+pub use quant::{QuantBits, QuantizedFactor, QuantizedLrim, QuantizedSeed, QUANTIZED_ENCODING_TAG};
+pub use sparse::{CooMatrix, CsrMatrix, RandomizedSvdConfig, SparseMatrix};
+pub use stream::{IterBlocks, MatrixFileSource, MatrixFileWriter, RowBlockSource, MATRIX_FILE_MAGIC};
+pub use tensor::{CpDecomposition, KnowledgePayload, Tensor3, TensorForm, TensorLayout, TensorPayload, TuckerDecomposition};
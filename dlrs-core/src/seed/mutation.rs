@@ -1,5 +1,7 @@
 //! Mutation rules — governs how a seed evolves
 
+use super::LowRankIdentity;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +30,27 @@ impl MutationRules {
         !self.frozen && current_fitness >= self.min_fitness_to_mutate
     }
 
+    /// Randomly perturb factor entries: each entry of U and V moves with
+    /// probability `perturbation_prob` by up to `max_learning_rate` of its
+    /// column's largest magnitude. The result is re-canonicalised.
+    pub fn perturb(&self, lrim: &LowRankIdentity) -> LowRankIdentity {
+        let mut rng = rand::thread_rng();
+        let mut u = lrim.u.clone();
+        let mut v = lrim.v.clone();
+        for factor in [&mut u, &mut v] {
+            for mut col in factor.column_iter_mut() {
+                let scale = col.amax() * self.max_learning_rate;
+                for x in col.iter_mut() {
+                    if rng.gen::<f64>() < self.perturbation_prob {
+                        *x += scale * rng.gen_range(-1.0..=1.0);
+                    }
+                }
+            }
+        }
+        let us = u * nalgebra::DMatrix::from_diagonal(&lrim.sigma);
+        LowRankIdentity::from_factor_pair(&us, &v, 1e-12)
+    }
+
     pub fn conservative() -> Self {
         Self {
             min_fitness_to_mutate: 0.7,
@@ -0,0 +1,164 @@
+//! Online evolution from streaming (input, desired output) examples
+//!
+//! Agents rarely see a whole m×n feedback matrix; they see what the seed
+//! produced for an input and what it should have produced. Gradients of the
+//! squared output error are computed directly in factor form, without ever
+//! reconstructing K, and unknown output entries can be masked out.
+
+use super::{DnaSeed, FactorGrads, LowRankIdentity, Trainer};
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+/// One observation: the seed should map `input` (length n) to `output` (length m)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Example {
+    pub input: DVector<f64>,
+    pub output: DVector<f64>,
+    /// Which output entries are known; `None` means all of them
+    pub mask: Option<Vec<bool>>,
+}
+
+impl Example {
+    pub fn new(input: DVector<f64>, output: DVector<f64>) -> Self {
+        Self { input, output, mask: None }
+    }
+
+    fn observed(&self, row: usize) -> bool {
+        self.mask.as_ref().is_none_or(|m| m[row])
+    }
+
+    fn fits(&self, lrim: &LowRankIdentity) -> bool {
+        self.input.len() == lrim.n
+            && self.output.len() == lrim.m
+            && self.mask.as_ref().is_none_or(|m| m.len() == lrim.m)
+    }
+
+    /// Masked residual K·x − y
+    fn residual(&self, lrim: &LowRankIdentity) -> DVector<f64> {
+        let z = lrim.v.transpose() * &self.input;
+        let predicted = &lrim.u * z.component_mul(&lrim.sigma);
+        let mut residual = predicted - &self.output;
+        for (row, e) in residual.iter_mut().enumerate() {
+            if !self.observed(row) { *e = 0.0; }
+        }
+        residual
+    }
+}
+
+/// Mean of ½‖M ⊙ (K·x − y)‖² over a batch
+pub fn batch_loss(lrim: &LowRankIdentity, batch: &[Example]) -> f64 {
+    let total: f64 = batch.iter().map(|ex| 0.5 * ex.residual(lrim).norm_squared()).sum();
+    total / batch.len().max(1) as f64
+}
+
+impl FactorGrads {
+    /// Gradients of `batch_loss` as sums of rank-one terms per example
+    pub fn from_examples(lrim: &LowRankIdentity, batch: &[Example]) -> Self {
+        let mut grads = Self {
+            u: DMatrix::zeros(lrim.m, lrim.rank),
+            sigma: DVector::zeros(lrim.rank),
+            v: DMatrix::zeros(lrim.n, lrim.rank),
+        };
+        for ex in batch {
+            let e = ex.residual(lrim);
+            let z = lrim.v.transpose() * &ex.input;
+            let w = lrim.u.transpose() * &e;
+            grads.u += &e * z.component_mul(&lrim.sigma).transpose();
+            grads.sigma += w.component_mul(&z);
+            grads.v += &ex.input * w.component_mul(&lrim.sigma).transpose();
+        }
+        let scale = 1.0 / batch.len().max(1) as f64;
+        grads.u *= scale;
+        grads.sigma *= scale;
+        grads.v *= scale;
+        grads
+    }
+}
+
+/// Online training state: the optimiser plus a running loss estimate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnlineEvolver {
+    pub trainer: Trainer,
+    /// Weight of the newest batch in the running loss (exponential average)
+    pub smoothing: f64,
+    pub running_loss: Option<f64>,
+    pub samples_seen: u64,
+}
+
+impl OnlineEvolver {
+    pub fn new(trainer: Trainer) -> Self {
+        Self { trainer, smoothing: 0.1, running_loss: None, samples_seen: 0 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnlineReport {
+    /// Loss on this batch before the update
+    pub batch_loss: f64,
+    pub running_loss: f64,
+    pub samples_seen: u64,
+}
+
+impl DnaSeed {
+    /// Take one optimiser step on a mini-batch of examples.
+    ///
+    /// Each batch counts as an epoch for fitness and lineage. Returns `None`
+    /// if the batch is empty, any example has the wrong dimensions, or the
+    /// seed may not mutate.
+    pub fn evolve_online(&mut self, batch: &[Example], evolver: &mut OnlineEvolver) -> Option<OnlineReport> {
+        if batch.is_empty() || !batch.iter().all(|ex| ex.fits(&self.lrim)) { return None; }
+        if !self.mutation.can_mutate(self.fitness) { return None; }
+
+        evolver.trainer.optimizer.prepare(&mut self.lrim);
+        let old_loss = batch_loss(&self.lrim, batch);
+        let grads = FactorGrads::from_examples(&self.lrim, batch);
+        let lr = evolver.trainer.learning_rate();
+        evolver.trainer.optimizer.step(&mut self.lrim, &grads, lr);
+        let new_loss = batch_loss(&self.lrim, batch);
+        self.record_epoch(old_loss, new_loss);
+        evolver.trainer.observe(new_loss);
+
+        let running = match evolver.running_loss {
+            Some(prev) => (1.0 - evolver.smoothing) * prev + evolver.smoothing * old_loss,
+            None => old_loss,
+        };
+        evolver.running_loss = Some(running);
+        evolver.samples_seen += batch.len() as u64;
+        Some(OnlineReport { batch_loss: old_loss, running_loss: running, samples_seen: evolver.samples_seen })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed::Optimizer;
+
+    #[test]
+    fn test_online_evolution_learns_teacher() {
+        let teacher = LowRankIdentity::from_matrix(&DMatrix::new_random(8, 6), 2);
+        let mut seed = DnaSeed::new("student", &DMatrix::new_random(8, 6), 2, vec!["test".into()]);
+        let mut evolver = OnlineEvolver::new(Trainer::new(Optimizer::adam(), 0.02));
+
+        let mut first = None;
+        let mut last = None;
+        for step in 0..300 {
+            let batch: Vec<Example> = (0..8)
+                .map(|i| {
+                    let x = DVector::new_random(6);
+                    let y = teacher.reconstruct() * &x;
+                    // Hide a different output entry in every example
+                    let mask = (0..8).map(|row| row != (step + i) % 8).collect();
+                    Example { input: x, output: y, mask: Some(mask) }
+                })
+                .collect();
+            let report = seed.evolve_online(&batch, &mut evolver).expect("batch fits");
+            first.get_or_insert(report.running_loss);
+            last = Some(report.running_loss);
+        }
+        assert!(last.unwrap() < 0.1 * first.unwrap());
+        assert_eq!(evolver.samples_seen, 300 * 8);
+
+        let bad = vec![Example::new(DVector::zeros(5), DVector::zeros(8))];
+        assert!(seed.evolve_online(&bad, &mut evolver).is_none());
+    }
+}
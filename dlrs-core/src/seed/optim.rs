@@ -0,0 +1,355 @@
+//! Optimisers for seed evolution
+//!
+//! `DnaSeed::evolve` takes one plain gradient step at a fixed rate. A `Trainer`
+//! bundles an optimiser (SGD with momentum, Adam, Riemannian Adam), a
+//! learning-rate schedule and early stopping. All of its state serialises, so
+//! training can stop and resume from a `SeedStore`.
+
+use super::{DnaSeed, LowRankIdentity};
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+
+/// Gradients of the loss with respect to each factor (or any per-factor
+/// quantity with the same shapes, such as optimiser moments)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactorGrads {
+    pub u: DMatrix<f64>,
+    pub sigma: DVector<f64>,
+    pub v: DMatrix<f64>,
+}
+
+impl FactorGrads {
+    pub fn zeros_like(other: &FactorGrads) -> Self {
+        Self {
+            u: DMatrix::zeros(other.u.nrows(), other.u.ncols()),
+            sigma: DVector::zeros(other.sigma.len()),
+            v: DMatrix::zeros(other.v.nrows(), other.v.ncols()),
+        }
+    }
+
+    /// Gradients of ½‖T − U·Σ·Vᵀ‖²_F against a full target matrix T
+    pub fn from_target(lrim: &LowRankIdentity, target: &DMatrix<f64>) -> Self {
+        let error = target - lrim.reconstruct();
+        let sigma_mat = DMatrix::from_diagonal(&lrim.sigma);
+        let ev = &error * &lrim.v;
+        let etu = error.transpose() * &lrim.u;
+        Self {
+            u: -(&ev * &sigma_mat),
+            sigma: -(lrim.u.transpose() * &ev).diagonal(),
+            v: -(etu * &sigma_mat),
+        }
+    }
+
+    fn same_shape(&self, other: &FactorGrads) -> bool {
+        self.u.shape() == other.u.shape()
+            && self.sigma.len() == other.sigma.len()
+            && self.v.shape() == other.v.shape()
+    }
+
+    fn values(&self) -> impl Iterator<Item = &f64> {
+        self.u.iter().chain(self.sigma.iter()).chain(self.v.iter())
+    }
+
+    fn values_mut(&mut self) -> impl Iterator<Item = &mut f64> {
+        self.u.iter_mut().chain(self.sigma.iter_mut()).chain(self.v.iter_mut())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OptimizerKind {
+    Sgd { momentum: f64 },
+    Adam { beta1: f64, beta2: f64, eps: f64 },
+    /// Adam on the Stiefel manifold: U and V stay orthonormal via tangent-space
+    /// projection and QR retraction; Σ is updated in Euclidean space and kept ≥ 0
+    RiemannianAdam { beta1: f64, beta2: f64, eps: f64 },
+}
+
+/// An optimiser and its accumulated state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Optimizer {
+    pub kind: OptimizerKind,
+    pub steps: u64,
+    first_moment: Option<FactorGrads>,
+    second_moment: Option<FactorGrads>,
+}
+
+impl Optimizer {
+    pub fn new(kind: OptimizerKind) -> Self {
+        Self { kind, steps: 0, first_moment: None, second_moment: None }
+    }
+
+    pub fn sgd(momentum: f64) -> Self {
+        Self::new(OptimizerKind::Sgd { momentum })
+    }
+
+    pub fn adam() -> Self {
+        Self::new(OptimizerKind::Adam { beta1: 0.9, beta2: 0.999, eps: 1e-8 })
+    }
+
+    pub fn riemannian_adam() -> Self {
+        Self::new(OptimizerKind::RiemannianAdam { beta1: 0.9, beta2: 0.999, eps: 1e-8 })
+    }
+
+    pub fn reset(&mut self) {
+        self.steps = 0;
+        self.first_moment = None;
+        self.second_moment = None;
+    }
+
+    /// Bring the factors into the form this optimiser expects. Riemannian
+    /// updates need orthonormal U and V, so other factorizations of the same
+    /// matrix are re-canonicalised (which invalidates accumulated moments).
+    pub fn prepare(&mut self, lrim: &mut LowRankIdentity) {
+        if !matches!(self.kind, OptimizerKind::RiemannianAdam { .. }) { return; }
+        let defect = |x: &DMatrix<f64>| {
+            (x.transpose() * x - DMatrix::identity(x.ncols(), x.ncols())).amax()
+        };
+        if defect(&lrim.u) > 1e-8 || defect(&lrim.v) > 1e-8 {
+            let us = &lrim.u * DMatrix::from_diagonal(&lrim.sigma);
+            *lrim = LowRankIdentity::from_factor_pair(&us, &lrim.v, 1e-12);
+            self.reset();
+        }
+    }
+
+    /// Apply one update with learning rate `lr`
+    pub fn step(&mut self, lrim: &mut LowRankIdentity, grads: &FactorGrads, lr: f64) {
+        let riemannian = matches!(self.kind, OptimizerKind::RiemannianAdam { .. });
+        let mut grads = grads.clone();
+        if riemannian {
+            grads.u = project_tangent(&lrim.u, &grads.u);
+            grads.v = project_tangent(&lrim.v, &grads.v);
+        }
+
+        let dir = self.direction(&grads);
+        if riemannian {
+            lrim.u = qr_retract(&lrim.u - lr * &dir.u);
+            lrim.v = qr_retract(&lrim.v - lr * &dir.v);
+            lrim.sigma = (&lrim.sigma - lr * &dir.sigma).map(|s| s.max(0.0));
+            if let Some(m) = self.first_moment.as_mut() {
+                m.u = project_tangent(&lrim.u, &m.u);
+                m.v = project_tangent(&lrim.v, &m.v);
+            }
+        } else {
+            lrim.u -= lr * &dir.u;
+            lrim.sigma -= lr * &dir.sigma;
+            lrim.v -= lr * &dir.v;
+        }
+    }
+
+    fn direction(&mut self, grads: &FactorGrads) -> FactorGrads {
+        if self.first_moment.as_ref().is_some_and(|m| !m.same_shape(grads)) {
+            self.reset();
+        }
+        self.steps += 1;
+        let first = self.first_moment.get_or_insert_with(|| FactorGrads::zeros_like(grads));
+        match self.kind {
+            OptimizerKind::Sgd { momentum } => {
+                for (m, g) in first.values_mut().zip(grads.values()) {
+                    *m = momentum * *m + g;
+                }
+                first.clone()
+            }
+            OptimizerKind::Adam { beta1, beta2, eps }
+            | OptimizerKind::RiemannianAdam { beta1, beta2, eps } => {
+                let second = self.second_moment.get_or_insert_with(|| FactorGrads::zeros_like(grads));
+                for ((m, v), g) in first.values_mut().zip(second.values_mut()).zip(grads.values()) {
+                    *m = beta1 * *m + (1.0 - beta1) * g;
+                    *v = beta2 * *v + (1.0 - beta2) * g * g;
+                }
+                let t = self.steps.min(i32::MAX as u64) as i32;
+                let (c1, c2) = (1.0 - beta1.powi(t), 1.0 - beta2.powi(t));
+                let mut dir = FactorGrads::zeros_like(grads);
+                for ((d, m), v) in dir.values_mut().zip(first.values()).zip(second.values()) {
+                    *d = (m / c1) / ((v / c2).sqrt() + eps);
+                }
+                dir
+            }
+        }
+    }
+}
+
+/// Project G onto the tangent space of the Stiefel manifold at X
+fn project_tangent(x: &DMatrix<f64>, g: &DMatrix<f64>) -> DMatrix<f64> {
+    let xtg = x.transpose() * g;
+    let sym = (&xtg + xtg.transpose()) * 0.5;
+    g - x * sym
+}
+
+/// QR retraction with signs fixed so R has a non-negative diagonal
+fn qr_retract(x: DMatrix<f64>) -> DMatrix<f64> {
+    let qr = x.qr();
+    let r = qr.r();
+    let mut q = qr.q();
+    for j in 0..q.ncols() {
+        if r[(j, j)] < 0.0 {
+            q.column_mut(j).neg_mut();
+        }
+    }
+    q
+}
+
+/// Learning-rate multiplier as a function of the optimiser step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LrSchedule {
+    Constant,
+    /// Cosine annealing from 1 down to `min_factor` over `total_steps`
+    Cosine { total_steps: u64, min_factor: f64 },
+    /// Multiply by `gamma` every `every` steps
+    Step { every: u64, gamma: f64 },
+    /// Linear ramp over `steps`, then hand over to `then`
+    Warmup { steps: u64, then: Box<LrSchedule> },
+}
+
+impl LrSchedule {
+    pub fn factor(&self, step: u64) -> f64 {
+        match self {
+            LrSchedule::Constant => 1.0,
+            LrSchedule::Cosine { total_steps, min_factor } => {
+                let t = step.min(*total_steps) as f64 / (*total_steps).max(1) as f64;
+                min_factor + (1.0 - min_factor) * 0.5 * (1.0 + (std::f64::consts::PI * t).cos())
+            }
+            LrSchedule::Step { every, gamma } => {
+                gamma.powi((step / (*every).max(1)).min(i32::MAX as u64) as i32)
+            }
+            LrSchedule::Warmup { steps, then } => {
+                if step < *steps {
+                    (step + 1) as f64 / *steps as f64
+                } else {
+                    then.factor(step - steps)
+                }
+            }
+        }
+    }
+}
+
+/// Stop when the loss has not improved by `min_delta` for `patience` epochs.
+/// A patience of 0 disables the rule, as if `early_stopping` were `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EarlyStopping {
+    pub patience: usize,
+    pub min_delta: f64,
+}
+
+/// Resumable training state: optimiser, schedule and early-stopping progress
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trainer {
+    pub optimizer: Optimizer,
+    pub base_lr: f64,
+    pub schedule: LrSchedule,
+    pub early_stopping: Option<EarlyStopping>,
+    pub best_loss: Option<f64>,
+    pub epochs_without_improvement: usize,
+}
+
+impl Trainer {
+    pub fn new(optimizer: Optimizer, base_lr: f64) -> Self {
+        Self {
+            optimizer,
+            base_lr,
+            schedule: LrSchedule::Constant,
+            early_stopping: None,
+            best_loss: None,
+            epochs_without_improvement: 0,
+        }
+    }
+
+    pub fn learning_rate(&self) -> f64 {
+        self.base_lr * self.schedule.factor(self.optimizer.steps)
+    }
+
+    /// Record an epoch's loss; returns true if training should stop
+    pub fn observe(&mut self, loss: f64) -> bool {
+        let min_delta = self.early_stopping.as_ref().map_or(0.0, |e| e.min_delta);
+        if self.best_loss.is_none_or(|best| loss < best - min_delta) {
+            self.best_loss = Some(loss);
+            self.epochs_without_improvement = 0;
+        } else {
+            self.epochs_without_improvement += 1;
+        }
+        self.early_stopping.as_ref()
+            .is_some_and(|e| e.patience > 0 && self.epochs_without_improvement >= e.patience)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainReport {
+    /// Reconstruction error ‖T − K‖_F after each epoch
+    pub loss_history: Vec<f64>,
+    pub best_loss: Option<f64>,
+    pub stopped_early: bool,
+}
+
+impl DnaSeed {
+    /// Train towards `target` for up to `max_epochs`, recording each epoch in
+    /// fitness and lineage like `evolve`. Stops early if mutation is no longer
+    /// allowed or the trainer's early-stopping rule fires.
+    pub fn train(&mut self, target: &DMatrix<f64>, trainer: &mut Trainer, max_epochs: usize) -> TrainReport {
+        let mut loss_history = Vec::new();
+        let mut stopped_early = false;
+        for _ in 0..max_epochs {
+            if !self.mutation.can_mutate(self.fitness) { break; }
+            trainer.optimizer.prepare(&mut self.lrim);
+            let old_error = self.lrim.reconstruction_error(target);
+            let grads = FactorGrads::from_target(&self.lrim, target);
+            let lr = trainer.learning_rate();
+            trainer.optimizer.step(&mut self.lrim, &grads, lr);
+            let new_error = self.lrim.reconstruction_error(target);
+            self.record_epoch(old_error, new_error);
+            loss_history.push(new_error);
+            if trainer.observe(new_error) {
+                stopped_early = true;
+                break;
+            }
+        }
+        TrainReport { loss_history, best_loss: trainer.best_loss, stopped_early }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimizers_reduce_loss() {
+        let target = DMatrix::new_random(15, 10);
+        for optimizer in [Optimizer::sgd(0.9), Optimizer::adam(), Optimizer::riemannian_adam()] {
+            let start = DMatrix::new_random(15, 10);
+            let mut seed = DnaSeed::new("trainee", &start, 4, vec!["test".into()]);
+            let initial = seed.lrim.reconstruction_error(&target);
+            let lr = if matches!(optimizer.kind, OptimizerKind::Sgd { .. }) { 0.002 } else { 0.01 };
+            let mut trainer = Trainer::new(optimizer, lr);
+            trainer.schedule = LrSchedule::Warmup {
+                steps: 5,
+                then: Box::new(LrSchedule::Cosine { total_steps: 200, min_factor: 0.1 }),
+            };
+            let report = seed.train(&target, &mut trainer, 200);
+            let last = *report.loss_history.last().unwrap();
+            assert!(last < initial, "{:?}: {last} >= {initial}", trainer.optimizer.kind);
+            assert_eq!(seed.epoch as usize, report.loss_history.len());
+        }
+    }
+
+    #[test]
+    fn test_riemannian_keeps_orthonormal_and_resumes() {
+        let target = DMatrix::new_random(12, 8);
+        let mut seed = DnaSeed::new("stiefel", &DMatrix::new_random(12, 8), 3, vec!["test".into()]);
+        let mut trainer = Trainer::new(Optimizer::riemannian_adam(), 0.05);
+        trainer.early_stopping = Some(EarlyStopping { patience: 3, min_delta: 1e-9 });
+        seed.train(&target, &mut trainer, 10);
+
+        let identity = DMatrix::<f64>::identity(3, 3);
+        assert!((seed.lrim.u.transpose() * &seed.lrim.u - &identity).amax() < 1e-9);
+        assert!((seed.lrim.v.transpose() * &seed.lrim.v - &identity).amax() < 1e-9);
+
+        let json = serde_json::to_string(&trainer).unwrap();
+        let mut resumed: Trainer = serde_json::from_str(&json).unwrap();
+        assert_eq!(resumed.optimizer.steps, 10);
+        let report = seed.train(&target, &mut resumed, 1000);
+        assert!(report.stopped_early);
+
+        // Zero patience never stops training
+        let mut trainer = Trainer::new(Optimizer::riemannian_adam(), 0.05);
+        trainer.early_stopping = Some(EarlyStopping { patience: 0, min_delta: 1.0 });
+        assert!((0..5).all(|_| !trainer.observe(1.0)));
+    }
+}
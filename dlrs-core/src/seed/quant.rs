@@ -0,0 +1,405 @@
+//! Quantized factor storage for distribution
+//!
+//! U and V are stored as unsigned int8 or int4 levels with a per-column
+//! scale and zero-point, x ≈ (q − zero_point) · scale; Σ stays f64. The zero
+//! point is chosen so that 0.0 is exactly representable. Expression works
+//! directly on the levels, and the quantisation error measured against the
+//! source factors travels with the payload.
+//!
+//! Payload encoding (integers little-endian), which commitments bind to:
+//!
+//! ```text
+//! tag    14 bytes  "DLRS/QLRIM/v1" followed by a 0 byte
+//! bits   u8        8 or 4
+//! m n r  u64 × 3
+//! error  f64       measured ‖K − K_q‖_F
+//! σ      r × f64
+//! U, V   each: scales r × f32, zero points r × u8, levels column-major,
+//!        one byte each (int8) or two per byte, low nibble first (int4)
+//! ```
+
+use super::dna::Instruction;
+use super::{DnaSeed, Lineage, LowRankIdentity, MutationRules, ReplicationPolicy};
+use crate::zk::ZkCommitment;
+use chrono::{DateTime, Utc};
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Domain-separation tag opening every quantized payload
+pub const QUANTIZED_ENCODING_TAG: &[u8; 14] = b"DLRS/QLRIM/v1\0";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuantBits {
+    Int8,
+    Int4,
+}
+
+impl QuantBits {
+    pub fn bits(self) -> u8 {
+        match self {
+            QuantBits::Int8 => 8,
+            QuantBits::Int4 => 4,
+        }
+    }
+
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        match bits {
+            8 => Some(QuantBits::Int8),
+            4 => Some(QuantBits::Int4),
+            _ => None,
+        }
+    }
+
+    fn max_level(self) -> u8 {
+        match self {
+            QuantBits::Int8 => u8::MAX,
+            QuantBits::Int4 => 15,
+        }
+    }
+
+    fn packed_len(self, values: usize) -> usize {
+        match self {
+            QuantBits::Int8 => values,
+            QuantBits::Int4 => values.div_ceil(2),
+        }
+    }
+}
+
+/// A factor matrix quantized column by column
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuantizedFactor {
+    pub bits: QuantBits,
+    pub rows: usize,
+    pub cols: usize,
+    pub scales: Vec<f32>,
+    pub zero_points: Vec<u8>,
+    /// Column-major levels (see module docs for the packing)
+    pub data: Vec<u8>,
+}
+
+impl QuantizedFactor {
+    pub fn quantize(x: &DMatrix<f64>, bits: QuantBits) -> Self {
+        let (rows, cols) = x.shape();
+        let max_level = bits.max_level() as f64;
+        let mut scales = Vec::with_capacity(cols);
+        let mut zero_points = Vec::with_capacity(cols);
+        let mut levels = Vec::with_capacity(rows * cols);
+        for column in x.column_iter() {
+            let lo = column.min().min(0.0);
+            let hi = column.max().max(0.0);
+            let scale = if hi > lo { ((hi - lo) / max_level) as f32 } else { 1.0 };
+            let zero_point = (-lo / scale as f64).round().clamp(0.0, max_level);
+            for &value in column.iter() {
+                let q = (value / scale as f64).round() + zero_point;
+                levels.push(q.clamp(0.0, max_level) as u8);
+            }
+            scales.push(scale);
+            zero_points.push(zero_point as u8);
+        }
+        let data = match bits {
+            QuantBits::Int8 => levels,
+            QuantBits::Int4 => levels.chunks(2)
+                .map(|pair| pair[0] | pair.get(1).map_or(0, |hi| hi << 4))
+                .collect(),
+        };
+        Self { bits, rows, cols, scales, zero_points, data }
+    }
+
+    pub fn level(&self, row: usize, col: usize) -> u8 {
+        let k = col * self.rows + row;
+        match self.bits {
+            QuantBits::Int8 => self.data[k],
+            QuantBits::Int4 => (self.data[k / 2] >> (4 * (k % 2))) & 0x0f,
+        }
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> f64 {
+        let q = self.level(row, col) as f64 - self.zero_points[col] as f64;
+        q * self.scales[col] as f64
+    }
+
+    pub fn dequantize(&self) -> DMatrix<f64> {
+        DMatrix::from_fn(self.rows, self.cols, |i, j| self.get(i, j))
+    }
+
+    /// Xᵀ·x without dequantizing: (Xᵀx)ⱼ = scaleⱼ · (Σᵢ qᵢⱼxᵢ − zpⱼ Σᵢ xᵢ)
+    pub fn transpose_mul(&self, x: &DVector<f64>) -> DVector<f64> {
+        let total = x.sum();
+        DVector::from_fn(self.cols, |j, _| {
+            let dot: f64 = (0..self.rows).map(|i| self.level(i, j) as f64 * x[i]).sum();
+            self.scales[j] as f64 * (dot - self.zero_points[j] as f64 * total)
+        })
+    }
+
+    /// X·w without dequantizing
+    pub fn mul(&self, w: &DVector<f64>) -> DVector<f64> {
+        let mut out = DVector::zeros(self.rows);
+        for j in 0..self.cols {
+            let weight = self.scales[j] as f64 * w[j];
+            let zero_point = self.zero_points[j] as f64;
+            for i in 0..self.rows {
+                out[i] += (self.level(i, j) as f64 - zero_point) * weight;
+            }
+        }
+        out
+    }
+
+    fn has_shape(&self, bits: QuantBits, rows: usize, cols: usize) -> bool {
+        self.bits == bits && self.rows == rows && self.cols == cols
+            && self.scales.len() == cols && self.zero_points.len() == cols
+            && self.data.len() == bits.packed_len(rows * cols)
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        for scale in &self.scales { out.extend_from_slice(&scale.to_le_bytes()); }
+        out.extend_from_slice(&self.zero_points);
+        out.extend_from_slice(&self.data);
+    }
+
+    fn decode(reader: &mut Reader<'_>, bits: QuantBits, rows: usize, cols: usize) -> Option<Self> {
+        let scales = (0..cols)
+            .map(|_| reader.take(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())))
+            .collect::<Option<Vec<_>>>()?;
+        let zero_points = reader.take(cols)?.to_vec();
+        let data = reader.take(bits.packed_len(rows.checked_mul(cols)?))?.to_vec();
+        Some(Self { bits, rows, cols, scales, zero_points, data })
+    }
+}
+
+/// A LowRankIdentity with quantized U and V
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuantizedLrim {
+    pub u: QuantizedFactor,
+    pub sigma: DVector<f64>,
+    pub v: QuantizedFactor,
+    pub rank: usize,
+    pub m: usize,
+    pub n: usize,
+    /// ‖K − K_q‖_F against the source factors, measured when quantizing
+    pub error: f64,
+}
+
+impl LowRankIdentity {
+    pub fn quantize(&self, bits: QuantBits) -> QuantizedLrim {
+        let mut q = QuantizedLrim {
+            u: QuantizedFactor::quantize(&self.u, bits),
+            sigma: self.sigma.clone(),
+            v: QuantizedFactor::quantize(&self.v, bits),
+            rank: self.rank,
+            m: self.m,
+            n: self.n,
+            error: 0.0,
+        };
+        q.error = (self.reconstruct() - q.dequantize().reconstruct()).norm();
+        q
+    }
+}
+
+impl QuantizedLrim {
+    pub fn bits(&self) -> QuantBits {
+        self.u.bits
+    }
+
+    pub fn dequantize(&self) -> LowRankIdentity {
+        LowRankIdentity::new(self.u.dequantize(), self.sigma.clone(), self.v.dequantize())
+    }
+
+    /// K_q · x evaluated on the quantized levels
+    pub fn express_on(&self, input: &DVector<f64>) -> DVector<f64> {
+        let z = self.v.transpose_mul(input).component_mul(&self.sigma);
+        self.u.mul(&z)
+    }
+
+    /// ‖original − K_q‖_F
+    pub fn reconstruction_error(&self, original: &DMatrix<f64>) -> f64 {
+        self.dequantize().reconstruction_error(original)
+    }
+
+    /// Shapes and payload lengths agree with each other
+    pub fn is_consistent(&self) -> bool {
+        let bits = self.bits();
+        self.sigma.len() == self.rank
+            && self.u.has_shape(bits, self.m, self.rank)
+            && self.v.has_shape(bits, self.n, self.rank)
+    }
+
+    /// The payload as transmitted (see module docs for the layout)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(
+            QUANTIZED_ENCODING_TAG.len() + 33 + 8 * self.rank + 2 * (5 * self.rank)
+                + self.u.data.len() + self.v.data.len(),
+        );
+        out.extend_from_slice(QUANTIZED_ENCODING_TAG);
+        out.push(self.bits().bits());
+        for dim in [self.m, self.n, self.rank] {
+            out.extend_from_slice(&(dim as u64).to_le_bytes());
+        }
+        out.extend_from_slice(&self.error.to_le_bytes());
+        for s in self.sigma.iter() { out.extend_from_slice(&s.to_le_bytes()); }
+        self.u.encode(&mut out);
+        self.v.encode(&mut out);
+        out
+    }
+
+    /// Parse a payload; `None` if it is truncated, malformed or has trailing bytes
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader(bytes);
+        if reader.take(QUANTIZED_ENCODING_TAG.len())? != QUANTIZED_ENCODING_TAG { return None; }
+        let bits = QuantBits::from_bits(reader.take(1)?[0])?;
+        let m = reader.u64()? as usize;
+        let n = reader.u64()? as usize;
+        let rank = reader.u64()? as usize;
+        let error = reader.f64()?;
+        let sigma = (0..rank).map(|_| reader.f64()).collect::<Option<Vec<_>>>()?;
+        let u = QuantizedFactor::decode(&mut reader, bits, m, rank)?;
+        let v = QuantizedFactor::decode(&mut reader, bits, n, rank)?;
+        if !reader.0.is_empty() { return None; }
+        Some(Self { u, sigma: DVector::from_vec(sigma), v, rank, m, n, error })
+    }
+
+    /// SHA256 of the payload bytes (what commitments bind to)
+    pub fn payload_hash(&self) -> String {
+        hex::encode(Sha256::digest(self.to_bytes()))
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len { return None; }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.take(8).map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+    }
+}
+
+/// A DnaSeed as sent over the network: quantized factors, with the
+/// commitment bound to the quantized payload rather than the f64 source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuantizedSeed {
+    pub id: String,
+    pub name: String,
+    pub payload: QuantizedLrim,
+    pub express: Vec<Instruction>,
+    pub mutation: MutationRules,
+    pub replication: ReplicationPolicy,
+    pub commitment: ZkCommitment,
+    pub lineage: Lineage,
+    pub epoch: u64,
+    pub fitness: f64,
+    pub domains: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub mutated_at: Option<DateTime<Utc>>,
+}
+
+impl DnaSeed {
+    /// Quantize for transmission. The drift monitor stays local.
+    pub fn quantize(&self, bits: QuantBits) -> QuantizedSeed {
+        let payload = self.lrim.quantize(bits);
+        QuantizedSeed {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            commitment: ZkCommitment::from_quantized(&payload),
+            payload,
+            express: self.express.clone(),
+            mutation: self.mutation.clone(),
+            replication: self.replication.clone(),
+            lineage: self.lineage.clone(),
+            epoch: self.epoch,
+            fitness: self.fitness,
+            domains: self.domains.clone(),
+            created_at: self.created_at,
+            mutated_at: self.mutated_at,
+        }
+    }
+}
+
+impl QuantizedSeed {
+    /// The payload is well-formed and matches the commitment
+    pub fn verify(&self) -> bool {
+        self.payload.is_consistent() && self.commitment.verify_quantized(&self.payload)
+    }
+
+    /// Expand into a working seed with dequantized factors; `None` unless
+    /// the payload matches the sender's commitment. The seed is re-committed
+    /// locally to the dequantized factors, recorded in its lineage, and
+    /// keeps the sender's binding as `received_commitment`.
+    pub fn into_seed(self) -> Option<DnaSeed> {
+        if !self.verify() { return None; }
+        let mut seed = DnaSeed {
+            id: self.id,
+            name: self.name,
+            lrim: self.payload.dequantize(),
+            express: self.express,
+            mutation: self.mutation,
+            replication: self.replication,
+            commitment: self.commitment.clone(),
+            opening: None,
+            lineage: self.lineage,
+            epoch: self.epoch,
+            fitness: self.fitness,
+            domains: self.domains,
+            created_at: self.created_at,
+            mutated_at: self.mutated_at,
+            drift: None,
+            tensor: None,
+            received_commitment: Some(self.commitment),
+        };
+        seed.recommit_recorded("dequantized");
+        Some(seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantized_payload() {
+        let k = DMatrix::new_random(40, 30);
+        let seed = DnaSeed::new("compact", &k, 6, vec!["test".into()]);
+        let exact = seed.lrim.reconstruct();
+        let x = DVector::new_random(30);
+
+        let int8 = seed.quantize(QuantBits::Int8);
+        let int4 = seed.quantize(QuantBits::Int4);
+        assert!(int8.verify() && int4.verify());
+        let measured = (int8.payload.dequantize().reconstruct() - &exact).norm();
+        assert!((int8.payload.error - measured).abs() < 1e-12);
+        assert!(int8.payload.error < 0.02 * exact.norm());
+        assert!(int8.payload.error < int4.payload.error);
+        let direct = int8.payload.express_on(&x);
+        assert!((direct - int8.payload.dequantize().reconstruct() * &x).norm() < 1e-9);
+
+        let bytes = int4.payload.to_bytes();
+        let json = serde_json::to_vec(&seed.lrim).unwrap();
+        assert!(bytes.len() * 10 < json.len());
+        assert_eq!(QuantizedLrim::from_bytes(&bytes).as_ref(), Some(&int4.payload));
+        assert!(QuantizedLrim::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+
+        let mut tampered = int8.clone();
+        tampered.payload.u.data[0] ^= 1;
+        assert!(!tampered.verify());
+        assert!(tampered.into_seed().is_none());
+        let expanded = int8.clone().into_seed().unwrap();
+        assert!(expanded.verify_commitment());
+        // The sender's binding survives the local re-commit
+        assert_eq!(expanded.received_commitment.as_ref().unwrap().matrix_hash, int8.commitment.matrix_hash);
+        assert!(expanded.received_commitment.as_ref().unwrap().verify_quantized(&int8.payload));
+        assert!(matches!(
+            &expanded.lineage.events.last().unwrap().event_type,
+            crate::seed::LineageEventType::Recommit { previous, current, .. }
+                if *previous == int8.commitment.matrix_hash && *current == expanded.commitment.matrix_hash
+        ));
+    }
+}
@@ -1,7 +1,112 @@
 //! Replication policy — when and how seeds copy themselves
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// How a merged seed's fitness is derived from its parents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeRule {
+    /// Plain average of both parents
+    Average,
+    /// Weighted by each parent's rank
+    RankWeighted,
+    /// Weighted by each parent's ‖K‖²_F
+    EnergyWeighted,
+}
+
+/// How fitness passes from parents to children and merged seeds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FitnessInheritance {
+    /// Child fitness = parent fitness × discount
+    pub discount: f64,
+    /// Epochs a derived seed spends on probation before its fitness counts fully
+    pub probation_epochs: u64,
+    /// Multiplier applied to a seed's fitness while on probation
+    pub probation_factor: f64,
+    pub merge_rule: MergeRule,
+}
+
+impl Default for FitnessInheritance {
+    fn default() -> Self {
+        Self {
+            discount: 0.9,
+            probation_epochs: 0,
+            probation_factor: 1.0,
+            merge_rule: MergeRule::Average,
+        }
+    }
+}
+
+impl FitnessInheritance {
+    /// Combine two parents' fitness; weights are rank or energy depending on the rule
+    pub fn merge_fitness(&self, a: (f64, f64), b: (f64, f64)) -> f64 {
+        let ((fa, wa), (fb, wb)) = (a, b);
+        if self.merge_rule == MergeRule::Average || wa + wb <= 0.0 {
+            return (fa + fb) / 2.0;
+        }
+        (fa * wa + fb * wb) / (wa + wb)
+    }
+}
+
+/// Time-based fitness decay since a seed was last created or mutated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "FitnessDecayRepr")]
+pub struct FitnessDecay {
+    /// Half-life of fitness in hours; `None` disables decay
+    half_life_hours: Option<f64>,
+    /// Decay never pushes fitness below this fraction of its stored value
+    floor: f64,
+}
+
+/// Wire form of a FitnessDecay, checked before it becomes one
+#[derive(Deserialize)]
+struct FitnessDecayRepr {
+    half_life_hours: Option<f64>,
+    floor: f64,
+}
+
+impl TryFrom<FitnessDecayRepr> for FitnessDecay {
+    type Error = String;
+
+    fn try_from(repr: FitnessDecayRepr) -> Result<Self, Self::Error> {
+        match repr.half_life_hours {
+            None if (0.0..=1.0).contains(&repr.floor) => Ok(Self { half_life_hours: None, floor: repr.floor }),
+            None => Err(format!("decay floor {} is not in [0, 1]", repr.floor)),
+            Some(hours) => Self::new(hours, repr.floor)
+                .ok_or_else(|| format!("invalid decay: half-life {hours} h, floor {}", repr.floor)),
+        }
+    }
+}
+
+impl Default for FitnessDecay {
+    fn default() -> Self {
+        Self { half_life_hours: None, floor: 0.0 }
+    }
+}
+
+impl FitnessDecay {
+    /// `None` unless the half-life is finite and positive and the floor
+    /// lies in [0, 1]; anything else would let stale seeds gain fitness
+    pub fn new(half_life_hours: f64, floor: f64) -> Option<Self> {
+        (half_life_hours.is_finite() && half_life_hours > 0.0 && (0.0..=1.0).contains(&floor))
+            .then_some(Self { half_life_hours: Some(half_life_hours), floor })
+    }
+
+    pub fn half_life_hours(&self) -> Option<f64> {
+        self.half_life_hours
+    }
+
+    pub fn floor(&self) -> f64 {
+        self.floor
+    }
+
+    pub fn factor(&self, last_active: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+        let Some(half_life) = self.half_life_hours else { return 1.0 };
+        let hours = (now - last_active).num_seconds().max(0) as f64 / 3600.0;
+        0.5f64.powf(hours / half_life).max(self.floor)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReplicationPolicy {
     pub min_fitness: f64,
@@ -9,6 +114,10 @@ pub struct ReplicationPolicy {
     pub max_children: usize,
     pub children_produced: usize,
     pub enabled: bool,
+    #[serde(default)]
+    pub inheritance: FitnessInheritance,
+    #[serde(default)]
+    pub decay: FitnessDecay,
 }
 
 impl Default for ReplicationPolicy {
@@ -19,6 +128,8 @@ impl Default for ReplicationPolicy {
             max_children: 3,
             children_produced: 0,
             enabled: true,
+            inheritance: FitnessInheritance::default(),
+            decay: FitnessDecay::default(),
         }
     }
 }
@@ -42,6 +153,8 @@ impl ReplicationPolicy {
             max_children: 10,
             children_produced: 0,
             enabled: true,
+            inheritance: FitnessInheritance::default(),
+            decay: FitnessDecay::default(),
         }
     }
 }
@@ -0,0 +1,164 @@
+//! Scalar types a LowRankIdentity can be stored in
+//!
+//! `f64` is the working precision for factorisation and training. `f32`
+//! halves memory and payload size. `Fixed` is a deterministic Q32.32
+//! fixed-point number: every operation is integer arithmetic, so results are
+//! bit-identical on every platform, and its values map one-to-one onto the
+//! fixed-point field encodings used by commitments and circuits.
+//!
+//! Conversions go through f64. Widening to f64 is lossless (for `Fixed`, for
+//! magnitudes below 2^21); narrowing rounds each value by at most
+//! `rounding_error(|x|)`.
+
+use super::encoding::{from_fixed, to_fixed, FIXED_POINT_FRAC_BITS};
+use nalgebra::{ClosedAddAssign, ClosedMulAssign, ClosedSubAssign, Scalar};
+use num_traits::{One, Zero};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// A scalar a LowRankIdentity can be stored and evaluated in
+pub trait LrimScalar:
+    Scalar + Copy + PartialOrd + Zero + One
+    + ClosedAddAssign + ClosedSubAssign + ClosedMulAssign + Neg<Output = Self>
+    + Serialize + DeserializeOwned + Send + Sync
+{
+    const NAME: &'static str;
+
+    /// Nearest representable value (saturating where the range is bounded)
+    fn from_f64(x: f64) -> Self;
+    fn to_f64(self) -> f64;
+    /// Largest |from_f64(x) − x| over finite x with |x| ≤ `magnitude`;
+    /// infinite if such x may fall outside the representable range
+    fn rounding_error(magnitude: f64) -> f64;
+}
+
+impl LrimScalar for f64 {
+    const NAME: &'static str = "f64";
+
+    fn from_f64(x: f64) -> Self { x }
+    fn to_f64(self) -> f64 { self }
+    fn rounding_error(_magnitude: f64) -> f64 { 0.0 }
+}
+
+impl LrimScalar for f32 {
+    const NAME: &'static str = "f32";
+
+    fn from_f64(x: f64) -> Self { x as f32 }
+    fn to_f64(self) -> f64 { self as f64 }
+    fn rounding_error(magnitude: f64) -> f64 {
+        if magnitude > f32::MAX as f64 { return f64::INFINITY; }
+        // Half an ulp, or half the smallest subnormal below the normal range
+        (magnitude * f32::EPSILON as f64 / 2.0).max((-150f64).exp2())
+    }
+}
+
+/// Deterministic Q32.32 fixed-point number: the value is `raw / 2^32`.
+///
+/// Arithmetic saturates at the ends of the range instead of wrapping, and
+/// products are rounded to nearest.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Fixed(pub i64);
+
+impl Fixed {
+    pub const FRAC_BITS: u32 = FIXED_POINT_FRAC_BITS;
+    pub const MAX: Fixed = Fixed(i64::MAX);
+    pub const MIN: Fixed = Fixed(i64::MIN);
+    /// Smallest positive value, 2^-32
+    pub const EPSILON: Fixed = Fixed(1);
+
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+}
+
+impl fmt::Debug for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Fixed({})", self.to_f64())
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl LrimScalar for Fixed {
+    const NAME: &'static str = "q32.32";
+
+    fn from_f64(x: f64) -> Self { Fixed(to_fixed(x, Self::FRAC_BITS)) }
+    fn to_f64(self) -> f64 { from_fixed(self.0, Self::FRAC_BITS) }
+    fn rounding_error(magnitude: f64) -> f64 {
+        if magnitude >= (63.0 - Self::FRAC_BITS as f64).exp2() { return f64::INFINITY; }
+        (-(Self::FRAC_BITS as f64) - 1.0).exp2()
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, rhs: Fixed) -> Fixed { Fixed(self.0.saturating_add(rhs.0)) }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, rhs: Fixed) -> Fixed { Fixed(self.0.saturating_sub(rhs.0)) }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, rhs: Fixed) -> Fixed {
+        let half = 1i128 << (Self::FRAC_BITS - 1);
+        let product = (self.0 as i128 * rhs.0 as i128 + half) >> Self::FRAC_BITS;
+        Fixed(product.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed { Fixed(self.0.saturating_neg()) }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Fixed) { *self = *self + rhs; }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Fixed) { *self = *self - rhs; }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, rhs: Fixed) { *self = *self * rhs; }
+}
+
+impl Zero for Fixed {
+    fn zero() -> Self { Fixed(0) }
+    fn is_zero(&self) -> bool { self.0 == 0 }
+}
+
+impl One for Fixed {
+    fn one() -> Self { Fixed(1 << Self::FRAC_BITS) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_arithmetic() {
+        let a = Fixed::from_f64(1.5);
+        let b = Fixed::from_f64(-2.25);
+        assert_eq!((a * b).to_f64(), -3.375);
+        assert_eq!((a + b).to_f64(), -0.75);
+        assert_eq!(Fixed::MAX + Fixed::one(), Fixed::MAX);
+        assert_eq!(Fixed::from_f64(1e12), Fixed::MAX);
+        assert_eq!(Fixed::from_f64(-0.0), Fixed::zero());
+
+        for x in [0.1, -1.0 / 3.0, 12_345.678_9] {
+            assert!((Fixed::from_f64(x).to_f64() - x).abs() <= Fixed::rounding_error(x.abs()));
+            assert!((f32::from_f64(x).to_f64() - x).abs() <= f32::rounding_error(x.abs()));
+        }
+    }
+}
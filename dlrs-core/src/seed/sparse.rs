@@ -0,0 +1,230 @@
+//! Sparse knowledge matrices
+//!
+//! Term–document and user–skill matrices are mostly zeros. Seeds can be
+//! factorised from COO or CSR input by a randomized SVD (Halko, Martinsson &
+//! Tropp) that only touches the input through products with thin dense
+//! blocks, so the input is never densified.
+
+use super::{DnaSeed, LowRankIdentity};
+use nalgebra::DMatrix;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// Read access to a sparse matrix through its stored entries
+pub trait SparseMatrix {
+    fn shape(&self) -> (usize, usize);
+    fn nnz(&self) -> usize;
+    /// Stored (row, col, value) triples; duplicates add up
+    fn entries(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_;
+
+    /// A · X for a dense X with `shape().1` rows
+    fn mul_dense(&self, x: &DMatrix<f64>) -> DMatrix<f64> {
+        let mut out = DMatrix::zeros(self.shape().0, x.ncols());
+        for (i, j, a) in self.entries() {
+            for c in 0..x.ncols() { out[(i, c)] += a * x[(j, c)]; }
+        }
+        out
+    }
+
+    /// Aᵀ · Y for a dense Y with `shape().0` rows
+    fn transpose_mul_dense(&self, y: &DMatrix<f64>) -> DMatrix<f64> {
+        let mut out = DMatrix::zeros(self.shape().1, y.ncols());
+        for (i, j, a) in self.entries() {
+            for c in 0..y.ncols() { out[(j, c)] += a * y[(i, c)]; }
+        }
+        out
+    }
+
+    fn to_dense(&self) -> DMatrix<f64> {
+        let (rows, cols) = self.shape();
+        let mut out = DMatrix::zeros(rows, cols);
+        for (i, j, a) in self.entries() { out[(i, j)] += a; }
+        out
+    }
+}
+
+/// Coordinate-format matrix: an unordered list of entries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CooMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub entries: Vec<(usize, usize, f64)>,
+}
+
+impl CooMatrix {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self { rows, cols, entries: Vec::new() }
+    }
+
+    pub fn push(&mut self, row: usize, col: usize, value: f64) {
+        assert!(row < self.rows && col < self.cols, "Entry ({row}, {col}) out of bounds");
+        self.entries.push((row, col, value));
+    }
+}
+
+impl SparseMatrix for CooMatrix {
+    fn shape(&self) -> (usize, usize) { (self.rows, self.cols) }
+    fn nnz(&self) -> usize { self.entries.len() }
+    fn entries(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        self.entries.iter().copied()
+    }
+}
+
+/// Compressed sparse row matrix; row `i` owns
+/// `col_indices[row_offsets[i]..row_offsets[i + 1]]` (sorted, no duplicates)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CsrMatrix {
+    pub rows: usize,
+    pub cols: usize,
+    pub row_offsets: Vec<usize>,
+    pub col_indices: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl From<&CooMatrix> for CsrMatrix {
+    /// Sorts entries and sums duplicates
+    fn from(coo: &CooMatrix) -> Self {
+        let mut entries = coo.entries.clone();
+        entries.sort_by_key(|&(i, j, _)| (i, j));
+        let mut row_offsets = vec![0; coo.rows + 1];
+        let mut col_indices: Vec<usize> = Vec::with_capacity(entries.len());
+        let mut values: Vec<f64> = Vec::with_capacity(entries.len());
+        let mut last = None;
+        for (i, j, a) in entries {
+            if last == Some((i, j)) {
+                *values.last_mut().unwrap() += a;
+                continue;
+            }
+            last = Some((i, j));
+            row_offsets[i + 1] += 1;
+            col_indices.push(j);
+            values.push(a);
+        }
+        for i in 0..coo.rows { row_offsets[i + 1] += row_offsets[i]; }
+        Self { rows: coo.rows, cols: coo.cols, row_offsets, col_indices, values }
+    }
+}
+
+impl CsrMatrix {
+    pub fn row(&self, i: usize) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let range = self.row_offsets[i]..self.row_offsets[i + 1];
+        self.col_indices[range.clone()].iter().copied().zip(self.values[range].iter().copied())
+    }
+}
+
+impl SparseMatrix for CsrMatrix {
+    fn shape(&self) -> (usize, usize) { (self.rows, self.cols) }
+    fn nnz(&self) -> usize { self.values.len() }
+    fn entries(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        (0..self.rows).flat_map(move |i| self.row(i).map(move |(j, a)| (i, j, a)))
+    }
+
+    fn mul_dense(&self, x: &DMatrix<f64>) -> DMatrix<f64> {
+        let mut out = DMatrix::zeros(self.rows, x.ncols());
+        for c in 0..x.ncols() {
+            for i in 0..self.rows {
+                out[(i, c)] = self.row(i).map(|(j, a)| a * x[(j, c)]).sum();
+            }
+        }
+        out
+    }
+}
+
+/// Parameters of the randomized range finder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RandomizedSvdConfig {
+    /// Extra sketch columns beyond the target rank
+    pub oversampling: usize,
+    /// Power iterations (A Aᵀ)^q sharpening a slowly decaying spectrum
+    pub power_iterations: usize,
+    /// Seed of the test matrix, for reproducible factorisations
+    pub seed: u64,
+}
+
+impl Default for RandomizedSvdConfig {
+    fn default() -> Self {
+        Self { oversampling: 10, power_iterations: 2, seed: 0 }
+    }
+}
+
+impl LowRankIdentity {
+    /// Truncated SVD of a sparse matrix by randomized range finding: a sketch
+    /// Q of the range of A, then the factor pair K = Q · (AᵀQ)ᵀ
+    pub fn from_sparse(a: &impl SparseMatrix, target_rank: usize, config: &RandomizedSvdConfig) -> Self {
+        let (m, n) = a.shape();
+        let width = (target_rank + config.oversampling).min(m.min(n));
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let omega = DMatrix::from_fn(n, width, |_, _| rng.gen_range(-1.0..1.0));
+        let mut q = a.mul_dense(&omega).qr().q();
+        for _ in 0..config.power_iterations {
+            let z = a.transpose_mul_dense(&q).qr().q();
+            q = a.mul_dense(&z).qr().q();
+        }
+        let bt = a.transpose_mul_dense(&q);
+        let lrim = Self::from_factor_pair(&q, &bt, 1e-12);
+        let keep: Vec<usize> = (0..target_rank.min(lrim.rank)).collect();
+        lrim.select_components(&keep)
+    }
+
+    /// ‖A − K‖_F for a sparse A, from ‖A‖² − 2⟨A, K⟩ + ‖K‖² in O(nnz · r).
+    /// Absolute accuracy is about ε‖A‖², so errors far below
+    /// √ε·‖A‖_F read as zero.
+    pub fn sparse_reconstruction_error(&self, original: &impl SparseMatrix) -> f64 {
+        let us = &self.u * DMatrix::from_diagonal(&self.sigma);
+        let mut norm_sq = 0.0;
+        let mut inner = 0.0;
+        for (i, j, a) in original.entries() {
+            norm_sq += a * a;
+            inner += a * us.row(i).dot(&self.v.row(j));
+        }
+        (norm_sq - 2.0 * inner + self.energy()).max(0.0).sqrt()
+    }
+}
+
+impl DnaSeed {
+    /// Seed from a sparse knowledge matrix, without densifying it
+    pub fn from_sparse(
+        name: impl Into<String>,
+        knowledge: &impl SparseMatrix,
+        rank: usize,
+        domains: Vec<String>,
+    ) -> Self {
+        let lrim = LowRankIdentity::from_sparse(knowledge, rank, &RandomizedSvdConfig::default());
+        Self::from_lrim(name, lrim, domains)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_factorization() {
+        // Exactly rank 3: outer products of vectors with disjoint supports
+        let mut coo = CooMatrix::new(300, 200);
+        for block in 0..3 {
+            for i in (block * 100..block * 100 + 100).step_by(7) {
+                for j in (block * 60..block * 60 + 60).step_by(5) {
+                    coo.push(i, j, (block + 1) as f64 * ((i % 5) as f64 + 1.0) * ((j % 3) as f64 + 0.5));
+                }
+            }
+        }
+        coo.push(0, 0, 0.0);
+        let csr = CsrMatrix::from(&coo);
+        assert_eq!(csr.nnz(), coo.nnz() - 1);
+        assert!(csr.nnz() * 50 < 300 * 200);
+
+        let seed = DnaSeed::from_sparse("sparse", &csr, 3, vec!["test".into()]);
+        let dense = csr.to_dense();
+        assert!(seed.lrim.reconstruction_error(&dense) < 1e-8 * dense.norm());
+        let expected = LowRankIdentity::from_matrix(&dense, 3);
+        assert!((&seed.lrim.sigma - &expected.sigma).norm() < 1e-8 * expected.sigma.norm());
+
+        // Sparse error evaluation agrees with the dense one
+        let truncated = LowRankIdentity::from_sparse(&coo, 2, &RandomizedSvdConfig::default());
+        let dense_error = truncated.reconstruction_error(&dense);
+        assert!((truncated.sparse_reconstruction_error(&coo) - dense_error).abs() < 1e-8 * dense.norm());
+        assert!((truncated.sparse_reconstruction_error(&csr) - dense_error).abs() < 1e-8 * dense.norm());
+    }
+}
@@ -0,0 +1,349 @@
+//! Out-of-core factorization of matrices too large for memory
+//!
+//! The input is read as consecutive blocks of rows and only ever touched
+//! through randomized sketches, so memory stays at O((m + n) · (r + p)) for
+//! oversampling p, plus one block.
+//!
+//! * Two-pass (plus two per power iteration): Y = AΩ, Q = qr(Y), then
+//!   Bᵀ = AᵀQ, K = Q · B. Needs a source that can be rewound.
+//! * Single pass (Tropp et al., "Practical sketching algorithms for low-rank
+//!   matrix approximation"): Y = AΩ and W = ΨA are accumulated together,
+//!   then K = Q · (ΨQ)⁺W. Works on streams read once; less accurate when
+//!   the spectrum decays slowly.
+//!
+//! Matrix files are a 24-byte header followed by the values (little-endian):
+//!
+//! ```text
+//! magic  8 bytes  "DLRSMAT1"
+//! rows   u64
+//! cols   u64
+//! data   rows · cols × f64, row-major
+//! ```
+
+use super::{LowRankIdentity, RandomizedSvdConfig};
+use nalgebra::DMatrix;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const MATRIX_FILE_MAGIC: &[u8; 8] = b"DLRSMAT1";
+const HEADER_LEN: u64 = 24;
+
+/// A matrix delivered as consecutive blocks of rows
+pub trait RowBlockSource {
+    fn cols(&self) -> usize;
+    /// Restart from the first row; sources that can only be read once fail
+    fn rewind(&mut self) -> io::Result<()>;
+    /// The next block of rows (`cols()` columns), `None` after the last row
+    fn next_block(&mut self) -> io::Result<Option<DMatrix<f64>>>;
+}
+
+/// Row blocks from an iterator, readable once
+pub struct IterBlocks<I> {
+    blocks: I,
+    cols: usize,
+}
+
+impl<I: Iterator<Item = DMatrix<f64>>> IterBlocks<I> {
+    pub fn new(blocks: I, cols: usize) -> Self {
+        Self { blocks, cols }
+    }
+}
+
+impl<I: Iterator<Item = DMatrix<f64>>> RowBlockSource for IterBlocks<I> {
+    fn cols(&self) -> usize { self.cols }
+
+    fn rewind(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "iterator sources cannot be rewound"))
+    }
+
+    fn next_block(&mut self) -> io::Result<Option<DMatrix<f64>>> {
+        match self.blocks.next() {
+            Some(block) if block.ncols() != self.cols => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("block has {} columns, expected {}", block.ncols(), self.cols),
+            )),
+            block => Ok(block),
+        }
+    }
+}
+
+/// Reads a matrix file `block_rows` rows at a time
+pub struct MatrixFileSource {
+    reader: BufReader<File>,
+    pub rows: usize,
+    pub cols: usize,
+    pub block_rows: usize,
+    next_row: usize,
+}
+
+impl MatrixFileSource {
+    /// Fails unless the file holds exactly the rows · cols values its
+    /// header declares
+    pub fn open(path: impl AsRef<Path>, block_rows: usize) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+        let mut header = [0u8; HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        if &header[..8] != MATRIX_FILE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a DLRS matrix file"));
+        }
+        let rows = u64::from_le_bytes(header[8..16].try_into().unwrap());
+        let cols = u64::from_le_bytes(header[16..24].try_into().unwrap());
+        let expected = rows.checked_mul(cols).and_then(|n| n.checked_mul(8)).and_then(|n| n.checked_add(HEADER_LEN));
+        if expected != Some(len) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("header declares {rows} × {cols} values, file is {len} bytes"),
+            ));
+        }
+        let (rows, cols) = match (usize::try_from(rows), usize::try_from(cols)) {
+            (Ok(rows), Ok(cols)) => (rows, cols),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "matrix too large for this platform")),
+        };
+        Ok(Self { reader, rows, cols, block_rows: block_rows.max(1), next_row: 0 })
+    }
+}
+
+impl RowBlockSource for MatrixFileSource {
+    fn cols(&self) -> usize { self.cols }
+
+    fn rewind(&mut self) -> io::Result<()> {
+        self.reader.seek(SeekFrom::Start(HEADER_LEN))?;
+        self.next_row = 0;
+        Ok(())
+    }
+
+    fn next_block(&mut self) -> io::Result<Option<DMatrix<f64>>> {
+        let count = self.block_rows.min(self.rows - self.next_row);
+        if count == 0 { return Ok(None); }
+        let mut bytes = vec![0u8; count * self.cols * 8];
+        self.reader.read_exact(&mut bytes)?;
+        let values = bytes.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap()));
+        self.next_row += count;
+        Ok(Some(DMatrix::from_row_iterator(count, self.cols, values)))
+    }
+}
+
+/// Writes a matrix file block by block; `finish` checks the row count
+pub struct MatrixFileWriter {
+    writer: BufWriter<File>,
+    rows: usize,
+    cols: usize,
+    written: usize,
+}
+
+impl MatrixFileWriter {
+    pub fn create(path: impl AsRef<Path>, rows: usize, cols: usize) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MATRIX_FILE_MAGIC)?;
+        writer.write_all(&(rows as u64).to_le_bytes())?;
+        writer.write_all(&(cols as u64).to_le_bytes())?;
+        Ok(Self { writer, rows, cols, written: 0 })
+    }
+
+    pub fn write_block(&mut self, block: &DMatrix<f64>) -> io::Result<()> {
+        if block.ncols() != self.cols || self.written + block.nrows() > self.rows {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "block does not fit the declared shape"));
+        }
+        for row in block.row_iter() {
+            for x in row.iter() { self.writer.write_all(&x.to_le_bytes())?; }
+        }
+        self.written += block.nrows();
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.written != self.rows {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("wrote {} of {} rows", self.written, self.rows),
+            ));
+        }
+        self.writer.flush()
+    }
+}
+
+fn random_matrix(rng: &mut StdRng, rows: usize, cols: usize) -> DMatrix<f64> {
+    DMatrix::from_fn(rows, cols, |_, _| rng.gen_range(-1.0..1.0))
+}
+
+/// Stack row blocks collected during a pass
+fn stack_rows(blocks: &[DMatrix<f64>], cols: usize) -> DMatrix<f64> {
+    let rows = blocks.iter().map(|b| b.nrows()).sum();
+    let mut out = DMatrix::zeros(rows, cols);
+    let mut start = 0;
+    for block in blocks {
+        out.rows_mut(start, block.nrows()).copy_from(block);
+        start += block.nrows();
+    }
+    out
+}
+
+/// Y = A · Ω over one pass
+fn sketch_range(source: &mut impl RowBlockSource, omega: &DMatrix<f64>) -> io::Result<DMatrix<f64>> {
+    let mut blocks = Vec::new();
+    while let Some(block) = source.next_block()? {
+        blocks.push(block * omega);
+    }
+    Ok(stack_rows(&blocks, omega.ncols()))
+}
+
+/// Aᵀ · Q over one pass; the pass must see exactly Q's rows
+fn sketch_corange(source: &mut impl RowBlockSource, q: &DMatrix<f64>) -> io::Result<DMatrix<f64>> {
+    let mut out = DMatrix::zeros(source.cols(), q.ncols());
+    let mut start = 0;
+    while let Some(block) = source.next_block()? {
+        if start + block.nrows() > q.nrows() {
+            return Err(rows_changed(q.nrows(), start + block.nrows()));
+        }
+        out += block.transpose() * q.rows(start, block.nrows());
+        start += block.nrows();
+    }
+    if start != q.nrows() { return Err(rows_changed(q.nrows(), start)); }
+    Ok(out)
+}
+
+/// Y = A · Ω over a later pass, which must see the rows of the first
+fn resketch_range(source: &mut impl RowBlockSource, omega: &DMatrix<f64>, rows: usize) -> io::Result<DMatrix<f64>> {
+    let y = sketch_range(source, omega)?;
+    if y.nrows() != rows { return Err(rows_changed(rows, y.nrows())); }
+    Ok(y)
+}
+
+fn rows_changed(expected: usize, read: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("source changed between passes: read {read} rows, expected {expected}"),
+    )
+}
+
+impl LowRankIdentity {
+    /// Randomized truncated SVD in 2 + 2 · `power_iterations` passes over a
+    /// rewindable source; fails if the passes disagree on the row count
+    pub fn from_row_blocks(
+        source: &mut impl RowBlockSource,
+        target_rank: usize,
+        config: &RandomizedSvdConfig,
+    ) -> io::Result<Self> {
+        let n = source.cols();
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let omega = random_matrix(&mut rng, n, (target_rank + config.oversampling).min(n));
+        source.rewind()?;
+        let mut q = sketch_range(source, &omega)?.qr().q();
+        let rows = q.nrows();
+        for _ in 0..config.power_iterations {
+            source.rewind()?;
+            let z = sketch_corange(source, &q)?.qr().q();
+            source.rewind()?;
+            q = resketch_range(source, &z, rows)?.qr().q();
+        }
+        source.rewind()?;
+        let bt = sketch_corange(source, &q)?;
+        let lrim = Self::from_factor_pair(&q, &bt, 1e-12);
+        let keep: Vec<usize> = (0..target_rank.min(lrim.rank)).collect();
+        Ok(lrim.select_components(&keep))
+    }
+
+    /// Randomized truncated SVD in a single pass, for sources that can only
+    /// be read once. `power_iterations` is ignored.
+    pub fn from_row_stream(
+        source: &mut impl RowBlockSource,
+        target_rank: usize,
+        config: &RandomizedSvdConfig,
+    ) -> io::Result<Self> {
+        let n = source.cols();
+        let k = (target_rank + config.oversampling).min(n);
+        let l = 2 * k + 1;
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let omega = random_matrix(&mut rng, n, k);
+        let mut range_blocks = Vec::new();
+        let mut psi_blocks = Vec::new();
+        let mut w = DMatrix::zeros(l, n);
+        while let Some(block) = source.next_block()? {
+            // Columns of Ψ for these rows, drawn as the rows arrive
+            let psi = random_matrix(&mut rng, l, block.nrows());
+            w += &psi * &block;
+            range_blocks.push(block * &omega);
+            psi_blocks.push(psi.transpose());
+        }
+        let q = stack_rows(&range_blocks, k).qr().q();
+        let psi_q = stack_rows(&psi_blocks, l).transpose() * &q;
+        let x = psi_q.svd(true, true).solve(&w, 1e-12)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let lrim = Self::from_factor_pair(&q, &x.transpose(), 1e-12);
+        let keep: Vec<usize> = (0..target_rank.min(lrim.rank)).collect();
+        Ok(lrim.select_components(&keep))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_out_of_core_factorization() {
+        let a = DMatrix::<f64>::new_random(150, 4);
+        let b = DMatrix::<f64>::new_random(90, 4);
+        let k = &a * b.transpose();
+        let path = std::env::temp_dir().join(format!("dlrs-{}.mat", uuid::Uuid::new_v4()));
+
+        let mut writer = MatrixFileWriter::create(&path, 150, 90).unwrap();
+        for start in (0..150).step_by(40) {
+            writer.write_block(&k.rows(start, 40.min(150 - start)).into_owned()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut file = MatrixFileSource::open(&path, 32).unwrap();
+        let two_pass = LowRankIdentity::from_row_blocks(&mut file, 4, &RandomizedSvdConfig::default()).unwrap();
+        assert!(two_pass.reconstruction_error(&k) < 1e-9 * k.norm());
+        file.rewind().unwrap();
+        let single = LowRankIdentity::from_row_stream(&mut file, 4, &RandomizedSvdConfig::default()).unwrap();
+        assert!(single.reconstruction_error(&k) < 1e-8 * k.norm());
+
+        // Headers that disagree with the file length, or overflow, are refused
+        let data = std::fs::read(&path).unwrap();
+        for (rows, cols) in [(151u64, 90u64), (150, 89), (u64::MAX, 2), (1 << 61, 1 << 3)] {
+            let mut corrupt = data.clone();
+            corrupt[8..16].copy_from_slice(&rows.to_le_bytes());
+            corrupt[16..24].copy_from_slice(&cols.to_le_bytes());
+            std::fs::write(&path, &corrupt).unwrap();
+            assert_eq!(MatrixFileSource::open(&path, 32).err().map(|e| e.kind()), Some(io::ErrorKind::InvalidData));
+        }
+        std::fs::write(&path, &data[..data.len() - 8]).unwrap();
+        assert!(MatrixFileSource::open(&path, 32).is_err());
+        std::fs::remove_file(&path).unwrap();
+
+        let blocks = (0..150).step_by(25).map(|s| k.rows(s, 25).into_owned());
+        let mut stream = IterBlocks::new(blocks, 90);
+        assert!(LowRankIdentity::from_row_blocks(&mut stream, 4, &RandomizedSvdConfig::default()).is_err());
+        let blocks = (0..150).step_by(25).map(|s| k.rows(s, 25).into_owned());
+        let from_iter = LowRankIdentity::from_row_stream(&mut IterBlocks::new(blocks, 90), 4, &Default::default()).unwrap();
+        assert!(from_iter.reconstruction_error(&k) < 1e-8 * k.norm());
+
+        // A source that loses rows after the first pass is rejected, not factored
+        struct Shrinking { k: DMatrix<f64>, rows: usize, done: bool }
+        impl RowBlockSource for Shrinking {
+            fn cols(&self) -> usize { self.k.ncols() }
+            fn rewind(&mut self) -> io::Result<()> {
+                if self.done { self.rows -= 10; }
+                self.done = false;
+                Ok(())
+            }
+            fn next_block(&mut self) -> io::Result<Option<DMatrix<f64>>> {
+                if self.done { return Ok(None); }
+                self.done = true;
+                Ok(Some(self.k.rows(0, self.rows).into_owned()))
+            }
+        }
+        for power_iterations in [0, 1] {
+            let config = RandomizedSvdConfig { power_iterations, ..Default::default() };
+            let mut source = Shrinking { k: k.clone(), rows: 150, done: false };
+            let err = LowRankIdentity::from_row_blocks(&mut source, 4, &config).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,528 @@
+//! Tensor seeds: three-way knowledge (expert × task × context)
+//!
+//! The tensor counterpart of `LowRankIdentity` comes in two forms: CP, a
+//! sum of r rank-one terms fitted by alternating least squares, and Tucker,
+//! a small core multiplied by a basis per mode, computed by truncated
+//! HOSVD. Expression contracts the tensor with a vector along one mode.
+//!
+//! Unfoldings follow one convention throughout: the mode-n unfolding has
+//! dims[n] rows, and its columns enumerate the two remaining modes in
+//! ascending order, the first of them varying fastest.
+//!
+//! `KnowledgePayload` is implemented by `LowRankIdentity` and by tensor
+//! payloads, so seeds can hold either. A tensor seed keeps its mode-0
+//! unfolding, at full rank, in `lrim`, which commitments, training and the
+//! network keep using, plus only the shape and form of its payload: the
+//! payload is refitted from `lrim` when `lrim` has changed since the last
+//! fit, so it is always covered by the commitment and never lags behind
+//! training.
+
+use super::encoding::{to_fixed, FIXED_POINT_FRAC_BITS};
+use super::{DnaSeed, LowRankIdentity};
+use nalgebra::{DMatrix, DVector};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+
+/// Behaviour shared by every form of seed knowledge
+pub trait KnowledgePayload {
+    /// Extent of every mode (2 for matrices, 3 for tensors)
+    fn dims(&self) -> Vec<usize>;
+    /// Stored parameters
+    fn parameter_count(&self) -> usize;
+    /// Contract with `input` along `mode`, giving an object of one order
+    /// less; vectors come back as single-column matrices
+    fn contract(&self, mode: usize, input: &DVector<f64>) -> DMatrix<f64>;
+    /// SHA256 over a fixed-point encoding of the stored parameters
+    fn payload_fingerprint(&self) -> String;
+
+    fn order(&self) -> usize {
+        self.dims().len()
+    }
+
+    /// Dense entries / stored parameters
+    fn payload_compression(&self) -> f64 {
+        self.dims().iter().product::<usize>() as f64 / self.parameter_count().max(1) as f64
+    }
+}
+
+impl<P: KnowledgePayload + ?Sized> KnowledgePayload for &P {
+    fn dims(&self) -> Vec<usize> { (**self).dims() }
+    fn parameter_count(&self) -> usize { (**self).parameter_count() }
+    fn contract(&self, mode: usize, input: &DVector<f64>) -> DMatrix<f64> { (**self).contract(mode, input) }
+    fn payload_fingerprint(&self) -> String { (**self).payload_fingerprint() }
+}
+
+impl KnowledgePayload for LowRankIdentity {
+    fn dims(&self) -> Vec<usize> {
+        vec![self.m, self.n]
+    }
+
+    fn parameter_count(&self) -> usize {
+        (self.m + self.n) * self.rank + self.rank
+    }
+
+    /// Mode 1 gives K·x, mode 0 gives Kᵀ·x
+    fn contract(&self, mode: usize, input: &DVector<f64>) -> DMatrix<f64> {
+        let (along, out) = match mode {
+            0 => (&self.u, &self.v),
+            1 => (&self.v, &self.u),
+            _ => panic!("matrix payloads have modes 0 and 1"),
+        };
+        let z = (along.transpose() * input).component_mul(&self.sigma);
+        let y = out * z;
+        DMatrix::from_column_slice(y.len(), 1, y.as_slice())
+    }
+
+    fn payload_fingerprint(&self) -> String {
+        self.fingerprint()
+    }
+}
+
+/// The two modes other than `mode`, ascending
+fn other_modes(mode: usize) -> (usize, usize) {
+    match mode {
+        0 => (1, 2),
+        1 => (0, 2),
+        2 => (0, 1),
+        _ => panic!("tensors have modes 0, 1 and 2"),
+    }
+}
+
+/// Dense third-order tensor, entry (i, j, k) at i + I·(j + J·k)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tensor3 {
+    pub dims: [usize; 3],
+    pub data: Vec<f64>,
+}
+
+impl Tensor3 {
+    pub fn zeros(dims: [usize; 3]) -> Self {
+        Self { dims, data: vec![0.0; dims.iter().product()] }
+    }
+
+    pub fn from_fn(dims: [usize; 3], mut f: impl FnMut(usize, usize, usize) -> f64) -> Self {
+        let mut t = Self::zeros(dims);
+        for k in 0..dims[2] {
+            for j in 0..dims[1] {
+                for i in 0..dims[0] { t.data[i + dims[0] * (j + dims[1] * k)] = f(i, j, k); }
+            }
+        }
+        t
+    }
+
+    fn offset(&self, idx: [usize; 3]) -> usize {
+        idx[0] + self.dims[0] * (idx[1] + self.dims[1] * idx[2])
+    }
+
+    pub fn get(&self, i: usize, j: usize, k: usize) -> f64 {
+        self.data[self.offset([i, j, k])]
+    }
+
+    pub fn norm(&self) -> f64 {
+        self.data.iter().map(|x| x * x).sum::<f64>().sqrt()
+    }
+
+    /// Mode-n unfolding (see module docs for the column order)
+    pub fn unfold(&self, mode: usize) -> DMatrix<f64> {
+        let (a, b) = other_modes(mode);
+        let mut out = DMatrix::zeros(self.dims[mode], self.dims[a] * self.dims[b]);
+        let mut idx = [0; 3];
+        for (pos, &x) in self.data.iter().enumerate() {
+            idx[0] = pos % self.dims[0];
+            idx[1] = (pos / self.dims[0]) % self.dims[1];
+            idx[2] = pos / (self.dims[0] * self.dims[1]);
+            out[(idx[mode], idx[a] + self.dims[a] * idx[b])] = x;
+        }
+        out
+    }
+
+    /// Inverse of `unfold` for a tensor of shape `dims`
+    pub fn fold(mode: usize, matrix: &DMatrix<f64>, dims: [usize; 3]) -> Self {
+        let (a, b) = other_modes(mode);
+        Self::from_fn(dims, |i, j, k| {
+            let idx = [i, j, k];
+            matrix[(idx[mode], idx[a] + dims[a] * idx[b])]
+        })
+    }
+
+    /// X ×ₙ M: every mode-n fibre multiplied by M
+    pub fn mode_product(&self, mode: usize, m: &DMatrix<f64>) -> Self {
+        let mut dims = self.dims;
+        dims[mode] = m.nrows();
+        Self::fold(mode, &(m * self.unfold(mode)), dims)
+    }
+
+    /// Σₜ X[…t…] · xₜ along `mode`; rows and columns follow the remaining modes
+    pub fn contract(&self, mode: usize, input: &DVector<f64>) -> DMatrix<f64> {
+        let (a, b) = other_modes(mode);
+        let folded = self.mode_product(mode, &DMatrix::from_row_slice(1, input.len(), input.as_slice()));
+        DMatrix::from_fn(self.dims[a], self.dims[b], |p, q| {
+            let mut idx = [0; 3];
+            idx[a] = p;
+            idx[b] = q;
+            folded.data[folded.offset(idx)]
+        })
+    }
+}
+
+/// Rows f + F·s hold fast[f] ⊙ slow[s], matching the unfolding convention
+fn khatri_rao(slow: &DMatrix<f64>, fast: &DMatrix<f64>) -> DMatrix<f64> {
+    let f = fast.nrows();
+    DMatrix::from_fn(f * slow.nrows(), fast.ncols(), |row, r| fast[(row % f, r)] * slow[(row / f, r)])
+}
+
+/// Leading left singular vectors of a mode-n unfolding, at most `rank`
+fn leading_basis(tensor: &Tensor3, mode: usize, rank: usize) -> DMatrix<f64> {
+    let unfolded = tensor.unfold(mode);
+    let d = unfolded.nrows();
+    // QR of the (long) transpose keeps the SVD small and accurate
+    let lrim = LowRankIdentity::from_factor_pair(&DMatrix::identity(d, d), &unfolded.transpose(), 1e-12);
+    lrim.u.columns(0, rank.min(lrim.rank)).into_owned()
+}
+
+fn encode_parameters(tag: &[u8], dims: &[usize], blocks: &[&[f64]]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(tag);
+    for d in dims { hasher.update((*d as u64).to_le_bytes()); }
+    for block in blocks {
+        hasher.update((block.len() as u64).to_le_bytes());
+        for x in block.iter() { hasher.update(to_fixed(*x, FIXED_POINT_FRAC_BITS).to_le_bytes()); }
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// X ≈ Σᵣ wᵣ · aᵣ ∘ bᵣ ∘ cᵣ with unit-norm factor columns
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpDecomposition {
+    pub weights: DVector<f64>,
+    pub factors: [DMatrix<f64>; 3],
+    /// ALS sweeps it took to fit
+    pub iterations: usize,
+}
+
+impl CpDecomposition {
+    /// Alternating least squares from a HOSVD start, until the error changes
+    /// by less than `tolerance · ‖X‖` or `max_iterations` sweeps
+    pub fn als(tensor: &Tensor3, rank: usize, max_iterations: usize, tolerance: f64) -> Self {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut factors: [DMatrix<f64>; 3] = std::array::from_fn(|mode| {
+            let basis = leading_basis(tensor, mode, rank);
+            DMatrix::from_fn(tensor.dims[mode], rank, |i, r| {
+                if r < basis.ncols() { basis[(i, r)] } else { rng.gen_range(-1.0..1.0) }
+            })
+        });
+        let unfoldings: Vec<DMatrix<f64>> = (0..3).map(|mode| tensor.unfold(mode)).collect();
+        let norm = tensor.norm();
+        let mut cp = Self { weights: DVector::from_element(rank, 1.0), factors: factors.clone(), iterations: 0 };
+        let mut last_error = f64::INFINITY;
+
+        for sweep in 1..=max_iterations {
+            for mode in 0..3 {
+                let (a, b) = other_modes(mode);
+                let kr = khatri_rao(&factors[b], &factors[a]);
+                let gram = (factors[a].transpose() * &factors[a])
+                    .component_mul(&(factors[b].transpose() * &factors[b]));
+                let pinv = gram.pseudo_inverse(1e-12).expect("non-negative epsilon");
+                let mut updated = &unfoldings[mode] * kr * pinv;
+                for (r, mut col) in updated.column_iter_mut().enumerate() {
+                    let w = col.norm();
+                    cp.weights[r] = w;
+                    if w > 0.0 { col /= w; }
+                }
+                factors[mode] = updated;
+            }
+            cp.factors = factors.clone();
+            cp.iterations = sweep;
+            let error = cp.reconstruction_error(tensor);
+            if (last_error - error).abs() <= tolerance * norm { break; }
+            last_error = error;
+        }
+        cp
+    }
+
+    pub fn rank(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn reconstruct(&self) -> Tensor3 {
+        let [a, b, c] = &self.factors;
+        let dims = [a.nrows(), b.nrows(), c.nrows()];
+        Tensor3::from_fn(dims, |i, j, k| {
+            (0..self.rank()).map(|r| self.weights[r] * a[(i, r)] * b[(j, r)] * c[(k, r)]).sum()
+        })
+    }
+
+    pub fn reconstruction_error(&self, original: &Tensor3) -> f64 {
+        let approx = self.reconstruct();
+        original.data.iter().zip(&approx.data).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt()
+    }
+}
+
+impl KnowledgePayload for CpDecomposition {
+    fn dims(&self) -> Vec<usize> {
+        self.factors.iter().map(|f| f.nrows()).collect()
+    }
+
+    fn parameter_count(&self) -> usize {
+        self.rank() * (1 + self.dims().iter().sum::<usize>())
+    }
+
+    fn contract(&self, mode: usize, input: &DVector<f64>) -> DMatrix<f64> {
+        let (a, b) = other_modes(mode);
+        let coefficients = (self.factors[mode].transpose() * input).component_mul(&self.weights);
+        &self.factors[a] * DMatrix::from_diagonal(&coefficients) * self.factors[b].transpose()
+    }
+
+    fn payload_fingerprint(&self) -> String {
+        let [a, b, c] = &self.factors;
+        encode_parameters(b"DLRS/CP/v1\0", &self.dims(), &[
+            self.weights.as_slice(), a.as_slice(), b.as_slice(), c.as_slice(),
+        ])
+    }
+}
+
+/// X ≈ G ×₀ U₀ ×₁ U₁ ×₂ U₂ with orthonormal bases Uₙ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TuckerDecomposition {
+    pub core: Tensor3,
+    pub factors: [DMatrix<f64>; 3],
+}
+
+impl TuckerDecomposition {
+    /// Truncated higher-order SVD; a mode's rank shrinks if its unfolding
+    /// has lower rank than requested
+    pub fn hosvd(tensor: &Tensor3, ranks: [usize; 3]) -> Self {
+        let factors: [DMatrix<f64>; 3] = std::array::from_fn(|mode| leading_basis(tensor, mode, ranks[mode]));
+        let core = (0..3).fold(tensor.clone(), |t, mode| t.mode_product(mode, &factors[mode].transpose()));
+        Self { core, factors }
+    }
+
+    pub fn ranks(&self) -> [usize; 3] {
+        self.core.dims
+    }
+
+    pub fn reconstruct(&self) -> Tensor3 {
+        (0..3).fold(self.core.clone(), |t, mode| t.mode_product(mode, &self.factors[mode]))
+    }
+
+    pub fn reconstruction_error(&self, original: &Tensor3) -> f64 {
+        let approx = self.reconstruct();
+        original.data.iter().zip(&approx.data).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt()
+    }
+}
+
+impl KnowledgePayload for TuckerDecomposition {
+    fn dims(&self) -> Vec<usize> {
+        self.factors.iter().map(|f| f.nrows()).collect()
+    }
+
+    fn parameter_count(&self) -> usize {
+        self.core.data.len() + self.factors.iter().map(|f| f.len()).sum::<usize>()
+    }
+
+    /// Contract the core with Uₙᵀ·x, then expand the remaining two modes
+    fn contract(&self, mode: usize, input: &DVector<f64>) -> DMatrix<f64> {
+        let (a, b) = other_modes(mode);
+        let reduced = self.factors[mode].transpose() * input;
+        let core = self.core.contract(mode, &reduced);
+        &self.factors[a] * core * self.factors[b].transpose()
+    }
+
+    fn payload_fingerprint(&self) -> String {
+        let [a, b, c] = &self.factors;
+        let mut dims = self.dims();
+        dims.extend(self.ranks());
+        encode_parameters(b"DLRS/TUCKER/v1\0", &dims, &[
+            &self.core.data, a.as_slice(), b.as_slice(), c.as_slice(),
+        ])
+    }
+}
+
+/// The tensor knowledge a seed can carry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TensorPayload {
+    Cp(CpDecomposition),
+    Tucker(TuckerDecomposition),
+}
+
+impl TensorPayload {
+    pub fn reconstruct(&self) -> Tensor3 {
+        match self {
+            TensorPayload::Cp(cp) => cp.reconstruct(),
+            TensorPayload::Tucker(t) => t.reconstruct(),
+        }
+    }
+
+    pub fn reconstruction_error(&self, original: &Tensor3) -> f64 {
+        match self {
+            TensorPayload::Cp(cp) => cp.reconstruction_error(original),
+            TensorPayload::Tucker(t) => t.reconstruction_error(original),
+        }
+    }
+
+    fn inner(&self) -> &dyn KnowledgePayload {
+        match self {
+            TensorPayload::Cp(cp) => cp,
+            TensorPayload::Tucker(t) => t,
+        }
+    }
+}
+
+impl KnowledgePayload for TensorPayload {
+    fn dims(&self) -> Vec<usize> { self.inner().dims() }
+    fn parameter_count(&self) -> usize { self.inner().parameter_count() }
+    fn contract(&self, mode: usize, input: &DVector<f64>) -> DMatrix<f64> { self.inner().contract(mode, input) }
+    fn payload_fingerprint(&self) -> String { self.inner().payload_fingerprint() }
+}
+
+/// ALS sweeps and tolerance used when refitting a CP payload
+const CP_REFIT_SWEEPS: usize = 500;
+const CP_REFIT_TOLERANCE: f64 = 1e-12;
+
+/// Which decomposition a tensor payload takes, with its ranks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TensorForm {
+    Cp { rank: usize },
+    Tucker { ranks: [usize; 3] },
+}
+
+/// The last payload fitted, with the fingerprint of the LRIM it was
+/// fitted from. Shared by clones; never serialised or compared.
+#[derive(Debug, Clone, Default)]
+struct FitCache(Arc<Mutex<Option<(String, TensorPayload)>>>);
+
+impl PartialEq for FitCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for FitCache {}
+
+/// What a tensor seed keeps of its payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TensorLayout {
+    pub dims: [usize; 3],
+    pub form: TensorForm,
+    #[serde(skip)]
+    fitted: FitCache,
+}
+
+impl TensorLayout {
+    pub fn of(payload: &TensorPayload) -> Self {
+        let form = match payload {
+            TensorPayload::Cp(cp) => TensorForm::Cp { rank: cp.rank() },
+            TensorPayload::Tucker(t) => TensorForm::Tucker { ranks: t.ranks() },
+        };
+        let dims = payload.dims();
+        Self { dims: [dims[0], dims[1], dims[2]], form, fitted: FitCache::default() }
+    }
+
+    /// Fold `lrim` back into a tensor and decompose it, or reuse the last
+    /// fit if `lrim` has not changed; `None` if `lrim` is not the mode-0
+    /// unfolding of a tensor of this shape
+    pub fn fit(&self, lrim: &LowRankIdentity) -> Option<TensorPayload> {
+        let [i, j, k] = self.dims;
+        if lrim.m != i || lrim.n != j * k { return None; }
+        let fingerprint = lrim.fingerprint();
+        let mut cache = self.fitted.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some((_, payload)) = cache.as_ref().filter(|(fitted_from, _)| *fitted_from == fingerprint) {
+            return Some(payload.clone());
+        }
+        let tensor = Tensor3::fold(0, &lrim.reconstruct(), self.dims);
+        let payload = match self.form {
+            TensorForm::Cp { rank } => TensorPayload::Cp(CpDecomposition::als(&tensor, rank, CP_REFIT_SWEEPS, CP_REFIT_TOLERANCE)),
+            TensorForm::Tucker { ranks } => TensorPayload::Tucker(TuckerDecomposition::hosvd(&tensor, ranks)),
+        };
+        *cache = Some((fingerprint, payload.clone()));
+        Some(payload)
+    }
+}
+
+impl DnaSeed {
+    /// Seed carrying tensor knowledge. `lrim` is the SVD of the mode-0
+    /// unfolding of the reconstructed tensor, at its full rank, so the
+    /// payload can be refitted from it; only the layout of `payload` is kept.
+    pub fn from_tensor(name: impl Into<String>, payload: TensorPayload, domains: Vec<String>) -> Self {
+        let unfolded = payload.reconstruct().unfold(0);
+        let m = unfolded.nrows();
+        let shadow = LowRankIdentity::from_factor_pair(&DMatrix::identity(m, m), &unfolded.transpose(), 1e-12);
+        let mut seed = Self::from_lrim(name, shadow, domains);
+        seed.tensor = Some(TensorLayout::of(&payload));
+        seed
+    }
+
+    /// The tensor payload, refitted from the current `lrim`; `None` for
+    /// matrix seeds or if `lrim` no longer matches the layout
+    pub fn tensor_payload(&self) -> Option<TensorPayload> {
+        self.tensor.as_ref()?.fit(&self.lrim)
+    }
+
+    /// The seed's knowledge: its tensor payload if it has one, else its LRIM
+    pub fn payload(&self) -> Box<dyn KnowledgePayload + '_> {
+        match self.tensor_payload() {
+            Some(tensor) => Box::new(tensor),
+            None => Box::new(&self.lrim),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cp_and_tucker() {
+        let centred = |rows| DMatrix::<f64>::new_random(rows, 2).map(|x| 2.0 * x - 1.0);
+        let (a, b, c) = (centred(8), centred(7), centred(6));
+        let x = Tensor3::from_fn([8, 7, 6], |i, j, k| (0..2).map(|r| a[(i, r)] * b[(j, r)] * c[(k, r)]).sum());
+        assert_eq!(Tensor3::fold(1, &x.unfold(1), x.dims), x);
+
+        let cp = CpDecomposition::als(&x, 2, 500, 1e-12);
+        assert!(cp.reconstruction_error(&x) < 1e-6 * x.norm(), "{}", cp.reconstruction_error(&x));
+        let tucker = TuckerDecomposition::hosvd(&x, [2, 2, 2]);
+        assert!(tucker.reconstruction_error(&x) < 1e-9 * x.norm());
+        assert!(tucker.payload_compression() > 5.0);
+
+        let v = DVector::new_random(7);
+        let dense = x.contract(1, &v);
+        assert_eq!(dense.shape(), (8, 6));
+        assert!((tucker.contract(1, &v) - &dense).norm() < 1e-9 * dense.norm());
+        assert!((cp.contract(1, &v) - &dense).norm() < 1e-5 * dense.norm());
+
+        let mut seed = DnaSeed::from_tensor("3-way", TensorPayload::Tucker(tucker), vec!["test".into()]);
+        assert_eq!(seed.payload().dims(), vec![8, 7, 6]);
+        assert!(seed.lrim.reconstruction_error(&x.unfold(0)) < 1e-9 * x.norm());
+        assert!((seed.payload().contract(1, &v) - &dense).norm() < 1e-9 * dense.norm());
+
+        // The shadow keeps the full mode-0 rank, so the refit is exact
+        let full = Tensor3::from_fn([6, 5, 4], |_, _, _| rand::random::<f64>());
+        let truncated = TensorPayload::Tucker(TuckerDecomposition::hosvd(&full, [3, 3, 3]));
+        let wide = DnaSeed::from_tensor("wide", truncated.clone(), vec![]);
+        assert_eq!(wide.lrim.rank, 3);
+        let refitted = wide.tensor_payload().unwrap();
+        let original = truncated.reconstruct();
+        assert!(refitted.reconstruction_error(&original) < 1e-9 * original.norm());
+        assert!(wide.tensor.as_ref().unwrap().fitted.0.lock().unwrap().is_some());
+
+        // Training moves the payload along with the shadow it is refitted from
+        let (d, e, f) = (centred(8), centred(7), centred(6));
+        let y = Tensor3::from_fn([8, 7, 6], |i, j, k| (0..2).map(|r| d[(i, r)] * e[(j, r)] * f[(k, r)]).sum());
+        let before = seed.payload().payload_fingerprint();
+        let mut trainer = crate::seed::Trainer::new(crate::seed::Optimizer::adam(), 0.01);
+        seed.train(&y.unfold(0), &mut trainer, 300);
+        assert_ne!(seed.payload().payload_fingerprint(), before);
+        let target = y.contract(1, &v);
+        let trained = seed.payload().contract(1, &v);
+        assert!((&trained - &target).norm() < (&dense - &target).norm());
+        let shadow = Tensor3::fold(0, &seed.lrim.reconstruct(), [8, 7, 6]).contract(1, &v);
+        assert!((&trained - &shadow).norm() < 0.1 * shadow.norm());
+        let k = DMatrix::new_random(8, 42);
+        let matrix_seed = DnaSeed::new("2-way", &k, 8, vec![]);
+        let w = DVector::new_random(42);
+        assert!((matrix_seed.payload().contract(1, &w) - &k * &w).norm() < 1e-9 * k.norm() * w.norm());
+    }
+}
@@ -0,0 +1,133 @@
+//! Unlearning — remove a knowledge direction from a seed
+//!
+//! Projects a direction or subspace out of U or V space, re-orthonormalises,
+//! re-commits and leaves an auditable trail in the lineage. The lineage only
+//! stores a hash of the removed subspace, never the subspace itself.
+
+use super::lrim::SPAN_TOLERANCE;
+use super::{orthonormal_span, to_fixed, DnaSeed, FactorSpace};
+use chrono::Utc;
+use nalgebra::DMatrix;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnlearnReport {
+    pub space: FactorSpace,
+    pub subspace_hash: String,
+    pub removed_dims: usize,
+    pub rank_before: usize,
+    pub rank_after: usize,
+    /// Fraction of ‖K‖²_F removed — the capability lost to unlearning
+    pub energy_lost: f64,
+}
+
+/// Fractional bits of the hashed projector; coarse enough that bases of
+/// the same span round to the same entries
+const PROJECTOR_FRAC_BITS: u32 = 24;
+
+/// Hash identifying a removed subspace: SHA256 over its space and the
+/// fixed-point orthogonal projector QQᵀ onto its span, so any basis of the
+/// same span reproduces it
+pub fn subspace_hash(basis: &DMatrix<f64>, space: FactorSpace) -> String {
+    let q = orthonormal_span(basis, SPAN_TOLERANCE);
+    let projector = &q * q.transpose();
+    let mut hasher = Sha256::new();
+    hasher.update(format!("unlearn:{:?}:{}x{}", space, projector.nrows(), q.ncols()).as_bytes());
+    for &val in projector.iter() {
+        hasher.update(to_fixed(val, PROJECTOR_FRAC_BITS).to_le_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+impl DnaSeed {
+    /// Project the span of `basis` out of the seed's U or V space.
+    ///
+    /// Unlearning is not gated by `MutationRules`: removal requests apply
+    /// even to frozen seeds. Returns `None` if `basis` has the wrong number
+    /// of rows for `space` or spans nothing. `removed_dims` is the dimension
+    /// of its span, not its column count.
+    pub fn unlearn(&mut self, basis: &DMatrix<f64>, space: FactorSpace) -> Option<UnlearnReport> {
+        let dim = match space {
+            FactorSpace::U => self.lrim.m,
+            FactorSpace::V => self.lrim.n,
+        };
+        if basis.nrows() != dim || basis.ncols() == 0 { return None; }
+
+        let rank_before = self.lrim.rank;
+        let energy_before = self.lrim.energy();
+        let (lrim, removed_dims) = self.lrim.project_out(basis, space);
+        if removed_dims == 0 { return None; }
+        self.lrim = lrim;
+        let energy_lost = if energy_before > 0.0 {
+            (1.0 - self.lrim.energy() / energy_before).max(0.0)
+        } else { 0.0 };
+
+        let hash = subspace_hash(basis, space);
+        self.recommit();
+        self.mutated_at = Some(Utc::now());
+        self.lineage.record_unlearn(self.epoch, space, &hash, removed_dims, energy_lost);
+
+        Some(UnlearnReport {
+            space,
+            subspace_hash: hash,
+            removed_dims,
+            rank_before,
+            rank_after: self.lrim.rank,
+            energy_lost,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed::lineage::LineageEventType;
+
+    #[test]
+    fn test_unlearn_records_and_recommits() {
+        let mut seed = DnaSeed::new("forgetful", &DMatrix::new_random(10, 8), 4, vec!["test".into()]);
+        let before = seed.commitment.clone();
+        let root_before = seed.lineage.root_hash.clone();
+
+        // Two copies of one direction remove one dimension
+        let direction = seed.lrim.v.column(0).into_owned();
+        let basis = DMatrix::from_columns(&[direction.clone(), &direction * 3.0]);
+        let report = seed.unlearn(&basis, FactorSpace::V).unwrap();
+        assert_eq!((report.removed_dims, report.rank_before, report.rank_after), (1, 4, 3));
+        assert!(report.energy_lost > 0.0 && report.energy_lost < 1.0);
+        assert!(seed.lrim.capability_in_domain(&direction) < 1e-9);
+
+        // The lineage gains one event, chained to the previous root
+        let event = seed.lineage.events.last().unwrap();
+        let LineageEventType::Unlearn { space, subspace_hash: hash, removed_dims, energy_lost } = &event.event_type else {
+            panic!("expected an unlearn event");
+        };
+        assert_eq!((*space, hash.as_str(), *removed_dims), (FactorSpace::V, report.subspace_hash.as_str(), 1));
+        assert_eq!(*hash, subspace_hash(&basis, FactorSpace::V));
+        assert_eq!(*hash, subspace_hash(&DMatrix::from_columns(&[&direction * -0.5]), FactorSpace::V));
+        let data = format!("unlearn:V:{hash}:1:{energy_lost}");
+        let expected = hex::encode(Sha256::new().chain_update(root_before.as_bytes()).chain_update(data).finalize());
+        assert_eq!(event.hash, expected);
+        assert_eq!(seed.lineage.root_hash, expected);
+
+        // The commitment is refreshed and opens to the new factors only
+        assert_ne!(seed.commitment.matrix_hash, before.matrix_hash);
+        assert_eq!(seed.commitment.committed_rank, 3);
+        assert!(seed.verify_commitment());
+
+        // Any basis of a span hashes like any other, and only like those
+        let plane = seed.lrim.u.columns(0, 2).into_owned();
+        let rotation = nalgebra::Rotation2::new(0.7).into_inner();
+        let turned = &plane * DMatrix::from_iterator(2, 2, rotation.iter().copied()) * 5.0;
+        assert_eq!(subspace_hash(&plane, FactorSpace::U), subspace_hash(&turned, FactorSpace::U));
+        assert_ne!(subspace_hash(&plane, FactorSpace::U), subspace_hash(&plane, FactorSpace::V));
+        assert_ne!(subspace_hash(&plane.columns(0, 1).into_owned(), FactorSpace::U), subspace_hash(&plane, FactorSpace::U));
+
+        // Nothing to remove, nothing recorded
+        let events = seed.lineage.events.len();
+        assert!(seed.unlearn(&DMatrix::zeros(8, 1), FactorSpace::V).is_none());
+        assert!(seed.unlearn(&DMatrix::new_random(9, 1), FactorSpace::V).is_none());
+        assert_eq!(seed.lineage.events.len(), events);
+    }
+}
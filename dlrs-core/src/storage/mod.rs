@@ -3,16 +3,46 @@
 //! Persistent store with JSON serialization.
 //! Open the app → see all your seeds → sync with network.
 
-use crate::seed::DnaSeed;
+use crate::seed::{DnaSeed, HealthReport, Trainer, TrainReport};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SeedStore {
+    #[serde(default)]
     pub seeds: HashMap<String, DnaSeed>,
+    /// Saved seeds that no longer load, kept as they were saved
+    #[serde(default)]
+    pub quarantine: HashMap<String, serde_json::Value>,
     pub path: PathBuf,
     pub metadata: StoreMetadata,
+    /// Training state per seed id, so interrupted training can resume
+    #[serde(default)]
+    pub trainers: HashMap<String, Trainer>,
+}
+
+/// What `SeedStore::add` or `SeedStore::repair` did with a seed
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreOutcome {
+    /// Stored or left as it was; the report lists any non-fatal issues
+    Stored(HealthReport),
+    /// Re-canonicalised and re-committed to fix the reported issues
+    Repaired(HealthReport),
+    /// Fatal issues; nothing was stored or changed
+    Rejected(HealthReport),
+}
+
+impl StoreOutcome {
+    pub fn is_stored(&self) -> bool {
+        !matches!(self, StoreOutcome::Rejected(_))
+    }
+
+    pub fn report(&self) -> &HealthReport {
+        match self {
+            StoreOutcome::Stored(r) | StoreOutcome::Repaired(r) | StoreOutcome::Rejected(r) => r,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,17 +55,28 @@ pub struct StoreMetadata {
 }
 
 impl SeedStore {
-    pub fn open(path: impl AsRef<Path>, owner: &str) -> Self {
+    /// Open the store saved at `path`, or a new one if there is none.
+    /// Seeds that fail to load, e.g. after a NaN crept in through training,
+    /// go to `quarantine`; a file that cannot be read or parsed at all is
+    /// an error, so that saving never overwrites it with an empty store.
+    pub fn open(path: impl AsRef<Path>, owner: &str) -> Result<Self, Box<dyn std::error::Error>> {
         let path = path.as_ref().to_path_buf();
         if path.exists() {
-            if let Ok(data) = std::fs::read_to_string(&path) {
-                if let Ok(store) = serde_json::from_str(&data) {
-                    return store;
+            let mut data: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            let seeds = data.as_object_mut().and_then(|fields| fields.remove("seeds"));
+            let mut store: Self = serde_json::from_value(data)?;
+            let seeds: HashMap<String, serde_json::Value> = seeds.map(serde_json::from_value).transpose()?.unwrap_or_default();
+            for (id, saved) in seeds {
+                match DnaSeed::deserialize(&saved) {
+                    Ok(seed) => { store.seeds.insert(id, seed); }
+                    Err(_) => { store.quarantine.insert(id, saved); }
                 }
             }
+            return Ok(store);
         }
-        Self {
+        Ok(Self {
             seeds: HashMap::new(),
+            quarantine: HashMap::new(),
             path,
             metadata: StoreMetadata {
                 owner: owner.to_string(),
@@ -44,7 +85,8 @@ impl SeedStore {
                 total_evolutions: 0,
                 total_replications: 0,
             },
-        }
+            trainers: HashMap::new(),
+        })
     }
 
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
@@ -53,9 +95,40 @@ impl SeedStore {
         Ok(())
     }
 
-    pub fn add(&mut self, seed: DnaSeed) {
+    /// Store a seed as it is. Seeds with fatal numerical issues are
+    /// rejected; other issues are reported but left alone, so the seed's
+    /// commitment and any proofs made against it stay valid. See `repair`.
+    pub fn add(&mut self, seed: DnaSeed) -> StoreOutcome {
+        let report = seed.lrim.validate();
+        if !report.is_repairable() { return StoreOutcome::Rejected(report); }
         self.metadata.total_seeds_ever += 1;
         self.seeds.insert(seed.id.clone(), seed);
+        StoreOutcome::Stored(report)
+    }
+
+    /// Re-canonicalise a stored seed with health issues and re-commit it,
+    /// recording the new commitment in its lineage. This invalidates the
+    /// seed's published commitment. `None` if there is no such seed.
+    pub fn repair(&mut self, id: &str) -> Option<StoreOutcome> {
+        let seed = self.seeds.get_mut(id)?;
+        let report = seed.lrim.validate();
+        if report.is_healthy() { return Some(StoreOutcome::Stored(report)); }
+        let Some(repaired) = seed.lrim.repair() else { return Some(StoreOutcome::Rejected(report)) };
+        seed.lrim = repaired;
+        seed.recommit_recorded("repair");
+        Some(StoreOutcome::Repaired(report))
+    }
+
+    /// Replicate a stored seed and store the child, returning its id. The
+    /// parent is only updated once the child has been stored.
+    pub fn replicate(&mut self, id: &str, mutate: bool) -> Option<String> {
+        let mut parent = self.seeds.get(id)?.clone();
+        let child = parent.replicate(mutate)?;
+        let child_id = child.id.clone();
+        if !self.add(child).is_stored() { return None; }
+        self.seeds.insert(id.to_string(), parent);
+        self.metadata.total_replications += 1;
+        Some(child_id)
     }
 
     pub fn get(&self, id: &str) -> Option<&DnaSeed> {
@@ -67,13 +140,35 @@ impl SeedStore {
     }
 
     pub fn remove(&mut self, id: &str) -> Option<DnaSeed> {
+        self.trainers.remove(id);
         self.seeds.remove(id)
     }
 
+    /// Train a stored seed, resuming its saved trainer if there is one and
+    /// starting from `fresh` otherwise
+    pub fn train(
+        &mut self,
+        id: &str,
+        target: &nalgebra::DMatrix<f64>,
+        fresh: Trainer,
+        max_epochs: usize,
+    ) -> Option<TrainReport> {
+        let seed = self.seeds.get_mut(id)?;
+        let mut trainer = self.trainers.remove(id).unwrap_or(fresh);
+        let report = seed.train(target, &mut trainer, max_epochs);
+        self.metadata.total_evolutions += report.loss_history.len() as u64;
+        self.trainers.insert(id.to_string(), trainer);
+        Some(report)
+    }
+
+    /// Seeds ordered by effective fitness (after decay and probation)
     pub fn list_by_fitness(&self) -> Vec<&DnaSeed> {
-        let mut seeds: Vec<&DnaSeed> = self.seeds.values().collect();
-        seeds.sort_by(|a, b| b.fitness.partial_cmp(&a.fitness).unwrap());
-        seeds
+        let now = chrono::Utc::now();
+        let mut seeds: Vec<(f64, &DnaSeed)> = self.seeds.values()
+            .map(|s| (s.effective_fitness(now), s))
+            .collect();
+        seeds.sort_by(|a, b| b.0.total_cmp(&a.0));
+        seeds.into_iter().map(|(_, s)| s).collect()
     }
 
     pub fn list_by_domain(&self, domain: &str) -> Vec<&DnaSeed> {
@@ -98,3 +193,60 @@ impl SeedStore {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn test_add_reports_and_repair_is_explicit() {
+        let path = std::env::temp_dir().join(format!("dlrs-store-{}.json", uuid::Uuid::new_v4()));
+        let mut store = SeedStore::open(&path, "tester").unwrap();
+
+        // Slight drift is reported, not silently re-committed
+        let mut drifted = DnaSeed::new("drifted", &DMatrix::new_random(8, 6), 3, vec!["test".into()]);
+        drifted.lrim.u[(0, 0)] += 1e-3;
+        let (id, commitment) = (drifted.id.clone(), drifted.commitment.matrix_hash.clone());
+        let outcome = store.add(drifted);
+        assert!(matches!(&outcome, StoreOutcome::Stored(r) if !r.is_healthy()));
+        assert_eq!(store.get(&id).unwrap().commitment.matrix_hash, commitment);
+
+        // Repairing is explicit, re-commits and leaves a trail
+        assert!(matches!(store.repair(&id), Some(StoreOutcome::Repaired(_))));
+        let seed = store.get(&id).unwrap();
+        assert!(seed.lrim.validate().is_healthy() && seed.verify_commitment());
+        assert_ne!(seed.commitment.matrix_hash, commitment);
+        assert!(matches!(
+            &seed.lineage.events.last().unwrap().event_type,
+            crate::seed::LineageEventType::Recommit { previous, .. } if *previous == commitment
+        ));
+        assert!(matches!(store.repair(&id), Some(StoreOutcome::Stored(r)) if r.is_healthy()));
+
+        let mut broken = DnaSeed::new("broken", &DMatrix::new_random(8, 6), 3, vec!["test".into()]);
+        broken.lrim.sigma[0] = f64::NAN;
+        assert!(!store.add(broken).is_stored());
+        assert_eq!(store.seeds.len(), 1);
+
+        // Openings are saved with the seeds
+        store.save().unwrap();
+        let reopened = SeedStore::open(&path, "tester").unwrap();
+        assert!(reopened.get(&id).unwrap().verify_commitment());
+
+        // A seed broken in place is quarantined on reopening, not lost
+        let mut stale = DnaSeed::new("stale", &DMatrix::new_random(8, 6), 3, vec!["test".into()]);
+        let stale_id = stale.id.clone();
+        assert!(store.add(stale.clone()).is_stored());
+        stale.lrim.sigma[0] = f64::NAN;
+        *store.get_mut(&stale_id).unwrap() = stale;
+        store.save().unwrap();
+        let reopened = SeedStore::open(&path, "tester").unwrap();
+        assert!(reopened.get(&id).is_some() && reopened.get(&stale_id).is_none());
+        assert!(reopened.quarantine.contains_key(&stale_id));
+        reopened.save().unwrap();
+        assert!(SeedStore::open(&path, "tester").unwrap().quarantine.contains_key(&stale_id));
+        std::fs::write(&path, "not json").unwrap();
+        assert!(SeedStore::open(&path, "tester").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
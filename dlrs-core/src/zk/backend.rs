@@ -0,0 +1,215 @@
+//! Proof systems behind `CircuitKeys` and `CapabilityProof`
+//!
+//! Every circuit is proved through a `ProofSystem`: setup for a circuit
+//! shape, prove, verify, and (de)serialisation of keys and proofs. A proof
+//! records the `Backend` that made it and only verifies under that backend.
+//!
+//! * `Groth16Backend`: Groth16 over BN254, the production backend.
+//! * `MockBackend`: transparent and instant, for tests. Proving checks the
+//!   witness and hashes the shape and public inputs; anyone can compute
+//!   that hash, so a mock proof convinces only whoever ran the prover.
+
+use super::circuit::{Circuit, CircuitShape};
+use super::pedersen::{decode_hex, encode_hex};
+use super::transcript::Transcript;
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::ConstraintSystem;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+
+const MOCK_PROTOCOL: &[u8] = b"DLRS/MOCK_PROOF/v1";
+
+/// Which proof system a proof was made with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Backend {
+    #[default]
+    Groth16Bn254,
+    Mock,
+}
+
+pub trait ProofSystem {
+    const BACKEND: Backend;
+    type ProvingKey: Clone + Debug;
+    type VerifyingKey: Clone + Debug;
+    type Proof: Clone + Debug;
+
+    fn setup(shape: CircuitShape) -> Option<Self::ProvingKey> {
+        Self::setup_with_rng(shape, &mut rand::thread_rng())
+    }
+    /// Setup with the given randomness; seeded, it is reproducible
+    fn setup_with_rng(shape: CircuitShape, rng: &mut impl rand::Rng) -> Option<Self::ProvingKey>;
+    fn verifying_key(key: &Self::ProvingKey) -> Self::VerifyingKey;
+    /// `None` if the witness does not satisfy the circuit
+    fn prove(key: &Self::ProvingKey, circuit: impl Circuit) -> Option<Self::Proof>;
+    fn verify(key: &Self::VerifyingKey, public_inputs: &[Fr], proof: &Self::Proof) -> bool;
+
+    fn encode_proof(proof: &Self::Proof) -> String;
+    fn decode_proof(text: &str) -> Option<Self::Proof>;
+    fn encode_proving_key(key: &Self::ProvingKey) -> Vec<u8>;
+    fn decode_proving_key(bytes: &[u8]) -> Option<Self::ProvingKey>;
+    fn encode_verifying_key(key: &Self::VerifyingKey) -> Vec<u8>;
+    fn decode_verifying_key(bytes: &[u8]) -> Option<Self::VerifyingKey>;
+}
+
+fn to_bytes(value: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes).expect("writing to a Vec cannot fail");
+    bytes
+}
+
+/// Groth16 over BN254; setup draws fresh toxic waste and drops it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Groth16Backend;
+
+impl ProofSystem for Groth16Backend {
+    const BACKEND: Backend = Backend::Groth16Bn254;
+    type ProvingKey = ProvingKey<Bn254>;
+    type VerifyingKey = VerifyingKey<Bn254>;
+    type Proof = Proof<Bn254>;
+
+    fn setup_with_rng(shape: CircuitShape, rng: &mut impl rand::Rng) -> Option<Self::ProvingKey> {
+        shape.blank_keys(rng)
+    }
+
+    fn verifying_key(key: &Self::ProvingKey) -> Self::VerifyingKey {
+        key.vk.clone()
+    }
+
+    fn prove(key: &Self::ProvingKey, circuit: impl Circuit) -> Option<Self::Proof> {
+        if !circuit.is_satisfied() { return None; }
+        Groth16::<Bn254>::create_random_proof_with_reduction(circuit, key, &mut rand::thread_rng()).ok()
+    }
+
+    fn verify(key: &Self::VerifyingKey, public_inputs: &[Fr], proof: &Self::Proof) -> bool {
+        if public_inputs.len() + 1 != key.gamma_abc_g1.len() { return false; }
+        let prepared = ark_groth16::prepare_verifying_key(key);
+        Groth16::<Bn254>::verify_proof(&prepared, proof, public_inputs).unwrap_or(false)
+    }
+
+    fn encode_proof(proof: &Self::Proof) -> String {
+        encode_hex(proof)
+    }
+
+    fn decode_proof(text: &str) -> Option<Self::Proof> {
+        decode_hex(text)
+    }
+
+    fn encode_proving_key(key: &Self::ProvingKey) -> Vec<u8> {
+        to_bytes(key)
+    }
+
+    fn decode_proving_key(bytes: &[u8]) -> Option<Self::ProvingKey> {
+        ProvingKey::deserialize_compressed(bytes).ok()
+    }
+
+    fn encode_verifying_key(key: &Self::VerifyingKey) -> Vec<u8> {
+        to_bytes(key)
+    }
+
+    fn decode_verifying_key(bytes: &[u8]) -> Option<Self::VerifyingKey> {
+        VerifyingKey::deserialize_compressed(bytes).ok()
+    }
+}
+
+/// Transparent test backend; its keys are just the shape
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MockBackend;
+
+fn mock_proof(shape: &CircuitShape, public_inputs: &[Fr]) -> [u8; 32] {
+    let mut transcript = Transcript::new(MOCK_PROTOCOL);
+    transcript.append_json(b"shape", shape);
+    for input in public_inputs {
+        transcript.append_serialized(b"input", input);
+    }
+    transcript.challenge_bytes(b"proof")
+}
+
+impl ProofSystem for MockBackend {
+    const BACKEND: Backend = Backend::Mock;
+    type ProvingKey = CircuitShape;
+    type VerifyingKey = CircuitShape;
+    type Proof = [u8; 32];
+
+    fn setup_with_rng(shape: CircuitShape, _rng: &mut impl rand::Rng) -> Option<Self::ProvingKey> {
+        Some(shape)
+    }
+
+    fn verifying_key(key: &Self::ProvingKey) -> Self::VerifyingKey {
+        *key
+    }
+
+    fn prove(key: &Self::ProvingKey, circuit: impl Circuit) -> Option<Self::Proof> {
+        let cs = ConstraintSystem::new_ref();
+        circuit.generate_constraints(cs.clone()).ok()?;
+        if !cs.is_satisfied().ok()? { return None; }
+        let inputs = cs.borrow()?.instance_assignment[1..].to_vec();
+        Some(mock_proof(key, &inputs))
+    }
+
+    fn verify(key: &Self::VerifyingKey, public_inputs: &[Fr], proof: &Self::Proof) -> bool {
+        mock_proof(key, public_inputs) == *proof
+    }
+
+    fn encode_proof(proof: &Self::Proof) -> String {
+        hex::encode(proof)
+    }
+
+    fn decode_proof(text: &str) -> Option<Self::Proof> {
+        hex::decode(text).ok()?.try_into().ok()
+    }
+
+    fn encode_proving_key(key: &Self::ProvingKey) -> Vec<u8> {
+        serde_json::to_vec(key).expect("shape serialises")
+    }
+
+    fn decode_proving_key(bytes: &[u8]) -> Option<Self::ProvingKey> {
+        serde_json::from_slice(bytes).ok()
+    }
+
+    fn encode_verifying_key(key: &Self::VerifyingKey) -> Vec<u8> {
+        Self::encode_proving_key(key)
+    }
+
+    fn decode_verifying_key(bytes: &[u8]) -> Option<Self::VerifyingKey> {
+        Self::decode_proving_key(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::fixture::Prover;
+    use crate::zk::{CapabilityProof, CircuitKeys};
+
+    #[test]
+    fn test_backends() {
+        let Prover { seed, opening, d, score, .. } = Prover::new(6, 4, 2);
+        let shape = CircuitShape::Capability { n: 4, rank: 2 };
+
+        // The mock runs the same circuit, so false claims still fail
+        let mock = CircuitKeys::<MockBackend>::setup(shape).unwrap();
+        let proof = CapabilityProof::prove_capability(&mock, &seed.lrim, &opening, &d, "test", score * 0.9).unwrap();
+        assert_eq!(proof.snark.as_ref().unwrap().backend, Backend::Mock);
+        assert!(proof.verify_capability(&seed.commitment, &mock.verifying_key(), &d));
+        assert!(CapabilityProof::prove_capability(&mock, &seed.lrim, &opening, &d, "test", score * 1.1).is_none());
+
+        // A proof verifies only under the backend that made it
+        let groth16 = CircuitKeys::<Groth16Backend>::setup(shape).unwrap();
+        let vk = groth16.verifying_key();
+        assert!(!proof.verify_capability(&seed.commitment, &vk, &d));
+        let real = CapabilityProof::prove_capability(&groth16, &seed.lrim, &opening, &d, "test", score * 0.9).unwrap();
+        assert!(!real.verify_capability(&seed.commitment, &mock.verifying_key(), &d));
+
+        // Keys and proofs survive serialisation
+        let bytes = Groth16Backend::encode_verifying_key(&vk.key);
+        let key = Groth16Backend::decode_verifying_key(&bytes).unwrap();
+        let decoded = crate::zk::CircuitVerifyingKey::<Groth16Backend> { shape, key };
+        assert!(real.verify_capability(&seed.commitment, &decoded, &d));
+        assert!(Groth16Backend::decode_verifying_key(&bytes[1..]).is_none());
+        let pk = Groth16Backend::decode_proving_key(&Groth16Backend::encode_proving_key(&groth16.proving_key)).unwrap();
+        assert_eq!(pk.vk, vk.key);
+        assert_eq!(MockBackend::decode_proving_key(&MockBackend::encode_proving_key(&shape)), Some(shape));
+    }
+}
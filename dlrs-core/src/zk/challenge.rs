@@ -0,0 +1,572 @@
+//! Interactive challenge–response capability check (paper §3.2)
+//!
+//! Bob, the verifier, poses problems from his domain and learns whether
+//! Alice's committed seed solves them, without seeing the seed:
+//!
+//! 1. Challenge: Bob picks c inputs X (e.g. `sample_challenges` over a basis
+//!    of his domain) and the answers E he expects. He sends X, a fresh
+//!    nonce, the tolerance τ, the quality bound q and a salted hash of E.
+//! 2. Answers: Alice sends A = `express_on`(X) in Q32.32.
+//! 3. Reveal: Bob opens E; Alice checks it against the hash. Her answers
+//!    were fixed before she saw it.
+//! 4. Proof: Alice proves with `ChallengeCircuit` that her committed
+//!    factors give A to within τ per entry and that ‖A − E‖_F ≤ q.
+//!
+//! The nonce is a public input of the proof, so a proof only counts in the
+//! session it was made for. Either side refuses a message that is out of
+//! turn for its session, and the prover answers each nonce at most once.
+//!
+//! Without a live verifier, a `PublicChallenge` stands in for Bob: a
+//! published basis and reference matrix R. The nonce and the inputs are
+//! drawn from a Fiat–Shamir transcript over the prover's commitment and
+//! the challenge, and E = R X, so anyone holding the challenge can check
+//! the serialised proof.
+//!
+//! In the circuit, canonical U and V entries have magnitude at most 1 and
+//! are range-checked to 34 signed bits, σ to 64: UΣVᵀx at scale 2^128 stays
+//! below 2^200 and never wraps. Entries compare to A·2^96 within τ·2^96.
+//!
+//! Public inputs, in order: the digest, the nonce, X, A and E row by row,
+//! τ·2^96, q² (scale 2^64).
+
+use super::backend::{Groth16Backend, ProofSystem};
+use super::circuit::{mimc_hash_wires, Circuit, CircuitKeys, CircuitShape, CircuitVerifyingKey, FactorWitness};
+use super::gadgets::{Wire, VALUE_BITS};
+use super::pedersen::{decode_hex, encode_hex, random_scalar};
+use super::transcript::Transcript;
+use super::proof::CapabilityProof;
+use super::ZkCommitment;
+use crate::seed::{from_fixed, to_fixed, DnaSeed, LowRankIdentity, LrimScalar, FIXED_POINT_FRAC_BITS};
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use nalgebra::{DMatrix, DVector};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+
+const EXPECTED_TAG: &[u8] = b"DLRS/CHALLENGE/EXPECTED/v1\0";
+const FIAT_SHAMIR_PROTOCOL: &[u8] = b"DLRS/CHALLENGE/FIAT_SHAMIR/v1";
+/// Canonical U and V entries lie in [−2^32, 2^32] in Q32.32
+const UNIT_BITS: u32 = 34;
+/// Width of the per-entry tolerance checks; τ·2^96 < 2^159
+const TOLERANCE_BITS: u32 = 160;
+/// Width of the quality check; q² < 2^126
+const QUALITY_BITS: u32 = 128;
+
+fn fixed(x: f64) -> i64 {
+    to_fixed(x, FIXED_POINT_FRAC_BITS)
+}
+
+/// `count` unit-norm inputs in the span of `basis` (n × k), as columns
+pub fn sample_challenges(basis: &DMatrix<f64>, count: usize) -> DMatrix<f64> {
+    let mut rng = rand::thread_rng();
+    unit_columns(basis * DMatrix::from_fn(basis.ncols(), count, |_, _| rng.gen_range(-1.0..1.0)))
+}
+
+fn unit_columns(mut x: DMatrix<f64>) -> DMatrix<f64> {
+    for mut column in x.column_iter_mut() {
+        let norm = column.norm();
+        if norm > 0.0 { column /= norm; }
+    }
+    x
+}
+
+fn fixed_columns(x: &DMatrix<f64>) -> Vec<Vec<i64>> {
+    x.column_iter().map(|c| c.iter().map(|&v| fixed(v)).collect()).collect()
+}
+
+/// A published challenge set, for non-interactive proofs
+#[derive(Debug, Clone)]
+pub struct PublicChallenge {
+    pub domain: String,
+    /// n × k; inputs are drawn from its span
+    pub basis: DMatrix<f64>,
+    /// m × n; the expected answers are R X
+    pub reference: DMatrix<f64>,
+    pub count: usize,
+    pub tolerance: f64,
+    pub quality_bound: f64,
+}
+
+impl PublicChallenge {
+    /// The statement a prover holding `commitment` must answer, with the
+    /// answers left empty; `None` if the shapes do not fit the commitment
+    pub fn statement(&self, commitment: &ZkCommitment) -> Option<ChallengeStatement> {
+        let (m, n) = commitment.committed_dims;
+        if self.basis.nrows() != n || self.reference.shape() != (m, n) { return None; }
+        let (tolerance, quality_bound) = (fixed(self.tolerance).max(0), fixed(self.quality_bound).max(0));
+        let mut transcript = Transcript::new(FIAT_SHAMIR_PROTOCOL);
+        transcript.append_message(b"commitment", commitment.matrix_hash.as_bytes());
+        transcript.append_json(b"circuit_digest", &commitment.circuit_digest);
+        transcript.append_message(b"domain", self.domain.as_bytes());
+        transcript.append_json(b"basis_shape", &self.basis.shape());
+        transcript.append_i64s(b"basis", &fixed_columns(&self.basis).concat());
+        transcript.append_i64s(b"reference", &fixed_columns(&self.reference).concat());
+        transcript.append_i64s(b"bounds", &[self.count as i64, tolerance, quality_bound]);
+
+        let nonce = encode_hex(&transcript.challenge_scalar(b"nonce"));
+        let k = self.basis.ncols();
+        let coefficients = DMatrix::from_fn(k, self.count, |_, _| transcript.challenge_unit(b"coefficient"));
+        let inputs = fixed_columns(&unit_columns(&self.basis * coefficients));
+        // E from the inputs as sent, not from their unrounded values
+        let sent = DMatrix::from_fn(n, self.count, |i, c| from_fixed(inputs[c][i], FIXED_POINT_FRAC_BITS));
+        let expected = fixed_columns(&(&self.reference * sent));
+        Some(ChallengeStatement { nonce, inputs, answers: Vec::new(), expected, tolerance, quality_bound })
+    }
+}
+
+/// Public side of a challenge–response proof; all values Q32.32
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeStatement {
+    /// Hex-encoded field element, fresh per session
+    pub nonce: String,
+    /// One row (length n) per challenge
+    pub inputs: Vec<Vec<i64>>,
+    /// One row (length m) per challenge
+    pub answers: Vec<Vec<i64>>,
+    pub expected: Vec<Vec<i64>>,
+    /// Per-entry tolerance on the answers
+    pub tolerance: i64,
+    /// Bound on ‖A − E‖_F
+    pub quality_bound: i64,
+}
+
+impl ChallengeStatement {
+    /// X, A and E row by row, then τ
+    pub fn flatten(&self) -> Vec<i64> {
+        let rows = self.inputs.iter().chain(&self.answers).chain(&self.expected);
+        rows.flatten().copied().chain([self.tolerance]).collect()
+    }
+
+    /// Inverse of `flatten` for an m × n seed
+    pub fn unflatten(nonce: &str, flat: &[i64], (m, n): (usize, usize), quality_bound: i64) -> Option<Self> {
+        let (&tolerance, values) = flat.split_last()?;
+        let count = values.len() / (n + 2 * m).max(1);
+        if count * (n + 2 * m) != values.len() { return None; }
+        let (inputs, rest) = values.split_at(count * n);
+        let (answers, expected) = rest.split_at(count * m);
+        let rows = |xs: &[i64], width: usize| xs.chunks(width.max(1)).map(<[i64]>::to_vec).collect::<Vec<_>>();
+        Some(Self {
+            nonce: nonce.to_string(),
+            inputs: rows(inputs, n),
+            answers: rows(answers, m),
+            expected: rows(expected, m),
+            tolerance,
+            quality_bound,
+        })
+    }
+}
+
+/// "The committed factors answer X with A, and A is close to E"
+#[derive(Debug, Clone)]
+pub struct ChallengeCircuit {
+    pub m: usize,
+    pub n: usize,
+    pub digest: Fr,
+    pub statement: ChallengeStatement,
+    witness: FactorWitness,
+}
+
+impl ChallengeCircuit {
+    /// The statement for `lrim`'s canonical factors, committed under `blinding`
+    pub fn new<T: LrimScalar>(lrim: &LowRankIdentity<T>, blinding: Fr, statement: ChallengeStatement) -> Self {
+        let witness = FactorWitness::new(lrim, blinding);
+        Self { m: lrim.m, n: lrim.n, digest: witness.digest(), statement, witness }
+    }
+
+    /// All-zero assignment of the right shape, for key generation
+    pub fn blank(m: usize, n: usize, rank: usize, count: usize) -> Self {
+        let statement = ChallengeStatement {
+            nonce: encode_hex(&Fr::ZERO),
+            inputs: vec![vec![0; n]; count],
+            answers: vec![vec![0; m]; count],
+            expected: vec![vec![0; m]; count],
+            tolerance: 0,
+            quality_bound: 0,
+        };
+        Self { m, n, digest: Fr::ZERO, statement, witness: FactorWitness::blank(m, n, rank) }
+    }
+
+    /// `None` if the nonce does not decode
+    pub fn public_inputs(digest: Fr, statement: &ChallengeStatement) -> Option<Vec<Fr>> {
+        let mut inputs = vec![digest, decode_hex(&statement.nonce)?];
+        let rows = statement.inputs.iter().chain(&statement.answers).chain(&statement.expected);
+        inputs.extend(rows.flatten().map(|&x| Fr::from(x)));
+        inputs.push(Fr::from(statement.tolerance.max(0)) * Fr::from(1u128 << 96));
+        let q = statement.quality_bound.max(0) as u128;
+        inputs.push(Fr::from(q * q));
+        Some(inputs)
+    }
+}
+
+impl Circuit for ChallengeCircuit {
+    fn shape(&self) -> CircuitShape {
+        CircuitShape::Challenge { m: self.m, n: self.n, rank: self.witness.rank(), count: self.statement.inputs.len() }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for ChallengeCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let (w, s) = (&self.witness, &self.statement);
+        let (m, n, count) = (self.m, self.n, s.inputs.len());
+        if !w.has_shape(m, n)
+            || s.inputs.iter().any(|x| x.len() != n)
+            || [&s.answers, &s.expected].iter().any(|rows| rows.len() != count || rows.iter().any(|y| y.len() != m))
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let public = Self::public_inputs(self.digest, s).ok_or(SynthesisError::Unsatisfiable)?;
+        let public = public.into_iter().map(|x| Wire::input(&cs, x)).collect::<Result<Vec<_>, _>>()?;
+        let (digest, nonce) = (&public[0], &public[1]);
+        let inputs: Vec<&[Wire]> = public[2..2 + count * n].chunks(n.max(1)).collect();
+        let answers = &public[2 + count * n..2 + count * (n + m)];
+        let expected = &public[2 + count * (n + m)..2 + count * (n + 2 * m)];
+        let (tolerance, bound) = (&public[public.len() - 2], &public[public.len() - 1]);
+
+        // An unconstrained input would not be bound by the proof
+        nonce.mul(&cs, nonce)?;
+
+        let blinding = Wire::witness(&cs, w.blinding)?;
+        let f = w.factors.allocate(&cs, Some(VALUE_BITS), Some(UNIT_BITS))?;
+
+        // The factors are the committed ones
+        let u_digest = mimc_hash_wires(&cs, f.u.iter().flatten())?;
+        let committed = std::iter::once(&blinding).chain(&f.sigma).chain(f.v.iter().flatten()).chain([&u_digest]);
+        mimc_hash_wires(&cs, committed)?.enforce_equal(&cs, digest)?;
+
+        // W = V Σ, column by column
+        let wv = f.sigma.iter().zip(&f.v)
+            .map(|(s, col)| col.iter().map(|x| x.mul(&cs, s)).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let u_rows: Vec<Vec<Wire>> = (0..m).map(|j| f.u.iter().map(|col| col[j].clone()).collect()).collect();
+        let scale = Fr::from(1u128 << 96);
+        let mut error = Wire::constant(Fr::ZERO);
+        for (c, x) in inputs.iter().enumerate() {
+            // t = Wᵀx, then U t against the answer
+            let t = Wire::mat_vec(&cs, &wv, x)?;
+            for (j, product) in Wire::mat_vec(&cs, &u_rows, &t)?.iter().enumerate() {
+                let answer = &answers[c * m + j];
+                answer.scale(scale).sub(product).enforce_abs_leq(&cs, tolerance, TOLERANCE_BITS)?;
+
+                let miss = answer.sub(&expected[c * m + j]);
+                error = error.add(&miss.mul(&cs, &miss)?);
+            }
+        }
+        error.enforce_leq(&cs, bound, QUALITY_BITS)
+    }
+}
+
+/// Salted hash the verifier commits to E with
+fn expected_hash(session: &str, expected: &[Vec<i64>], salt: &str) -> String {
+    let bytes = serde_json::to_vec(expected).expect("answers serialise");
+    let digest = Sha256::new()
+        .chain_update(EXPECTED_TAG)
+        .chain_update(session.as_bytes())
+        .chain_update([0])
+        .chain_update(salt.as_bytes())
+        .chain_update([0])
+        .chain_update(bytes)
+        .finalize();
+    hex::encode(digest)
+}
+
+/// What the verifier sends to open a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Challenge {
+    pub session: String,
+    /// Hex-encoded field element
+    pub nonce: String,
+    /// `matrix_hash` of the prover's commitment
+    pub commitment: String,
+    pub domain: String,
+    /// Q32.32, one row per challenge
+    pub inputs: Vec<Vec<i64>>,
+    /// Q32.32
+    pub tolerance: i64,
+    /// Q32.32
+    pub quality_bound: i64,
+    /// Salted hash of the expected answers
+    pub expected_hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChallengeMessage {
+    /// Verifier → prover
+    Challenge(Challenge),
+    /// Prover → verifier: A in Q32.32, one row per challenge
+    Answers { session: String, answers: Vec<Vec<i64>> },
+    /// Verifier → prover: E in Q32.32 and the salt of its hash
+    Reveal { session: String, expected: Vec<Vec<i64>>, salt: String },
+    /// Prover → verifier
+    Proof { session: String, proof: Box<CapabilityProof> },
+}
+
+enum ProverSession {
+    /// Answers sent, waiting for E
+    Answered { challenge: Challenge, answers: Vec<Vec<i64>> },
+    Closed,
+}
+
+/// The seed holder's side
+pub struct ChallengeProver<S: ProofSystem = Groth16Backend> {
+    seed: DnaSeed,
+    keys: CircuitKeys<S>,
+    sessions: HashMap<String, ProverSession>,
+    /// Nonces of every challenge answered so far
+    nonces: HashSet<String>,
+}
+
+impl<S: ProofSystem> ChallengeProver<S> {
+    /// `None` if the seed's opening is missing or wrong, or the keys are
+    /// not for challenges to the seed's shape
+    pub fn new(seed: DnaSeed, keys: CircuitKeys<S>) -> Option<Self> {
+        if !seed.commitment.verify_opening(&seed.lrim, seed.opening.as_ref()?) { return None; }
+        let CircuitShape::Challenge { m, n, rank, .. } = keys.shape else { return None };
+        let fits = (m, n) == seed.commitment.committed_dims && rank == seed.commitment.committed_rank;
+        fits.then(|| Self { seed, keys, sessions: HashMap::new(), nonces: HashSet::new() })
+    }
+
+    /// Reply to a verifier message; `None` if it is out of turn, replayed
+    /// or malformed, or the answers miss the quality bound
+    pub fn receive(&mut self, message: &ChallengeMessage) -> Option<ChallengeMessage> {
+        match message {
+            ChallengeMessage::Challenge(challenge) => self.answer(challenge),
+            ChallengeMessage::Reveal { session, expected, salt } => self.prove(session, expected, salt),
+            _ => None,
+        }
+    }
+
+    fn answer(&mut self, challenge: &Challenge) -> Option<ChallengeMessage> {
+        let CircuitShape::Challenge { n, count, .. } = self.keys.shape else { return None };
+        if challenge.commitment != self.seed.commitment.matrix_hash
+            || challenge.inputs.len() != count || challenge.inputs.iter().any(|x| x.len() != n)
+            || self.sessions.contains_key(&challenge.session)
+            || !self.nonces.insert(challenge.nonce.clone())
+        {
+            return None;
+        }
+        let answers = self.answers(&challenge.inputs);
+        let session = challenge.session.clone();
+        let state = ProverSession::Answered { challenge: challenge.clone(), answers: answers.clone() };
+        self.sessions.insert(session.clone(), state);
+        Some(ChallengeMessage::Answers { session, answers })
+    }
+
+    /// `express_on` each input, in Q32.32
+    fn answers(&self, inputs: &[Vec<i64>]) -> Vec<Vec<i64>> {
+        inputs.iter()
+            .map(|x| {
+                let x = DVector::from_iterator(x.len(), x.iter().map(|&v| from_fixed(v, FIXED_POINT_FRAC_BITS)));
+                self.seed.express_on(&x).iter().map(|&y| fixed(y)).collect()
+            })
+            .collect()
+    }
+
+    /// Answer a published challenge without a verifier; `None` if the
+    /// answers miss the quality bound or the keys are for another count
+    pub fn prove_public(&self, challenge: &PublicChallenge) -> Option<CapabilityProof> {
+        let mut statement = challenge.statement(&self.seed.commitment)?;
+        statement.answers = self.answers(&statement.inputs);
+        let opening = self.seed.opening.as_ref()?;
+        CapabilityProof::prove_challenge_response(&self.keys, &self.seed.lrim, opening, &challenge.domain, &statement)
+    }
+
+    fn prove(&mut self, session: &str, expected: &[Vec<i64>], salt: &str) -> Option<ChallengeMessage> {
+        let state = self.sessions.insert(session.to_string(), ProverSession::Closed)?;
+        let ProverSession::Answered { challenge, answers } = state else { return None };
+        if expected_hash(session, expected, salt) != challenge.expected_hash { return None; }
+        let statement = ChallengeStatement {
+            nonce: challenge.nonce,
+            inputs: challenge.inputs,
+            answers,
+            expected: expected.to_vec(),
+            tolerance: challenge.tolerance,
+            quality_bound: challenge.quality_bound,
+        };
+        let opening = self.seed.opening.as_ref()?;
+        let proof = CapabilityProof::prove_challenge_response(&self.keys, &self.seed.lrim, opening, &challenge.domain, &statement)?;
+        Some(ChallengeMessage::Proof { session: session.to_string(), proof: Box::new(proof) })
+    }
+}
+
+enum VerifierSession {
+    /// Waiting for the answers
+    Challenged { challenge: Challenge, expected: Vec<Vec<i64>>, salt: String },
+    /// E revealed, waiting for the proof
+    Revealed { statement: ChallengeStatement },
+    Finished(bool),
+}
+
+/// The challenger's side, for one prover commitment
+pub struct ChallengeVerifier<S: ProofSystem = Groth16Backend> {
+    commitment: ZkCommitment,
+    key: CircuitVerifyingKey<S>,
+    sessions: HashMap<String, VerifierSession>,
+}
+
+impl<S: ProofSystem> ChallengeVerifier<S> {
+    /// `None` if the key is not for challenges to the commitment's shape
+    pub fn new(commitment: ZkCommitment, key: CircuitVerifyingKey<S>) -> Option<Self> {
+        let CircuitShape::Challenge { m, n, rank, .. } = key.shape else { return None };
+        let fits = !commitment.is_dense() && (m, n) == commitment.committed_dims && rank == commitment.committed_rank;
+        fits.then(|| Self { commitment, key, sessions: HashMap::new() })
+    }
+
+    /// Open a session with the columns of `inputs` (n × c) as problems and
+    /// the columns of `expected` (m × c) as the answers to compare against.
+    /// `tolerance` is the per-entry slack between the prover's answers and
+    /// its exact fixed-point product; `quality_bound` bounds ‖A − E‖_F.
+    pub fn challenge(
+        &mut self,
+        domain: &str,
+        inputs: &DMatrix<f64>,
+        expected: &DMatrix<f64>,
+        tolerance: f64,
+        quality_bound: f64,
+    ) -> Option<ChallengeMessage> {
+        let (m, n) = self.commitment.committed_dims;
+        let CircuitShape::Challenge { count, .. } = self.key.shape else { return None };
+        if inputs.shape() != (n, count) || expected.shape() != (m, count) { return None; }
+        let session = uuid::Uuid::new_v4().to_string();
+        let expected = fixed_columns(expected);
+        let salt = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+        let challenge = Challenge {
+            session: session.clone(),
+            nonce: encode_hex(&random_scalar()),
+            commitment: self.commitment.matrix_hash.clone(),
+            domain: domain.to_string(),
+            inputs: fixed_columns(inputs),
+            tolerance: fixed(tolerance).max(0),
+            quality_bound: fixed(quality_bound).max(0),
+            expected_hash: expected_hash(&session, &expected, &salt),
+        };
+        let state = VerifierSession::Challenged { challenge: challenge.clone(), expected, salt };
+        self.sessions.insert(session, state);
+        Some(ChallengeMessage::Challenge(challenge))
+    }
+
+    /// Take a prover message; returns the reveal after the answers, and
+    /// `None` otherwise. A session whose proof fails to check, or that
+    /// sees a message out of turn, finishes rejected.
+    pub fn receive(&mut self, message: &ChallengeMessage) -> Option<ChallengeMessage> {
+        let (session, proof) = match message {
+            ChallengeMessage::Answers { session, answers } => {
+                let state = self.sessions.get_mut(session)?;
+                let VerifierSession::Challenged { challenge, expected, salt } = state else {
+                    *state = VerifierSession::Finished(false);
+                    return None;
+                };
+                let m = self.commitment.committed_dims.0;
+                if answers.len() != challenge.inputs.len() || answers.iter().any(|a| a.len() != m) {
+                    *state = VerifierSession::Finished(false);
+                    return None;
+                }
+                let reveal = ChallengeMessage::Reveal { session: session.clone(), expected: expected.clone(), salt: salt.clone() };
+                let statement = ChallengeStatement {
+                    nonce: challenge.nonce.clone(),
+                    inputs: challenge.inputs.clone(),
+                    answers: answers.clone(),
+                    expected: std::mem::take(expected),
+                    tolerance: challenge.tolerance,
+                    quality_bound: challenge.quality_bound,
+                };
+                *state = VerifierSession::Revealed { statement };
+                return Some(reveal);
+            }
+            ChallengeMessage::Proof { session, proof } => (session, proof),
+            _ => return None,
+        };
+        let state = self.sessions.get_mut(session)?;
+        let accepted = match state {
+            VerifierSession::Revealed { statement } => {
+                proof.challenge_statement(self.commitment.committed_dims).as_ref() == Some(statement)
+                    && proof.verify_against_commitment(&self.commitment, &self.key)
+            }
+            _ => false,
+        };
+        *state = VerifierSession::Finished(accepted);
+        None
+    }
+
+    /// Whether the session's proof was accepted; `None` while it is open
+    pub fn outcome(&self, session: &str) -> Option<bool> {
+        match self.sessions.get(session)? {
+            VerifierSession::Finished(accepted) => Some(*accepted),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deliver a message as JSON
+    fn wire(message: &ChallengeMessage) -> ChallengeMessage {
+        serde_json::from_slice(&serde_json::to_vec(message).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_challenge_response_sessions() {
+        let k = DMatrix::<f64>::new_random(4, 3);
+        let seed = DnaSeed::new("alice", &k, 2, vec!["test".into()]);
+        let keys: CircuitKeys = CircuitKeys::setup(CircuitShape::Challenge { m: 4, n: 3, rank: 2, count: 2 }).unwrap();
+        let mut verifier = ChallengeVerifier::new(seed.commitment.clone(), keys.verifying_key()).unwrap();
+        let mut prover = ChallengeProver::new(seed.clone(), keys).unwrap();
+
+        // Bob expects roughly what his own copy of the knowledge says
+        let x = sample_challenges(&DMatrix::identity(3, 2), 2);
+        let expected = seed.lrim.reconstruct() * &x + DMatrix::from_element(4, 2, 0.01);
+        let challenge = verifier.challenge("test", &x, &expected, 1e-6, 0.1).unwrap();
+        let ChallengeMessage::Challenge(sent) = &challenge else { unreachable!() };
+        let session = sent.session.clone();
+        let answers = prover.receive(&wire(&challenge)).unwrap();
+        // The same challenge is answered only once
+        assert!(prover.receive(&challenge).is_none());
+        let reveal = verifier.receive(&wire(&answers)).unwrap();
+        let proof = prover.receive(&wire(&reveal)).unwrap();
+        assert!(prover.receive(&reveal).is_none());
+        let ChallengeMessage::Proof { proof: published, .. } = &proof else { unreachable!() };
+        assert_eq!(published.response.as_ref().unwrap().len(), 8);
+        assert!(verifier.receive(&wire(&proof)).is_none());
+        assert_eq!(verifier.outcome(&session), Some(true));
+
+        // Replaying the proof into a fresh session is rejected
+        let fresh = verifier.challenge("test", &x, &expected, 1e-6, 0.1).unwrap();
+        let ChallengeMessage::Challenge(fresh) = fresh else { unreachable!() };
+        let mut replayed = proof.clone();
+        let ChallengeMessage::Proof { session, .. } = &mut replayed else { unreachable!() };
+        *session = fresh.session.clone();
+        let reveal = verifier.receive(&ChallengeMessage::Answers { session: fresh.session.clone(), answers: vec![vec![0; 4]; 2] });
+        assert!(reveal.is_some());
+        verifier.receive(&replayed);
+        assert_eq!(verifier.outcome(&fresh.session), Some(false));
+
+        // Answers far from what Bob expects cannot be proved
+        let challenge = verifier.challenge("test", &x, &(expected * 2.0), 1e-6, 0.1).unwrap();
+        let answers = prover.receive(&challenge).unwrap();
+        let reveal = verifier.receive(&answers).unwrap();
+        assert!(prover.receive(&reveal).is_none());
+
+        // Non-interactive: the challenges come from a transcript over the
+        // commitment, and the serialised proof checks on its own
+        let public = PublicChallenge {
+            domain: "test".into(),
+            basis: DMatrix::identity(3, 2),
+            reference: seed.lrim.reconstruct(),
+            count: 2,
+            tolerance: 1e-6,
+            quality_bound: 1e-3,
+        };
+        let proof = prover.prove_public(&public).unwrap();
+        let proof: CapabilityProof = serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert!(proof.verify_public_challenge(&seed.commitment, &verifier.key, &public));
+        let other = PublicChallenge { domain: "other".into(), ..public.clone() };
+        assert!(!proof.verify_public_challenge(&seed.commitment, &verifier.key, &other));
+        let mut edited = proof.clone();
+        edited.claim.push('!');
+        assert!(!edited.verify_public_challenge(&seed.commitment, &verifier.key, &public));
+    }
+}
@@ -0,0 +1,338 @@
+//! Groth16 capability circuit over BN254
+//!
+//! Proves |Σᵢ σᵢ·(vᵢ·d)| ≥ τ for a public domain vector d and threshold τ,
+//! where σ and V are the private factors behind a seed's commitment.
+//!
+//! Pedersen points live in G1, whose coordinates are not in the circuit's
+//! scalar field, so the circuit binds to a second, SNARK-friendly
+//! commitment instead: a MiMC digest (x⁷, 91 rounds, Miyaguchi–Preneel) of
+//! a blinding, Σ, V and a digest of U, all as Q32.32 values in Fr. The
+//! committer's opening covers both commitments; a consistency proof (see
+//! `zk::consistency`) shows others that they commit to the same factors.
+//!
+//! Arithmetic is exact: σ and V are range-checked to signed 64 bits (d is
+//! public and an i64 by construction), so s = Σᵢ σᵢ Σₖ Vₖᵢ dₖ at scale 2^96
+//! never wraps. The prover supplies the sign of s and shows that
+//! |s| − τ·2^96 fits in a few more bits than |s| can need; with the wrong
+//! sign, |s| would be a field element close to the modulus.
+//!
+//! Public inputs, in order: the digest, d (Q32.32), τ·2^64 (τ in Q32.32).
+
+use super::backend::{Groth16Backend, ProofSystem};
+use super::challenge::ChallengeCircuit;
+use super::consistency::ConsistencyCircuit;
+use super::gadgets::{FixedFactors, Wire, VALUE_BITS};
+use super::pedersen::{committed_vectors, CommittedVector};
+use super::rank::RankBoundCircuit;
+use super::reconstruction::ReconstructionCircuit;
+use crate::seed::{LowRankIdentity, LrimScalar};
+use ark_bn254::{Bn254, Fr};
+use ark_ff::{AdditiveGroup, Field, PrimeField};
+use ark_groth16::{Groth16, ProvingKey};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+pub const MIMC_ROUNDS: usize = 91;
+const MIMC_TAG: &[u8] = b"DLRS/MIMC7/BN254/v1\0";
+
+fn mimc_constants() -> &'static [Fr] {
+    static CONSTANTS: OnceLock<Vec<Fr>> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        (0..MIMC_ROUNDS as u64)
+            .map(|i| {
+                let digest = Sha256::new().chain_update(MIMC_TAG).chain_update(i.to_le_bytes()).finalize();
+                Fr::from_le_bytes_mod_order(&digest)
+            })
+            .collect()
+    })
+}
+
+/// MiMC-7 block cipher E_k(x)
+fn mimc_encrypt(key: Fr, mut x: Fr) -> Fr {
+    for c in mimc_constants() {
+        let t = x + key + c;
+        let t2 = t.square();
+        x = t2.square() * t2 * t;
+    }
+    x + key
+}
+
+/// Miyaguchi–Preneel hash: h ← E_h(m) + h + m for each input
+pub fn mimc_hash(inputs: &[Fr]) -> Fr {
+    inputs.iter().fold(Fr::ZERO, |h, &m| mimc_encrypt(h, m) + h + m)
+}
+
+/// Q32.32 factors of the canonical factorization, as the circuit sees them
+#[derive(Debug, Clone)]
+pub(super) struct FactorWitness {
+    pub blinding: Fr,
+    pub factors: FixedFactors,
+    /// U enters the digest through this
+    pub u_digest: Fr,
+}
+
+impl FactorWitness {
+    pub fn new<T: LrimScalar>(lrim: &LowRankIdentity<T>, blinding: Fr) -> Self {
+        let mut factors = FixedFactors { sigma: Vec::new(), u: Vec::new(), v: Vec::new() };
+        for (vector, values) in committed_vectors(lrim) {
+            match vector {
+                CommittedVector::Sigma => factors.sigma = values,
+                CommittedVector::U(_) => factors.u.push(values),
+                CommittedVector::V(_) => factors.v.push(values),
+            }
+        }
+        let u_digest = mimc_hash(&factors.u.iter().flatten().map(|&x| Fr::from(x)).collect::<Vec<_>>());
+        Self { blinding, factors, u_digest }
+    }
+
+    pub fn blank(m: usize, n: usize, rank: usize) -> Self {
+        let factors = FixedFactors { sigma: vec![0; rank], u: vec![vec![0; m]; rank], v: vec![vec![0; n]; rank] };
+        Self { blinding: Fr::ZERO, factors, u_digest: Fr::ZERO }
+    }
+
+    pub fn rank(&self) -> usize {
+        self.factors.rank()
+    }
+
+    /// Whether the factors are r columns of length m and n
+    pub fn has_shape(&self, m: usize, n: usize) -> bool {
+        let f = &self.factors;
+        f.u.len() == f.rank() && f.v.len() == f.rank()
+            && f.u.iter().all(|col| col.len() == m) && f.v.iter().all(|col| col.len() == n)
+    }
+
+    pub fn digest(&self) -> Fr {
+        let f = &self.factors;
+        let mut inputs = vec![self.blinding];
+        inputs.extend(f.sigma.iter().chain(f.v.iter().flatten()).map(|&x| Fr::from(x)));
+        inputs.push(self.u_digest);
+        mimc_hash(&inputs)
+    }
+}
+
+/// The circuit-facing commitment to `lrim` under `blinding`
+pub fn factor_digest<T: LrimScalar>(lrim: &LowRankIdentity<T>, blinding: Fr) -> Fr {
+    FactorWitness::new(lrim, blinding).digest()
+}
+
+/// h ← E_h(m) + h + m, four constraints per round
+fn mimc_absorb(cs: &ConstraintSystemRef<Fr>, h: &Wire, m: &Wire) -> Result<Wire, SynthesisError> {
+    let mut x = m.clone();
+    for &c in mimc_constants() {
+        let t = x.add(h).add(&Wire::constant(c));
+        let t2 = t.mul(cs, &t)?;
+        let t4 = t2.mul(cs, &t2)?;
+        let t6 = t4.mul(cs, &t2)?;
+        x = t6.mul(cs, &t)?;
+    }
+    Ok(x.add(h).add(h).add(m))
+}
+
+/// In-circuit `mimc_hash`
+pub(super) fn mimc_hash_wires<'a>(
+    cs: &ConstraintSystemRef<Fr>,
+    inputs: impl IntoIterator<Item = &'a Wire>,
+) -> Result<Wire, SynthesisError> {
+    inputs.into_iter().try_fold(Wire::constant(Fr::ZERO), |h, m| mimc_absorb(cs, &h, m))
+}
+
+/// Bits that |s| − τ·2^96 must fit in: each product of three range-checked
+/// values is at most 2^189 in magnitude, and there are n·r of them
+fn score_bits(n: usize, rank: usize) -> u32 {
+    3 * (VALUE_BITS - 1) + 1 + (usize::BITS - (n * rank).leading_zeros())
+}
+
+/// The capability statement for one committed seed
+#[derive(Debug, Clone)]
+pub struct CapabilityCircuit {
+    pub digest: Fr,
+    /// Q32.32
+    pub domain: Vec<i64>,
+    /// Q32.32, non-negative
+    pub threshold: i64,
+    witness: FactorWitness,
+}
+
+impl CapabilityCircuit {
+    /// The statement for `lrim`'s canonical factors, committed under `blinding`
+    pub fn new<T: LrimScalar>(lrim: &LowRankIdentity<T>, blinding: Fr, domain: Vec<i64>, threshold: i64) -> Self {
+        let witness = FactorWitness::new(lrim, blinding);
+        Self { digest: witness.digest(), domain, threshold: threshold.max(0), witness }
+    }
+
+    /// All-zero assignment of the right shape, for key generation
+    pub fn blank(n: usize, rank: usize) -> Self {
+        Self { digest: Fr::ZERO, domain: vec![0; n], threshold: 0, witness: FactorWitness::blank(0, n, rank) }
+    }
+
+    pub fn public_inputs(digest: Fr, domain: &[i64], threshold: i64) -> Vec<Fr> {
+        let mut inputs = vec![digest];
+        inputs.extend(domain.iter().map(|&x| Fr::from(x)));
+        inputs.push(Fr::from(threshold.max(0)) * Fr::from(1u128 << 64));
+        inputs
+    }
+}
+
+impl Circuit for CapabilityCircuit {
+    fn shape(&self) -> CircuitShape {
+        CircuitShape::Capability { n: self.domain.len(), rank: self.witness.rank() }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for CapabilityCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let w = &self.witness;
+        let (n, rank) = (self.domain.len(), w.rank());
+        if w.factors.v.len() != rank || w.factors.v.iter().any(|col| col.len() != n) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let public = Self::public_inputs(self.digest, &self.domain, self.threshold);
+        let public = public.into_iter().map(|x| Wire::input(&cs, x)).collect::<Result<Vec<_>, _>>()?;
+        let (digest, domain, threshold) = (&public[0], &public[1..=n], &public[n + 1]);
+
+        // U stays out of the circuit, so only σ and V become wires
+        let blinding = Wire::witness(&cs, w.blinding)?;
+        let signed = |xs: &[i64]| xs.iter().map(|&x| Wire::witness_signed(&cs, x, VALUE_BITS)).collect::<Result<Vec<_>, _>>();
+        let sigma = signed(&w.factors.sigma)?;
+        let v = w.factors.v.iter().map(|col| signed(col)).collect::<Result<Vec<_>, _>>()?;
+        let u_digest = Wire::witness(&cs, w.u_digest)?;
+
+        // The factors are the committed ones
+        let inputs = std::iter::once(&blinding).chain(&sigma).chain(v.iter().flatten()).chain([&u_digest]);
+        mimc_hash_wires(&cs, inputs)?.enforce_equal(&cs, digest)?;
+
+        // s = Σᵢ σᵢ (vᵢ · d), then |s| ≥ τ
+        let projections = Wire::mat_vec(&cs, &v, domain)?;
+        let s = Wire::dot(&cs, &sigma, &projections)?;
+        s.enforce_abs_geq(&cs, threshold, score_bits(n, rank))
+    }
+}
+
+/// Which circuit a key pair is for, and its dimensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CircuitShape {
+    /// Seeds with domain dimension `n` and rank `rank`
+    Capability { n: usize, rank: usize },
+    /// An m × n matrix of rank at most `rank`, committed densely or as factors
+    RankBound { m: usize, n: usize, rank: usize, dense: bool },
+    /// `count` challenge inputs to an m × n seed of rank `rank`
+    Challenge { m: usize, n: usize, rank: usize, count: usize },
+    /// `count` benchmark inputs to an m × n seed of rank `rank`
+    Reconstruction { m: usize, n: usize, rank: usize, count: usize },
+    /// The two commitments to an m × n seed of rank `rank` agree
+    Consistency { m: usize, n: usize, rank: usize },
+}
+
+/// A circuit instance; its shape selects the keys it is proved with
+pub trait Circuit: ConstraintSynthesizer<Fr> + Clone {
+    fn shape(&self) -> CircuitShape;
+
+    /// The witness satisfies every constraint
+    fn is_satisfied(&self) -> bool {
+        let cs = ConstraintSystem::new_ref();
+        self.clone().generate_constraints(cs.clone()).is_ok() && cs.is_satisfied().unwrap_or(false)
+    }
+}
+
+impl CircuitShape {
+    /// Groth16 parameters for the all-zero instance of this shape
+    pub(super) fn blank_keys(self, rng: &mut impl rand::Rng) -> Option<ProvingKey<Bn254>> {
+        match self {
+            CircuitShape::Capability { n, rank } => {
+                Groth16::<Bn254>::generate_random_parameters_with_reduction(CapabilityCircuit::blank(n, rank), rng).ok()
+            }
+            CircuitShape::RankBound { m, n, rank, dense } => {
+                let blank = RankBoundCircuit::blank(m, n, rank, dense);
+                Groth16::<Bn254>::generate_random_parameters_with_reduction(blank, rng).ok()
+            }
+            CircuitShape::Challenge { m, n, rank, count } => {
+                let blank = ChallengeCircuit::blank(m, n, rank, count);
+                Groth16::<Bn254>::generate_random_parameters_with_reduction(blank, rng).ok()
+            }
+            CircuitShape::Reconstruction { m, n, rank, count } => {
+                let blank = ReconstructionCircuit::blank(m, n, rank, count);
+                Groth16::<Bn254>::generate_random_parameters_with_reduction(blank, rng).ok()
+            }
+            CircuitShape::Consistency { m, n, rank } => {
+                let blank = ConsistencyCircuit::blank(m, n, rank);
+                Groth16::<Bn254>::generate_random_parameters_with_reduction(blank, rng).ok()
+            }
+        }
+    }
+}
+
+/// Verifying key for one circuit shape
+#[derive(Debug, Clone)]
+pub struct CircuitVerifyingKey<S: ProofSystem = Groth16Backend> {
+    pub shape: CircuitShape,
+    pub key: S::VerifyingKey,
+}
+
+impl<S: ProofSystem> CircuitVerifyingKey<S> {
+    pub fn verify(&self, public_inputs: &[Fr], proof: &S::Proof) -> bool {
+        S::verify(&self.key, public_inputs, proof)
+    }
+}
+
+/// Proving key for one circuit shape
+#[derive(Debug, Clone)]
+pub struct CircuitKeys<S: ProofSystem = Groth16Backend> {
+    pub shape: CircuitShape,
+    pub proving_key: S::ProvingKey,
+}
+
+impl<S: ProofSystem> CircuitKeys<S> {
+    pub fn setup(shape: CircuitShape) -> Option<Self> {
+        Some(Self { shape, proving_key: S::setup(shape)? })
+    }
+
+    pub fn verifying_key(&self) -> CircuitVerifyingKey<S> {
+        CircuitVerifyingKey { shape: self.shape, key: S::verifying_key(&self.proving_key) }
+    }
+
+    /// `None` if the circuit is for another shape or its claim is false
+    pub fn prove(&self, circuit: impl Circuit) -> Option<S::Proof> {
+        if circuit.shape() != self.shape { return None; }
+        S::prove(&self.proving_key, circuit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed::{to_fixed, FIXED_POINT_FRAC_BITS};
+    use nalgebra::{DMatrix, DVector};
+
+    #[test]
+    fn test_capability_circuit() {
+        let lrim = LowRankIdentity::from_matrix(&DMatrix::new_random(6, 4), 2);
+        let d = DVector::<f64>::new_random(4).normalize();
+        let domain: Vec<i64> = d.iter().map(|&x| to_fixed(x, FIXED_POINT_FRAC_BITS)).collect();
+        let canonical = lrim.canonical();
+        let score = canonical.capability_in_domain(&d);
+        let below = to_fixed(score * 0.99, FIXED_POINT_FRAC_BITS);
+        let above = to_fixed(score * 1.01, FIXED_POINT_FRAC_BITS);
+        let blinding = Fr::from(42u64);
+
+        assert!(CapabilityCircuit::new(&lrim, blinding, domain.clone(), below).is_satisfied());
+        assert!(!CapabilityCircuit::new(&lrim, blinding, domain.clone(), above).is_satisfied());
+        // The sign of the score does not matter
+        let negated: Vec<i64> = domain.iter().map(|x| -x).collect();
+        assert!(CapabilityCircuit::new(&lrim, blinding, negated, below).is_satisfied());
+
+        let keys: CircuitKeys = CircuitKeys::setup(CircuitShape::Capability { n: 4, rank: 2 }).unwrap();
+        let vk = keys.verifying_key();
+        let digest = factor_digest(&lrim, blinding);
+        assert!(keys.prove(CapabilityCircuit::new(&lrim, blinding, domain.clone(), above)).is_none());
+        let proof = keys.prove(CapabilityCircuit::new(&lrim, blinding, domain.clone(), below)).unwrap();
+        let inputs = |digest, domain: &[i64], threshold| CapabilityCircuit::public_inputs(digest, domain, threshold);
+        assert!(vk.verify(&inputs(digest, &domain, below), &proof));
+        assert!(!vk.verify(&inputs(digest, &domain, above), &proof));
+        assert!(!vk.verify(&inputs(digest + Fr::ONE, &domain, below), &proof));
+        let mut other = domain.clone();
+        other[0] += 1;
+        assert!(!vk.verify(&inputs(digest, &other, below), &proof));
+    }
+}
@@ -3,18 +3,27 @@
 //! Commit to LRIM without revealing U, Σ, V.
 //! Anyone can verify the commitment matches future proofs.
 
-use crate::seed::LowRankIdentity;
+use super::circuit::factor_digest;
+use super::rank::dense_digest;
+use super::pedersen::{decode_hex, encode_hex, random_scalar, ColumnOpening, PedersenCommitment, PedersenOpening};
+use crate::seed::{LowRankIdentity, LrimScalar, QuantizedLrim};
+use nalgebra::DMatrix;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 
 /// A cryptographic commitment to a low-rank identity matrix
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZkCommitment {
+    /// Digest of the Pedersen points, or of the payload for quantized seeds
     pub matrix_hash: String,
     pub committed_rank: usize,
     pub committed_dims: (usize, usize),
-    pub blinding_hash: String,
     pub sigma_norm_commitment: f64,
+    /// Per-column commitments to the factors (absent for quantized payloads)
+    #[serde(default)]
+    pub pedersen: Option<PedersenCommitment>,
+    /// MiMC digest of the factors that capability proofs bind to (hex Fr)
+    #[serde(default)]
+    pub circuit_digest: Option<String>,
 }
 
 impl Default for ZkCommitment {
@@ -23,35 +32,99 @@ impl Default for ZkCommitment {
             matrix_hash: String::new(),
             committed_rank: 0,
             committed_dims: (0, 0),
-            blinding_hash: String::new(),
             sigma_norm_commitment: 0.0,
+            pedersen: None,
+            circuit_digest: None,
         }
     }
 }
 
 impl ZkCommitment {
-    pub fn from_lrim(lrim: &LowRankIdentity) -> Self {
-        let matrix_hash = lrim.fingerprint();
-        let blinding = rand::random::<[u8; 32]>();
-        let mut hasher = Sha256::new();
-        hasher.update(&blinding);
-        hasher.update(matrix_hash.as_bytes());
-        let blinding_hash = hex::encode(hasher.finalize());
-        let sigma_norm: f64 = lrim.sigma.iter().map(|s| s * s).sum::<f64>().sqrt();
-        Self {
-            matrix_hash,
+    /// Pedersen-commit to every column of U and V and to Σ, and to all of
+    /// them at once in a circuit digest. The opening is needed to verify
+    /// the commitment and to prove capabilities later and must be kept.
+    /// Only the opening ties the points to the digest; others need a
+    /// consistency proof (`CapabilityProof::prove_consistency`).
+    pub fn commit<T: LrimScalar>(lrim: &LowRankIdentity<T>) -> (Self, PedersenOpening) {
+        let (pedersen, mut opening) = PedersenCommitment::commit(lrim);
+        let blinding = random_scalar();
+        opening.digest = encode_hex(&blinding);
+        let sigma_norm: f64 = lrim.sigma.iter().map(|s| s.to_f64().powi(2)).sum::<f64>().sqrt();
+        let commitment = Self {
+            matrix_hash: pedersen.digest(),
             committed_rank: lrim.rank,
             committed_dims: (lrim.m, lrim.n),
-            blinding_hash,
             sigma_norm_commitment: sigma_norm,
+            pedersen: Some(pedersen),
+            circuit_digest: Some(encode_hex(&factor_digest(lrim, blinding))),
+        };
+        (commitment, opening)
+    }
+
+    /// Commit to a dense matrix whose rank the committer claims is at most
+    /// `rank`, to be shown with a rank-bound proof. The opening holds only
+    /// the digest blinding.
+    pub fn commit_dense(k: &DMatrix<f64>, rank: usize) -> (Self, PedersenOpening) {
+        let blinding = random_scalar();
+        let digest = encode_hex(&dense_digest(k, blinding));
+        let commitment = Self {
+            matrix_hash: digest.clone(),
+            committed_rank: rank,
+            committed_dims: (k.nrows(), k.ncols()),
+            sigma_norm_commitment: k.norm(),
+            pedersen: None,
+            circuit_digest: Some(digest),
+        };
+        let opening = PedersenOpening { sigma: String::new(), u: Vec::new(), v: Vec::new(), digest: encode_hex(&blinding) };
+        (commitment, opening)
+    }
+
+    /// Made by `commit_dense`: the digest covers entries, not factors
+    pub fn is_dense(&self) -> bool {
+        self.pedersen.is_none() && self.circuit_digest.is_some()
+    }
+
+    pub fn verify_dense(&self, k: &DMatrix<f64>, opening: &PedersenOpening) -> bool {
+        let Some(blinding) = decode_hex(&opening.digest) else { return false };
+        self.is_dense()
+            && (k.nrows(), k.ncols()) == self.committed_dims
+            && self.circuit_digest.as_ref() == Some(&encode_hex(&dense_digest(k, blinding)))
+    }
+
+    /// Commit to a quantized payload exactly as it is transmitted. The
+    /// payload is public, so this is a plain hash binding.
+    pub fn from_quantized(q: &QuantizedLrim) -> Self {
+        Self {
+            matrix_hash: q.payload_hash(),
+            committed_rank: q.rank,
+            committed_dims: (q.m, q.n),
+            sigma_norm_commitment: q.sigma.norm(),
+            pedersen: None,
+            circuit_digest: None,
         }
     }
 
-    pub fn verify(&self, lrim: &LowRankIdentity) -> bool {
-        lrim.fingerprint() == self.matrix_hash
+    /// `lrim` is what was committed, under the blindings in `opening`
+    pub fn verify_opening<T: LrimScalar>(&self, lrim: &LowRankIdentity<T>, opening: &PedersenOpening) -> bool {
+        let Some(pedersen) = &self.pedersen else { return false };
+        pedersen.digest() == self.matrix_hash
             && lrim.rank == self.committed_rank
-            && lrim.m == self.committed_dims.0
-            && lrim.n == self.committed_dims.1
+            && (lrim.m, lrim.n) == self.committed_dims
+            && pedersen.verify_opening(lrim, opening)
+            && self.circuit_digest.as_ref().is_none_or(|digest| {
+                decode_hex(&opening.digest).is_some_and(|blinding| *digest == encode_hex(&factor_digest(lrim, blinding)))
+            })
+    }
+
+    /// A single revealed column matches its commitment
+    pub fn verify_column(&self, opening: &ColumnOpening) -> bool {
+        self.pedersen.as_ref().is_some_and(|p| p.digest() == self.matrix_hash && p.verify_column(opening))
+    }
+
+    pub fn verify_quantized(&self, q: &QuantizedLrim) -> bool {
+        q.payload_hash() == self.matrix_hash
+            && q.rank == self.committed_rank
+            && (q.m, q.n) == self.committed_dims
     }
 
     pub fn public_summary(&self) -> String {
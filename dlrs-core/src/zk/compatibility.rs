@@ -0,0 +1,588 @@
+//! Compatibility check (ZK_MATCH) between two committed seeds
+//!
+//! Alice and Bob learn how much their V subspaces overlap,
+//! ‖V_aᵀV_b‖²_F / min(r_a, r_b) ∈ [0, 1] (the mean squared cosine of the
+//! principal angles), or only whether it reaches a threshold, and nothing
+//! else about each other's factors.
+//!
+//! The protocol is two-party computation over the BN254 scalar field with
+//! a commodity dealer (Du & Atallah) who hands out correlated randomness
+//! beforehand and never sees the inputs. Values are Q32.32 fixed point.
+//!
+//! 0. Each party publishes its canonical V columns masked by dealer
+//!    vectors, V̂ᵢ = Vᵢ + Rᵢ. The dealer has published Pedersen commitments
+//!    to the Rᵢ, so anyone can check Com(Vᵢ) + Com(Rᵢ) = Com(V̂ᵢ) against the
+//!    party's seed commitment.
+//! 1. With the dealer's offsets each party turns the other's masked columns
+//!    into an additive share of G = V_aᵀV_b. Squares use the dealer's pairs
+//!    (α, α²): both open g − α, and g² = α² + 2(g − α)α + (g − α)².
+//! 2. Score mode: both open their share of S = Σ g²ᵢⱼ. Threshold mode: they
+//!    multiply S − T by a dealer mask ρ ∈ [1, 2⁴⁰) with a Beaver triple …
+//! 3. … add δ ∈ [0, ρ) and open only z = ρ(S − T) + δ, whose sign is the
+//!    answer; |S − T| is revealed only up to the unknown factor ρ.
+//!
+//! Every other opened value is uniformly masked. Masked inputs are checked
+//! against the commitments; later rounds assume semi-honest parties.
+//!
+//! Each party signs every message it sends with its seed's opening (see
+//! `zk::pedersen`), over both commitments, the dealer's commitments, the
+//! round and the values. Rewriting any round of a transcript therefore
+//! takes that party's opening, and a transcript verifies against both
+//! commitments: its outcome follows from what the two parties sent.
+
+use super::gadgets::FixedPoint;
+use super::pedersen::{
+    commit_field_vector, committed_vectors, decode_hex, encode_hex, random_scalar, CommittedVector, OpeningKey,
+    OpeningSignature,
+};
+use super::transcript::Transcript;
+use super::{PedersenOpening, ZkCommitment};
+use crate::seed::{to_fixed, LowRankIdentity, FIXED_POINT_FRAC_BITS};
+use ark_bn254::{Fr, G1Affine};
+use ark_ec::CurveGroup;
+use ark_ff::{AdditiveGroup, Field, PrimeField};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+const PROTOCOL: &[u8] = b"DLRS/ZK_MATCH/v1";
+/// ρ is drawn from [1, 2^MASK_BITS)
+const MASK_BITS: u32 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    Alice,
+    Bob,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CompatibilityMode {
+    /// Both learn the overlap
+    Score,
+    /// Both learn only whether the overlap reaches the threshold
+    Threshold(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CompatibilityOutcome {
+    Score(f64),
+    Threshold { threshold: f64, reached: bool },
+}
+
+impl CompatibilityMode {
+    fn rounds(self) -> usize {
+        match self {
+            CompatibilityMode::Score => 3,
+            CompatibilityMode::Threshold(_) => 4,
+        }
+    }
+
+    /// Values each party opens in `round` ≥ 1, for a `gram`-entry Gram
+    /// matrix
+    fn opened(self, round: usize, gram: usize) -> Option<usize> {
+        match (round, self) {
+            (1, _) => Some(gram),
+            (2, CompatibilityMode::Score) => Some(1),
+            (2, CompatibilityMode::Threshold(_)) => Some(2),
+            (3, CompatibilityMode::Threshold(_)) => Some(1),
+            _ => None,
+        }
+    }
+}
+
+/// (share, x − share) with a uniform first share
+fn split(x: Fr) -> (Fr, Fr) {
+    let share = random_scalar();
+    (share, x - share)
+}
+
+fn dot(a: &[Fr], b: &[Fr]) -> Fr {
+    a.iter().zip(b).map(|(x, y)| *x * y).sum()
+}
+
+/// One party's share of the dealer's correlated randomness. Private to
+/// that party.
+#[derive(Clone)]
+pub struct DealerShare {
+    /// Input masks Rᵢ, one per own V column
+    masks: Vec<Vec<Fr>>,
+    mask_blindings: Vec<Fr>,
+    /// Gram offsets, r_a × r_b row-major
+    offsets: Vec<Fr>,
+    /// Shares of (α, α²) per Gram entry
+    squares: Vec<(Fr, Fr)>,
+    /// Shares of ρ, δ and a triple (α, β, αβ); threshold mode only
+    compare: Option<[Fr; 5]>,
+}
+
+/// What the dealer publishes: commitments to every input mask
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DealerCommitments {
+    pub alice: Vec<String>,
+    pub bob: Vec<String>,
+}
+
+/// Correlated randomness for seeds of domain dimension `n` and ranks
+/// `rank_a`, `rank_b`
+pub fn deal(n: usize, rank_a: usize, rank_b: usize, mode: CompatibilityMode) -> (DealerShare, DealerShare, DealerCommitments) {
+    let mask = |rank: usize| -> (Vec<Vec<Fr>>, Vec<Fr>, Vec<String>) {
+        let masks: Vec<Vec<Fr>> = (0..rank).map(|_| (0..n).map(|_| random_scalar()).collect()).collect();
+        let blindings: Vec<Fr> = (0..rank).map(|_| random_scalar()).collect();
+        let points = masks.iter().zip(&blindings).map(|(r, b)| encode_hex(&commit_field_vector(r, b))).collect();
+        (masks, blindings, points)
+    };
+    let (masks_a, blindings_a, points_a) = mask(rank_a);
+    let (masks_b, blindings_b, points_b) = mask(rank_b);
+
+    let mut offsets_a = Vec::new();
+    let mut offsets_b = Vec::new();
+    for ra in &masks_a {
+        for rb in &masks_b {
+            let (a, b) = split(dot(ra, rb));
+            offsets_a.push(a);
+            offsets_b.push(b);
+        }
+    }
+    let (squares_a, squares_b): (Vec<_>, Vec<_>) = (0..rank_a * rank_b)
+        .map(|_| {
+            let alpha = random_scalar();
+            let (a0, b0) = split(alpha);
+            let (a1, b1) = split(alpha.square());
+            ((a0, a1), (b0, b1))
+        })
+        .unzip();
+    let (compare_a, compare_b) = match mode {
+        CompatibilityMode::Score => (None, None),
+        CompatibilityMode::Threshold(_) => {
+            let mut rng = rand::thread_rng();
+            let rho: u64 = rng.gen_range(1..1 << MASK_BITS);
+            let delta = rng.gen_range(0..rho);
+            let (alpha, beta) = (random_scalar(), random_scalar());
+            let values = [Fr::from(rho), Fr::from(delta), alpha, beta, alpha * beta];
+            let shares = values.map(split);
+            (Some(shares.map(|s| s.0)), Some(shares.map(|s| s.1)))
+        }
+    };
+    let alice = DealerShare {
+        masks: masks_a,
+        mask_blindings: blindings_a,
+        offsets: offsets_a,
+        squares: squares_a,
+        compare: compare_a,
+    };
+    let bob = DealerShare {
+        masks: masks_b,
+        mask_blindings: blindings_b,
+        offsets: offsets_b,
+        squares: squares_b,
+        compare: compare_b,
+    };
+    (alice, bob, DealerCommitments { alice: points_a, bob: points_b })
+}
+
+/// Values one party opens in one round
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtocolMessage {
+    pub round: usize,
+    pub sender: Role,
+    /// Hex-encoded field elements
+    pub values: Vec<String>,
+    /// Blindings of the masked columns, round 0 only
+    pub blindings: Vec<String>,
+    /// By the sender, over `signed_bytes`
+    #[serde(default)]
+    pub signature: Option<OpeningSignature>,
+}
+
+impl ProtocolMessage {
+    fn new(round: usize, sender: Role, values: &[Fr]) -> Self {
+        let values = values.iter().map(encode_hex).collect();
+        Self { round, sender, values, blindings: Vec::new(), signature: None }
+    }
+
+    /// What the sender signs: the message in the run `context`
+    fn signed_bytes(&self, context: &[u8]) -> [u8; 32] {
+        let mut transcript = Transcript::new(PROTOCOL);
+        transcript.append_message(b"context", context);
+        transcript.append_u64(b"round", self.round as u64);
+        transcript.append_json(b"sender", &self.sender);
+        transcript.append_json(b"values", &self.values);
+        transcript.append_json(b"blindings", &self.blindings);
+        transcript.challenge_bytes(b"signed")
+    }
+
+    /// Signed by the holder of `sender`'s opening
+    fn is_signed_by(&self, sender: &ZkCommitment, context: &[u8]) -> bool {
+        let (Some(pedersen), Some(signature)) = (&sender.pedersen, &self.signature) else { return false };
+        pedersen.verify_signature(&self.signed_bytes(context), signature)
+    }
+
+    fn field_values(&self) -> Option<Vec<Fr>> {
+        self.values.iter().map(|v| decode_hex(v)).collect()
+    }
+}
+
+/// Masked columns in `message` open the V commitments of `commitment`
+/// shifted by the dealer's mask commitments
+fn check_masked_input(commitment: &ZkCommitment, dealer: &[String], message: &ProtocolMessage) -> bool {
+    let Some(pedersen) = &commitment.pedersen else { return false };
+    let (n, rank) = (commitment.committed_dims.1, commitment.committed_rank);
+    let Some(values) = message.field_values() else { return false };
+    if message.round != 0 || values.len() != n * rank || message.blindings.len() != rank || dealer.len() != rank {
+        return false;
+    }
+    (0..rank).all(|i| {
+        let (Some(committed), Some(mask), Some(blinding)) = (
+            pedersen.point(CommittedVector::V(i)),
+            decode_hex::<G1Affine>(&dealer[i]),
+            decode_hex::<Fr>(&message.blindings[i]),
+        ) else {
+            return false;
+        };
+        commit_field_vector(&values[i * n..(i + 1) * n], &blinding) == (committed + mask).into_affine()
+    })
+}
+
+/// Everything both parties opened, in order, bound to both commitments
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompatibilityTranscript {
+    /// `matrix_hash` of Alice's commitment
+    pub alice: String,
+    /// `matrix_hash` of Bob's commitment
+    pub bob: String,
+    pub mode: CompatibilityMode,
+    pub dealer: DealerCommitments,
+    /// Alice's then Bob's message, round by round
+    pub messages: Vec<ProtocolMessage>,
+    pub outcome: Option<CompatibilityOutcome>,
+}
+
+impl CompatibilityTranscript {
+    /// Both commitments, the mode and the dealer's commitments: what every
+    /// signature is bound to
+    fn context(&self) -> [u8; 32] {
+        let mut transcript = Transcript::new(PROTOCOL);
+        transcript.append_message(b"alice", self.alice.as_bytes());
+        transcript.append_message(b"bob", self.bob.as_bytes());
+        transcript.append_json(b"mode", &self.mode);
+        transcript.append_json(b"dealer", &self.dealer);
+        transcript.challenge_bytes(b"context")
+    }
+
+    /// Hash over both commitments, the dealer's commitments, every message
+    /// in order and the outcome
+    pub fn digest(&self) -> String {
+        let mut transcript = Transcript::new(PROTOCOL);
+        transcript.append_message(b"alice", self.alice.as_bytes());
+        transcript.append_message(b"bob", self.bob.as_bytes());
+        transcript.append_json(b"mode", &self.mode);
+        transcript.append_json(b"dealer", &self.dealer);
+        for message in &self.messages {
+            transcript.append_json(b"message", message);
+        }
+        transcript.append_json(b"outcome", &self.outcome);
+        hex::encode(transcript.challenge_bytes(b"digest"))
+    }
+
+    /// The run was between these two commitments, each message is signed
+    /// by its sender, the masked inputs open the commitments, and the
+    /// recorded outcome follows from the opened values
+    pub fn verify(&self, alice: &ZkCommitment, bob: &ZkCommitment) -> bool {
+        let (ra, rb) = (alice.committed_rank, bob.committed_rank);
+        if self.alice != alice.matrix_hash || self.bob != bob.matrix_hash
+            || alice.committed_dims.1 != bob.committed_dims.1
+            || self.messages.len() != 2 * self.mode.rounds()
+        {
+            return false;
+        }
+        let ordered = self.messages.chunks(2).enumerate().all(|(round, pair)| {
+            pair[0].round == round && pair[1].round == round && pair[0].sender == Role::Alice && pair[1].sender == Role::Bob
+        });
+        if !ordered { return false; }
+        let context = self.context();
+        let signed = self.messages.iter().all(|m| {
+            m.is_signed_by(if m.sender == Role::Alice { alice } else { bob }, &context)
+        });
+        if !signed { return false; }
+        let sized = self.messages[2..].iter()
+            .all(|m| self.mode.opened(m.round, ra * rb) == Some(m.values.len()) && m.blindings.is_empty());
+        if !sized
+            || !check_masked_input(alice, &self.dealer.alice, &self.messages[0])
+            || !check_masked_input(bob, &self.dealer.bob, &self.messages[1])
+        {
+            return false;
+        }
+        let last = &self.messages[self.messages.len() - 2..];
+        let (Some(a), Some(b)) = (last[0].field_values(), last[1].field_values()) else { return false };
+        self.outcome.is_some() && self.outcome == finish(self.mode, a[0] + b[0], ra.min(rb))
+    }
+}
+
+/// Outcome from the final opened value
+fn finish(mode: CompatibilityMode, opened: Fr, min_rank: usize) -> Option<CompatibilityOutcome> {
+    match mode {
+        CompatibilityMode::Score => {
+            let score = FixedPoint.decode_field(opened, 4) / min_rank.max(1) as f64;
+            Some(CompatibilityOutcome::Score(score))
+        }
+        CompatibilityMode::Threshold(threshold) => {
+            let reached = opened.into_bigint() <= Fr::MODULUS_MINUS_ONE_DIV_TWO;
+            Some(CompatibilityOutcome::Threshold { threshold, reached })
+        }
+    }
+}
+
+/// One side of a compatibility check
+pub struct CompatibilityParty {
+    role: Role,
+    mode: CompatibilityMode,
+    /// Own canonical V columns
+    columns: Vec<Vec<Fr>>,
+    /// Pedersen blindings of those columns
+    blindings: Vec<Fr>,
+    other: ZkCommitment,
+    /// Signs this party's messages
+    key: OpeningKey,
+    dealer: DealerShare,
+    /// This round's own message, until the other's arrives
+    sent: Option<ProtocolMessage>,
+    transcript: CompatibilityTranscript,
+}
+
+impl CompatibilityParty {
+    /// `None` if `opening` does not open `own` to `lrim`, or the seeds'
+    /// domain dimensions differ
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        role: Role,
+        lrim: &LowRankIdentity,
+        opening: &PedersenOpening,
+        own: &ZkCommitment,
+        other: &ZkCommitment,
+        dealer: DealerShare,
+        dealer_commitments: DealerCommitments,
+        mode: CompatibilityMode,
+    ) -> Option<Self> {
+        if !own.verify_opening(lrim, opening) || own.committed_dims.1 != other.committed_dims.1 { return None; }
+        let key = opening.signing_key(own.pedersen.as_ref()?, lrim)?;
+        let columns = committed_vectors(lrim).into_iter()
+            .filter(|(v, _)| matches!(v, CommittedVector::V(_)))
+            .map(|(_, values)| values.into_iter().map(Fr::from).collect())
+            .collect();
+        let blindings = opening.v.iter().map(|b| decode_hex(b)).collect::<Option<_>>()?;
+        let (alice, bob) = match role {
+            Role::Alice => (own, other),
+            Role::Bob => (other, own),
+        };
+        let transcript = CompatibilityTranscript {
+            alice: alice.matrix_hash.clone(),
+            bob: bob.matrix_hash.clone(),
+            mode,
+            dealer: dealer_commitments,
+            messages: Vec::new(),
+            outcome: None,
+        };
+        Some(Self { role, mode, columns, blindings, other: other.clone(), key, dealer, sent: None, transcript })
+    }
+
+    /// Sign `message` and hold it until the other's for the round arrives
+    fn send(&mut self, mut message: ProtocolMessage) -> ProtocolMessage {
+        message.signature = Some(self.key.sign(&message.signed_bytes(&self.transcript.context())));
+        self.sent = Some(message.clone());
+        message
+    }
+
+    /// Round 0: the masked columns
+    pub fn start(&mut self) -> ProtocolMessage {
+        let masked: Vec<Fr> = self.columns.iter().zip(&self.dealer.masks)
+            .flat_map(|(v, r)| v.iter().zip(r).map(|(x, m)| *x + m).collect::<Vec<_>>())
+            .collect();
+        let mut message = ProtocolMessage::new(0, self.role, &masked);
+        message.blindings = self.blindings.iter().zip(&self.dealer.mask_blindings).map(|(b, m)| encode_hex(&(*b + m))).collect();
+        self.send(message)
+    }
+
+    /// Take the other party's message for the current round and return
+    /// this party's next one. `None` once the outcome is known, or if the
+    /// message is out of turn or malformed (the run is then abandoned).
+    pub fn receive(&mut self, message: &ProtocolMessage) -> Option<ProtocolMessage> {
+        let sent = self.sent.take()?;
+        if message.round != sent.round || message.sender == self.role { return None; }
+        if !message.is_signed_by(&self.other, &self.transcript.context()) { return None; }
+        let theirs = message.field_values()?;
+        let mine = sent.field_values()?;
+        let round = sent.round;
+        let alice = self.role == Role::Alice;
+        let (rank_a, rank_b) = match self.role {
+            Role::Alice => (self.columns.len(), self.other.committed_rank),
+            Role::Bob => (self.other.committed_rank, self.columns.len()),
+        };
+        let n = self.other.committed_dims.1;
+        let expected = match round {
+            0 => Some(n * self.other.committed_rank),
+            _ => self.mode.opened(round, rank_a * rank_b),
+        };
+        if expected != Some(theirs.len()) { return None; }
+        self.transcript.messages.extend(match self.role {
+            Role::Alice => [sent, message.clone()],
+            Role::Bob => [message.clone(), sent],
+        });
+
+        let next: Vec<Fr> = match round {
+            0 => {
+                let other_dealer = if alice { &self.transcript.dealer.bob } else { &self.transcript.dealer.alice };
+                if !check_masked_input(&self.other, other_dealer, message) { return None; }
+                // Share of G = V_aᵀV_b, row-major over (Alice column, Bob column)
+                let other_columns: Vec<&[Fr]> = theirs.chunks(n).collect();
+                let gram: Vec<Fr> = (0..rank_a * rank_b)
+                    .map(|k| {
+                        let (i, j) = (k / rank_b, k % rank_b);
+                        if alice {
+                            self.dealer.offsets[k] - dot(&self.dealer.masks[i], other_columns[j])
+                        } else {
+                            dot(other_columns[i], &self.columns[j]) + self.dealer.offsets[k]
+                        }
+                    })
+                    .collect();
+                gram.iter().zip(&self.dealer.squares).map(|(g, (alpha, _))| *g - alpha).collect()
+            }
+            1 => {
+                // Share of S = Σ g², from the opened g − α
+                let mut s = Fr::ZERO;
+                for ((a, b), (alpha, alpha_sq)) in mine.iter().zip(&theirs).zip(&self.dealer.squares) {
+                    let e = *a + b;
+                    s += *alpha_sq + e.double() * alpha;
+                    if alice { s += e.square(); }
+                }
+                match self.mode {
+                    CompatibilityMode::Score => vec![s],
+                    CompatibilityMode::Threshold(threshold) => {
+                        let [rho, _, alpha, beta, _] = self.dealer.compare?;
+                        if alice {
+                            let t = to_fixed(threshold * rank_a.min(rank_b) as f64, FIXED_POINT_FRAC_BITS);
+                            s -= Fr::from(t) * Fr::from(1u128 << 96);
+                        }
+                        vec![s - alpha, rho - beta]
+                    }
+                }
+            }
+            2 if matches!(self.mode, CompatibilityMode::Threshold(_)) => {
+                // z = ρ(S − T) + δ by the Beaver triple
+                let [_, delta, alpha, beta, gamma] = self.dealer.compare?;
+                let (d, e) = (mine[0] + theirs[0], mine[1] + theirs[1]);
+                let mut z = gamma + d * beta + e * alpha + delta;
+                if alice { z += d * e; }
+                vec![z]
+            }
+            _ => {
+                let min_rank = rank_a.min(rank_b);
+                self.transcript.outcome = finish(self.mode, mine[0] + theirs[0], min_rank);
+                return None;
+            }
+        };
+        Some(self.send(ProtocolMessage::new(round + 1, self.role, &next)))
+    }
+
+    pub fn outcome(&self) -> Option<CompatibilityOutcome> {
+        self.transcript.outcome
+    }
+
+    pub fn transcript(&self) -> &CompatibilityTranscript {
+        &self.transcript
+    }
+}
+
+/// Run both parties in process, delivering each message as JSON
+pub fn run_compatibility(alice: &mut CompatibilityParty, bob: &mut CompatibilityParty) -> Option<CompatibilityOutcome> {
+    let wire = |m: &ProtocolMessage| serde_json::from_slice::<ProtocolMessage>(&serde_json::to_vec(m).ok()?).ok();
+    let (mut to_bob, mut to_alice) = (wire(&alice.start())?, wire(&bob.start())?);
+    loop {
+        match (alice.receive(&to_alice), bob.receive(&to_bob)) {
+            (Some(a), Some(b)) => (to_bob, to_alice) = (wire(&a)?, wire(&b)?),
+            (None, None) => break,
+            _ => return None,
+        }
+    }
+    let outcome = alice.outcome()?;
+    (bob.outcome() == Some(outcome)).then_some(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seed::DnaSeed;
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn test_compatibility_protocol() {
+        let shared = DMatrix::<f64>::new_random(10, 2);
+        let a = DnaSeed::new("alice", &(DMatrix::<f64>::new_random(8, 2) * shared.transpose()), 2, vec!["x".into()]);
+        let mix = DMatrix::<f64>::new_random(9, 10) * 0.3;
+        let b = DnaSeed::new("bob", &(DMatrix::<f64>::new_random(9, 2) * shared.transpose() + mix), 3, vec!["y".into()]);
+        let va = a.lrim.v.clone();
+        let vb = b.lrim.v.clone();
+        let expected = (va.transpose() * vb).norm_squared() / 2.0;
+
+        let party = |seed: &DnaSeed, other: &DnaSeed, role, share, dealer: &DealerCommitments, mode| {
+            let opening = seed.opening.as_ref().unwrap();
+            CompatibilityParty::new(role, &seed.lrim, opening, &seed.commitment, &other.commitment, share, dealer.clone(), mode)
+                .unwrap()
+        };
+        let run = |mode| {
+            let (sa, sb, dealer) = deal(10, 2, 3, mode);
+            let mut alice = party(&a, &b, Role::Alice, sa, &dealer, mode);
+            let mut bob = party(&b, &a, Role::Bob, sb, &dealer, mode);
+            let outcome = run_compatibility(&mut alice, &mut bob).unwrap();
+            assert_eq!(alice.transcript(), bob.transcript());
+            assert!(alice.transcript().verify(&a.commitment, &b.commitment));
+            assert!(!alice.transcript().verify(&b.commitment, &a.commitment));
+            (outcome, alice.transcript().clone())
+        };
+
+        let (outcome, mut transcript) = run(CompatibilityMode::Score);
+        let CompatibilityOutcome::Score(score) = outcome else { panic!("expected a score") };
+        assert!((score - expected).abs() < 1e-6, "{score} vs {expected}");
+        let proof = crate::zk::CapabilityProof::from_compatibility(transcript.clone(), &b.commitment).unwrap();
+        assert!(proof.verify_compatibility(&b.commitment, &a.commitment));
+        assert!(!proof.verify_compatibility(&a.commitment, &b.commitment));
+        transcript.outcome = Some(CompatibilityOutcome::Score(score + 0.1));
+        assert!(!transcript.verify(&a.commitment, &b.commitment));
+
+        // Rewriting a share to fit another outcome breaks Alice's signature
+        let mut rewritten = proof.compatibility.clone().unwrap();
+        let last = rewritten.messages.len() - 2;
+        let [theirs] = rewritten.messages[last + 1].field_values().unwrap()[..] else { panic!("one share") };
+        // S = 2 at scale 2^128, over min(r_a, r_b) = 2
+        let target = Fr::from(2u64) * Fr::from(1u128 << 64).square();
+        rewritten.messages[last].values = vec![encode_hex(&(target - theirs))];
+        rewritten.outcome = Some(CompatibilityOutcome::Score(1.0));
+        assert_eq!(finish(rewritten.mode, target, 2), rewritten.outcome);
+        assert!(!rewritten.verify(&a.commitment, &b.commitment));
+        let mut republished = proof.clone();
+        republished.proof_hash = rewritten.digest();
+        republished.compatibility = Some(rewritten);
+        assert!(!republished.verify_compatibility(&b.commitment, &a.commitment));
+
+        for (threshold, reached) in [(expected - 0.01, true), (expected + 0.01, false)] {
+            let (outcome, transcript) = run(CompatibilityMode::Threshold(threshold));
+            assert_eq!(outcome, CompatibilityOutcome::Threshold { threshold, reached });
+            // Nothing but z is opened in the last round
+            assert_eq!(transcript.messages.last().unwrap().values.len(), 1);
+        }
+
+        // A short message abandons the run instead of being read past its end
+        let mode = CompatibilityMode::Threshold(0.5);
+        let (sa, sb, dealer) = deal(10, 2, 3, mode);
+        let mut alice = party(&a, &b, Role::Alice, sa, &dealer, mode);
+        let mut bob = party(&b, &a, Role::Bob, sb, &dealer, mode);
+        let (to_bob, to_alice) = (alice.start(), bob.start());
+        let (to_bob, to_alice) = (alice.receive(&to_alice).unwrap(), bob.receive(&to_bob).unwrap());
+        let (_, mut to_alice) = (alice.receive(&to_alice).unwrap(), bob.receive(&to_bob).unwrap());
+        let context = alice.transcript().context();
+        to_alice.values.truncate(1);
+        assert!(!to_alice.is_signed_by(&b.commitment, &context));
+        let bob_key = b.opening.as_ref().unwrap().signing_key(b.commitment.pedersen.as_ref().unwrap(), &b.lrim).unwrap();
+        to_alice.signature = Some(bob_key.sign(&to_alice.signed_bytes(&context)));
+        assert!(alice.receive(&to_alice).is_none());
+        assert!(alice.transcript().messages.len() == 4 && alice.outcome().is_none());
+    }
+}
@@ -0,0 +1,328 @@
+//! Consistency of a commitment's Pedersen points and circuit digest
+//!
+//! `ZkCommitment::commit` binds the factors twice: Pedersen points, which
+//! column openings and `zk::compatibility` use, and a MiMC digest, which
+//! every SNARK uses. G1 arithmetic is not native to the circuit, so neither
+//! can be checked against the other directly. A consistency proof links
+//! them through a masked random linear combination:
+//!
+//! 1. The prover draws uniform masks w_σ (length r), w_U (m) and w_V (n),
+//!    commits to each with Pedersen (W_σ, W_U, W_V) and to all of them at
+//!    once with a blinded MiMC digest h_w.
+//! 2. Challenges c_σ, c_Uᵢ, c_Vᵢ are Fiat–Shamir over both commitments and
+//!    the mask commitments.
+//! 3. The prover reveals z_σ = w_σ + c_σ·σ, z_U = w_U + Σᵢ c_Uᵢ·uᵢ and
+//!    z_V = w_V + Σᵢ c_Vᵢ·vᵢ with their Pedersen blindings; anyone checks
+//!    them against W + Σ c·C by homomorphism.
+//! 4. A SNARK shows that the digest and h_w open to factors and masks that
+//!    give the same z.
+//!
+//! The masks are fixed before the challenges, so if the points and the
+//! digest opened to different factors, the two sides would agree only with
+//! probability about 1/|Fr|. The masks are uniform, so z reveals nothing.
+//!
+//! Public inputs, in order: the digest, h_w, c_σ, c_U, c_V, z_σ, z_U, z_V.
+
+use super::circuit::{mimc_hash, mimc_hash_wires, Circuit, CircuitShape, FactorWitness};
+use super::gadgets::Wire;
+use super::pedersen::{commit_field_vector, decode_hex, encode_hex, random_scalar, CommittedVector, PedersenOpening};
+use super::transcript::Transcript;
+use super::ZkCommitment;
+use crate::seed::{LowRankIdentity, LrimScalar};
+use ark_bn254::{Fr, G1Affine, G1Projective};
+use ark_ec::CurveGroup;
+use ark_ff::AdditiveGroup;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use serde::{Deserialize, Serialize};
+
+const PROTOCOL: &[u8] = b"DLRS/COMMITMENT_CONSISTENCY/v1";
+
+/// One masked combination: the mask's Pedersen point, then the revealed
+/// z and its blinding (all hex)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaskedCombination {
+    pub mask: String,
+    pub values: Vec<String>,
+    pub blinding: String,
+}
+
+/// The public side of a consistency proof
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsistencyStatement {
+    /// MiMC digest of the mask blinding and masks w_σ, w_U, w_V (hex Fr)
+    pub mask_digest: String,
+    pub sigma: MaskedCombination,
+    pub u: MaskedCombination,
+    pub v: MaskedCombination,
+}
+
+/// c_σ, then one challenge per column of U and of V
+#[derive(Debug, Clone, PartialEq)]
+struct Challenges {
+    sigma: Fr,
+    u: Vec<Fr>,
+    v: Vec<Fr>,
+}
+
+impl Challenges {
+    fn derive(commitment: &ZkCommitment, statement: &ConsistencyStatement) -> Self {
+        let mut transcript = Transcript::new(PROTOCOL);
+        transcript.append_message(b"matrix_hash", commitment.matrix_hash.as_bytes());
+        transcript.append_json(b"circuit_digest", &commitment.circuit_digest);
+        transcript.append_json(b"dims", &commitment.committed_dims);
+        transcript.append_u64(b"rank", commitment.committed_rank as u64);
+        transcript.append_message(b"mask_digest", statement.mask_digest.as_bytes());
+        for masked in [&statement.sigma, &statement.u, &statement.v] {
+            transcript.append_message(b"mask", masked.mask.as_bytes());
+        }
+        let rank = commitment.committed_rank;
+        Self {
+            sigma: transcript.challenge_scalar(b"sigma"),
+            u: (0..rank).map(|_| transcript.challenge_scalar(b"u")).collect(),
+            v: (0..rank).map(|_| transcript.challenge_scalar(b"v")).collect(),
+        }
+    }
+}
+
+/// w + Σᵢ cᵢ·xᵢ over columns of equal length
+fn combine(mask: &[Fr], challenges: &[Fr], columns: &[Vec<Fr>]) -> Vec<Fr> {
+    mask.iter().enumerate()
+        .map(|(k, w)| *w + challenges.iter().zip(columns).map(|(c, col)| *c * col[k]).sum::<Fr>())
+        .collect()
+}
+
+impl ConsistencyStatement {
+    /// Check the revealed combinations against the Pedersen points of
+    /// `commitment` and return the SNARK's public inputs; `None` if they
+    /// do not open
+    pub fn public_inputs(&self, commitment: &ZkCommitment) -> Option<Vec<Fr>> {
+        let pedersen = commitment.pedersen.as_ref().filter(|p| p.digest() == commitment.matrix_hash)?;
+        let digest: Fr = decode_hex(commitment.circuit_digest.as_deref()?)?;
+        let (m, n) = commitment.committed_dims;
+        let rank = commitment.committed_rank;
+        let challenges = Challenges::derive(commitment, self);
+        let groups = [
+            (&self.sigma, rank, vec![(challenges.sigma, CommittedVector::Sigma)]),
+            (&self.u, m, challenges.u.iter().enumerate().map(|(i, c)| (*c, CommittedVector::U(i))).collect()),
+            (&self.v, n, challenges.v.iter().enumerate().map(|(i, c)| (*c, CommittedVector::V(i))).collect()),
+        ];
+        let mut inputs = vec![digest, decode_hex(&self.mask_digest)?, challenges.sigma];
+        inputs.extend(challenges.u.iter().chain(&challenges.v));
+        for (masked, len, terms) in groups {
+            let values: Vec<Fr> = masked.values.iter().map(|x| decode_hex(x)).collect::<Option<_>>()?;
+            if values.len() != len { return None; }
+            let mut expected = G1Projective::from(decode_hex::<G1Affine>(&masked.mask)?);
+            for (c, vector) in terms {
+                expected += pedersen.point(vector)? * c;
+            }
+            if commit_field_vector(&values, &decode_hex(&masked.blinding)?) != expected.into_affine() { return None; }
+            inputs.extend(values);
+        }
+        Some(inputs)
+    }
+}
+
+/// The masks and the blinding of their digest
+#[derive(Debug, Clone)]
+struct Masks {
+    blinding: Fr,
+    sigma: Vec<Fr>,
+    u: Vec<Fr>,
+    v: Vec<Fr>,
+}
+
+impl Masks {
+    fn random(m: usize, n: usize, rank: usize) -> Self {
+        let draw = |len: usize| (0..len).map(|_| random_scalar()).collect();
+        Self { blinding: random_scalar(), sigma: draw(rank), u: draw(m), v: draw(n) }
+    }
+
+    fn digest(&self) -> Fr {
+        let mut inputs = vec![self.blinding];
+        inputs.extend(self.sigma.iter().chain(&self.u).chain(&self.v));
+        mimc_hash(&inputs)
+    }
+}
+
+/// "The circuit digest and the Pedersen points commit to the same factors"
+#[derive(Debug, Clone)]
+pub struct ConsistencyCircuit {
+    pub m: usize,
+    pub n: usize,
+    /// As `ConsistencyStatement::public_inputs` returns them
+    pub inputs: Vec<Fr>,
+    witness: FactorWitness,
+    masks: Masks,
+}
+
+impl ConsistencyCircuit {
+    /// The proof for `commitment` to `lrim`; `None` unless `opening` opens it
+    pub fn new<T: LrimScalar>(
+        commitment: &ZkCommitment,
+        lrim: &LowRankIdentity<T>,
+        opening: &PedersenOpening,
+    ) -> Option<(Self, ConsistencyStatement)> {
+        if !commitment.verify_opening(lrim, opening) { return None; }
+        Self::build(commitment, FactorWitness::new(lrim, decode_hex(&opening.digest)?), opening)
+    }
+
+    /// `new` without checking the opening first
+    fn build(
+        commitment: &ZkCommitment,
+        witness: FactorWitness,
+        opening: &PedersenOpening,
+    ) -> Option<(Self, ConsistencyStatement)> {
+        let f = &witness.factors;
+        let ((m, n), rank) = (commitment.committed_dims, f.rank());
+        let field = |xs: &[i64]| xs.iter().map(|&x| Fr::from(x)).collect::<Vec<_>>();
+        let blindings = |xs: &[String]| xs.iter().map(|x| decode_hex::<Fr>(x)).collect::<Option<Vec<_>>>();
+        let (rho_sigma, rho_u, rho_v) = (decode_hex::<Fr>(&opening.sigma)?, blindings(&opening.u)?, blindings(&opening.v)?);
+
+        let masks = Masks::random(m, n, rank);
+        let mask_blindings = [random_scalar(), random_scalar(), random_scalar()];
+        let point = |mask: &[Fr], blinding: &Fr| encode_hex(&commit_field_vector(mask, blinding));
+        let mut statement = ConsistencyStatement {
+            mask_digest: encode_hex(&masks.digest()),
+            sigma: MaskedCombination { mask: point(&masks.sigma, &mask_blindings[0]), values: Vec::new(), blinding: String::new() },
+            u: MaskedCombination { mask: point(&masks.u, &mask_blindings[1]), values: Vec::new(), blinding: String::new() },
+            v: MaskedCombination { mask: point(&masks.v, &mask_blindings[2]), values: Vec::new(), blinding: String::new() },
+        };
+        let c = Challenges::derive(commitment, &statement);
+        let reveal = |masked: &mut MaskedCombination, values: Vec<Fr>, blinding: Fr| {
+            masked.values = values.iter().map(encode_hex).collect();
+            masked.blinding = encode_hex(&blinding);
+        };
+        let dot = |cs: &[Fr], xs: &[Fr]| cs.iter().zip(xs).map(|(c, x)| *c * x).sum::<Fr>();
+        reveal(
+            &mut statement.sigma,
+            combine(&masks.sigma, &[c.sigma], &[field(&f.sigma)]),
+            mask_blindings[0] + c.sigma * rho_sigma,
+        );
+        reveal(
+            &mut statement.u,
+            combine(&masks.u, &c.u, &f.u.iter().map(|col| field(col)).collect::<Vec<_>>()),
+            mask_blindings[1] + dot(&c.u, &rho_u),
+        );
+        reveal(
+            &mut statement.v,
+            combine(&masks.v, &c.v, &f.v.iter().map(|col| field(col)).collect::<Vec<_>>()),
+            mask_blindings[2] + dot(&c.v, &rho_v),
+        );
+        let inputs = statement.public_inputs(commitment)?;
+        Some((Self { m, n, inputs, witness, masks }, statement))
+    }
+
+    /// All-zero assignment of the right shape, for key generation
+    pub fn blank(m: usize, n: usize, rank: usize) -> Self {
+        let masks = Masks { blinding: Fr::ZERO, sigma: vec![Fr::ZERO; rank], u: vec![Fr::ZERO; m], v: vec![Fr::ZERO; n] };
+        let inputs = vec![Fr::ZERO; 3 + 2 * rank + rank + m + n];
+        Self { m, n, inputs, witness: FactorWitness::blank(m, n, rank), masks }
+    }
+}
+
+impl Circuit for ConsistencyCircuit {
+    fn shape(&self) -> CircuitShape {
+        CircuitShape::Consistency { m: self.m, n: self.n, rank: self.witness.rank() }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for ConsistencyCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let (w, masks) = (&self.witness, &self.masks);
+        let (m, n, rank) = (self.m, self.n, w.rank());
+        if !w.has_shape(m, n) || self.inputs.len() != 3 + 3 * rank + m + n
+            || masks.sigma.len() != rank || masks.u.len() != m || masks.v.len() != n
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let public = self.inputs.iter().map(|&x| Wire::input(&cs, x)).collect::<Result<Vec<_>, _>>()?;
+        let (digest, mask_digest, c_sigma) = (&public[0], &public[1], &public[2]);
+        let (c_u, rest) = public[3..].split_at(rank);
+        let (c_v, rest) = rest.split_at(rank);
+        let (z_sigma, rest) = rest.split_at(rank);
+        let (z_u, z_v) = rest.split_at(m);
+
+        // The digest opens to the factors …
+        let blinding = Wire::witness(&cs, w.blinding)?;
+        let f = w.factors.allocate(&cs, None, None)?;
+        let u_digest = mimc_hash_wires(&cs, f.u.iter().flatten())?;
+        let committed = std::iter::once(&blinding).chain(&f.sigma).chain(f.v.iter().flatten()).chain([&u_digest]);
+        mimc_hash_wires(&cs, committed)?.enforce_equal(&cs, digest)?;
+
+        // … h_w to the masks …
+        let witnesses = |xs: &[Fr]| xs.iter().map(|&x| Wire::witness(&cs, x)).collect::<Result<Vec<_>, _>>();
+        let mask_blinding = Wire::witness(&cs, masks.blinding)?;
+        let (w_sigma, w_u, w_v) = (witnesses(&masks.sigma)?, witnesses(&masks.u)?, witnesses(&masks.v)?);
+        let masked = std::iter::once(&mask_blinding).chain(&w_sigma).chain(&w_u).chain(&w_v);
+        mimc_hash_wires(&cs, masked)?.enforce_equal(&cs, mask_digest)?;
+
+        // … and together they give the revealed combinations
+        let sigma = [f.sigma];
+        enforce_combination(&cs, &w_sigma, std::slice::from_ref(c_sigma), &sigma, z_sigma)?;
+        enforce_combination(&cs, &w_u, c_u, &f.u, z_u)?;
+        enforce_combination(&cs, &w_v, c_v, &f.v, z_v)?;
+        Ok(())
+    }
+}
+
+/// zₖ = wₖ + Σⱼ cⱼ·xⱼₖ for every entry k of the columns xⱼ
+fn enforce_combination(
+    cs: &ConstraintSystemRef<Fr>,
+    mask: &[Wire],
+    challenges: &[Wire],
+    columns: &[Vec<Wire>],
+    revealed: &[Wire],
+) -> Result<(), SynthesisError> {
+    for (k, (w, z)) in mask.iter().zip(revealed).enumerate() {
+        let mut sum = w.clone();
+        for (c, col) in challenges.iter().zip(columns) {
+            sum = sum.add(&col[k].mul(cs, c)?);
+        }
+        sum.enforce_equal(cs, z)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::fixture::Prover;
+    use crate::zk::{CapabilityProof, CircuitKeys, Groth16Backend, MockBackend};
+
+    #[test]
+    fn test_commitment_consistency() {
+        let Prover { seed, opening, .. } = Prover::new(6, 4, 2);
+        let shape = CircuitShape::Consistency { m: 6, n: 4, rank: 2 };
+        let (circuit, statement) = ConsistencyCircuit::new(&seed.commitment, &seed.lrim, &opening).unwrap();
+        assert!(circuit.is_satisfied());
+        assert_eq!(statement.public_inputs(&seed.commitment), Some(circuit.inputs.clone()));
+
+        // A digest over other factors than the points cannot be linked to them
+        let other = Prover::new(6, 4, 2).seed;
+        let mut spliced = seed.commitment.clone();
+        spliced.circuit_digest = other.commitment.circuit_digest.clone();
+        let mut mixed = opening.clone();
+        mixed.digest = other.opening.as_ref().unwrap().digest.clone();
+        assert!(ConsistencyCircuit::new(&spliced, &seed.lrim, &mixed).is_none());
+        // Even for a prover who knows both openings: the points only open
+        // to seed's factors, the digest only to other's
+        let blinding = decode_hex(&mixed.digest).unwrap();
+        let (forged, _) = ConsistencyCircuit::build(&spliced, FactorWitness::new(&seed.lrim, blinding), &opening).unwrap();
+        assert!(!forged.is_satisfied());
+        assert!(ConsistencyCircuit::build(&spliced, FactorWitness::new(&other.lrim, blinding), &opening).is_none());
+
+        let mock = CircuitKeys::<MockBackend>::setup(shape).unwrap();
+        let proof = CapabilityProof::prove_consistency(&mock, &seed.commitment, &seed.lrim, &opening).unwrap();
+        assert!(proof.verify_against_commitment(&seed.commitment, &mock.verifying_key()));
+        assert!(!proof.verify_against_commitment(&spliced, &mock.verifying_key()));
+        assert!(!proof.verify_against_commitment(&other.commitment, &mock.verifying_key()));
+        let mut tampered = proof.clone();
+        tampered.consistency.as_mut().unwrap().u.values[0] = encode_hex(&Fr::from(1u64));
+        assert!(!tampered.verify_against_commitment(&seed.commitment, &mock.verifying_key()));
+
+        let keys = CircuitKeys::<Groth16Backend>::setup(shape).unwrap();
+        let proof = CapabilityProof::prove_consistency(&keys, &seed.commitment, &seed.lrim, &opening).unwrap();
+        assert!(proof.verify_against_commitment(&seed.commitment, &keys.verifying_key()));
+        assert!(!proof.verify_against_commitment(&spliced, &keys.verifying_key()));
+    }
+}
@@ -0,0 +1,343 @@
+//! R1CS gadgets for fixed-point linear algebra over BN254
+//!
+//! Reals are fixed-point integers embedded in Fr, a negative x as p − |x|.
+//! With F fractional bits, a product of k encoded values carries scale
+//! 2^(kF). Arithmetic is exact while every magnitude stays far below
+//! p/2 ≈ 2^253. Witnesses get that guarantee from range checks: bound each
+//! private value's width, then size every comparison to the widest value
+//! it can see.
+//!
+//! `FixedPoint` and `FixedFactors` are the host side: encodings, and what
+//! they cost in accuracy. Precision is fixed at Q32.32, the encoding seeds
+//! are committed in; the range-check widths here and in the circuits are
+//! sized for it, and key files refuse any other.
+
+use crate::seed::{from_fixed, to_fixed, LowRankIdentity, LrimScalar, FIXED_POINT_FRAC_BITS};
+use ark_bn254::Fr;
+use ark_ff::{AdditiveGroup, BigInteger, Field, PrimeField};
+use ark_relations::lc;
+use ark_relations::r1cs::{ConstraintSystemRef, LinearCombination, SynthesisError, Variable};
+use nalgebra::{DMatrix, DVector};
+
+/// Default width of a range-checked factor entry
+pub const VALUE_BITS: u32 = 64;
+
+/// Q32.32 fixed point
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FixedPoint;
+
+impl FixedPoint {
+    pub const FRAC_BITS: u32 = FIXED_POINT_FRAC_BITS;
+
+    pub fn encode(&self, x: f64) -> i64 {
+        to_fixed(x, Self::FRAC_BITS)
+    }
+
+    pub fn decode(&self, x: i64) -> f64 {
+        from_fixed(x, Self::FRAC_BITS)
+    }
+
+    pub fn to_field(&self, x: f64) -> Fr {
+        Fr::from(self.encode(x))
+    }
+
+    /// A field element at scale 2^(products·F), read as a signed real
+    pub fn decode_field(&self, x: Fr, products: u32) -> f64 {
+        let negative = x.into_bigint() > Fr::MODULUS_MINUS_ONE_DIV_TWO;
+        let magnitude = if negative { -x } else { x }.into_bigint();
+        let value: f64 = magnitude.0.iter().rev().fold(0.0, |acc, &limb| acc * 2f64.powi(64) + limb as f64);
+        let value = value * 2f64.powi(-((products * Self::FRAC_BITS) as i32));
+        if negative { -value } else { value }
+    }
+
+    /// 2^(products·F)
+    pub fn scale(&self, products: u32) -> Fr {
+        Fr::from(2u64).pow([(products * Self::FRAC_BITS) as u64])
+    }
+
+    /// Worst-case error of one encoding
+    pub fn max_error(&self) -> f64 {
+        0.5 * from_fixed(1, Self::FRAC_BITS)
+    }
+}
+
+/// A linear combination together with its assigned value
+#[derive(Clone)]
+pub struct Wire {
+    pub lc: LinearCombination<Fr>,
+    pub value: Fr,
+}
+
+impl Wire {
+    pub fn input(cs: &ConstraintSystemRef<Fr>, value: Fr) -> Result<Self, SynthesisError> {
+        Ok(Self { lc: lc!() + cs.new_input_variable(|| Ok(value))?, value })
+    }
+
+    pub fn witness(cs: &ConstraintSystemRef<Fr>, value: Fr) -> Result<Self, SynthesisError> {
+        Ok(Self { lc: lc!() + cs.new_witness_variable(|| Ok(value))?, value })
+    }
+
+    /// A witness range-checked to `bits` signed bits
+    pub fn witness_signed(cs: &ConstraintSystemRef<Fr>, value: i64, bits: u32) -> Result<Self, SynthesisError> {
+        let wire = Self::witness(cs, Fr::from(value))?;
+        wire.enforce_signed_bits(cs, bits)?;
+        Ok(wire)
+    }
+
+    pub fn constant(value: Fr) -> Self {
+        Self { lc: lc!() + (value, Variable::One), value }
+    }
+
+    pub fn add(&self, other: &Wire) -> Self {
+        Self { lc: self.lc.clone() + &other.lc, value: self.value + other.value }
+    }
+
+    pub fn sub(&self, other: &Wire) -> Self {
+        Self { lc: self.lc.clone() - &other.lc, value: self.value - other.value }
+    }
+
+    pub fn scale(&self, c: Fr) -> Self {
+        Self { lc: self.lc.clone() * c, value: self.value * c }
+    }
+
+    pub fn mul(&self, cs: &ConstraintSystemRef<Fr>, other: &Wire) -> Result<Self, SynthesisError> {
+        let product = Self::witness(cs, self.value * other.value)?;
+        cs.enforce_constraint(self.lc.clone(), other.lc.clone(), product.lc.clone())?;
+        Ok(product)
+    }
+
+    /// Σ aᵢbᵢ, one constraint per term; scales add
+    pub fn dot(cs: &ConstraintSystemRef<Fr>, a: &[Wire], b: &[Wire]) -> Result<Self, SynthesisError> {
+        a.iter().zip(b).try_fold(Self::constant(Fr::ZERO), |sum, (x, y)| Ok(sum.add(&x.mul(cs, y)?)))
+    }
+
+    /// M x for the rows of M
+    pub fn mat_vec(cs: &ConstraintSystemRef<Fr>, rows: &[Vec<Wire>], x: &[Wire]) -> Result<Vec<Self>, SynthesisError> {
+        rows.iter().map(|row| Self::dot(cs, row, x)).collect()
+    }
+
+    /// ⌊value / 2^frac_bits⌋, back down one fixed-point scale; the quotient
+    /// is range-checked to `bits` signed bits
+    pub fn rescale(&self, cs: &ConstraintSystemRef<Fr>, frac_bits: u32, bits: u32) -> Result<Self, SynthesisError> {
+        let negative = self.value.into_bigint() > Fr::MODULUS_MINUS_ONE_DIV_TWO;
+        let magnitude = if negative { -self.value } else { self.value };
+        let truncated = Fr::from_bigint(magnitude.into_bigint() >> frac_bits).expect("smaller than the modulus");
+        let unit = Fr::from(2u64).pow([frac_bits as u64]);
+        let quotient = match (negative, truncated * unit == magnitude) {
+            (false, _) => truncated,
+            (true, true) => -truncated,
+            (true, false) => -(truncated + Fr::ONE),
+        };
+        let q = Self::witness(cs, quotient)?;
+        q.enforce_signed_bits(cs, bits)?;
+        let r = Self::witness(cs, self.value - quotient * unit)?;
+        r.enforce_bits(cs, frac_bits)?;
+        q.scale(unit).add(&r).enforce_equal(cs, self)?;
+        Ok(q)
+    }
+
+    /// Q32.32 product at the inputs' scale, truncated toward −∞
+    pub fn fixed_mul(&self, cs: &ConstraintSystemRef<Fr>, other: &Wire, bits: u32) -> Result<Self, SynthesisError> {
+        self.mul(cs, other)?.rescale(cs, FixedPoint::FRAC_BITS, bits)
+    }
+
+    pub fn enforce_equal(&self, cs: &ConstraintSystemRef<Fr>, other: &Wire) -> Result<(), SynthesisError> {
+        cs.enforce_constraint(self.lc.clone() - &other.lc, lc!() + Variable::One, lc!())
+    }
+
+    pub fn enforce_boolean(&self, cs: &ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        cs.enforce_constraint(self.lc.clone(), self.lc.clone() - Variable::One, lc!())
+    }
+
+    /// 0 ≤ value < 2^bits, by bit decomposition
+    pub fn enforce_bits(&self, cs: &ConstraintSystemRef<Fr>, bits: u32) -> Result<(), SynthesisError> {
+        self.to_bits(cs, bits).map(drop)
+    }
+
+    /// The `bits` low bits of value, constrained to recompose it exactly
+    fn to_bits(&self, cs: &ConstraintSystemRef<Fr>, bits: u32) -> Result<Vec<Wire>, SynthesisError> {
+        let mut le = self.value.into_bigint().to_bits_le();
+        le.resize(bits as usize, false);
+        let mut sum = lc!();
+        let mut weight = Fr::ONE;
+        let mut wires = Vec::with_capacity(bits as usize);
+        for &bit in &le[..bits as usize] {
+            let b = Self::witness(cs, Fr::from(bit))?;
+            b.enforce_boolean(cs)?;
+            sum = sum + (b.lc.clone() * weight);
+            weight.double_in_place();
+            wires.push(b);
+        }
+        cs.enforce_constraint(sum, lc!() + Variable::One, self.lc.clone())?;
+        Ok(wires)
+    }
+
+    /// −2^63 ≤ value < 2^63
+    pub fn enforce_signed(&self, cs: &ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        self.enforce_signed_bits(cs, VALUE_BITS)
+    }
+
+    /// −2^(bits−1) ≤ value < 2^(bits−1), for bits ≤ 128
+    pub fn enforce_signed_bits(&self, cs: &ConstraintSystemRef<Fr>, bits: u32) -> Result<(), SynthesisError> {
+        self.add(&Self::constant(Fr::from(1u128 << (bits - 1)))).enforce_bits(cs, bits)
+    }
+
+    /// value ≤ bound, for bound − value < 2^bits
+    pub fn enforce_leq(&self, cs: &ConstraintSystemRef<Fr>, bound: &Wire, bits: u32) -> Result<(), SynthesisError> {
+        bound.sub(self).enforce_bits(cs, bits)
+    }
+
+    /// −bound ≤ value ≤ bound, for |value| + bound < 2^bits
+    pub fn enforce_abs_leq(&self, cs: &ConstraintSystemRef<Fr>, bound: &Wire, bits: u32) -> Result<(), SynthesisError> {
+        self.add(bound).enforce_bits(cs, bits)?;
+        self.enforce_leq(cs, bound, bits)
+    }
+
+    /// |value| ≥ bound, for |value| − bound < 2^bits. The prover supplies
+    /// the sign; with the wrong one |value| is close to p and fails the
+    /// range check, provided |value| is known to be far below p/2.
+    pub fn enforce_abs_geq(&self, cs: &ConstraintSystemRef<Fr>, bound: &Wire, bits: u32) -> Result<(), SynthesisError> {
+        let negative = self.value.into_bigint() > Fr::MODULUS_MINUS_ONE_DIV_TWO;
+        let sign = Self::witness(cs, Fr::from(negative))?;
+        sign.enforce_boolean(cs)?;
+        let magnitude = self.mul(cs, &Self::constant(Fr::ONE).sub(&sign.scale(Fr::from(2u64))))?;
+        bound.enforce_leq(cs, &magnitude, bits)
+    }
+
+    /// Boolean [value ≥ other], for |value − other| < 2^bits: the top bit
+    /// of value − other + 2^bits
+    pub fn is_geq(&self, cs: &ConstraintSystemRef<Fr>, other: &Wire, bits: u32) -> Result<Self, SynthesisError> {
+        let shifted = self.sub(other).add(&Self::constant(Fr::from(2u64).pow([bits as u64])));
+        let mut decomposed = shifted.to_bits(cs, bits + 1)?;
+        Ok(decomposed.pop().expect("bits + 1 ≥ 1"))
+    }
+}
+
+/// What encoding a seed's factors cost
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncodingError {
+    /// Largest change of any factor entry
+    pub max_entry: f64,
+    /// ‖K − K̃‖_F
+    pub reconstruction: f64,
+    /// ‖K − K̃‖_F / ‖K‖_F
+    pub relative: f64,
+}
+
+/// A seed's canonical factors in Q32.32, as circuits take them
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedFactors {
+    pub sigma: Vec<i64>,
+    /// Columns of U
+    pub u: Vec<Vec<i64>>,
+    /// Columns of V
+    pub v: Vec<Vec<i64>>,
+}
+
+/// Factor wires, laid out like `FixedFactors`
+#[derive(Clone)]
+pub struct FactorWires {
+    pub sigma: Vec<Wire>,
+    pub u: Vec<Vec<Wire>>,
+    pub v: Vec<Vec<Wire>>,
+}
+
+impl FixedFactors {
+    /// Encode the canonical factors of `lrim`, and measure the damage
+    pub fn encode<T: LrimScalar>(lrim: &LowRankIdentity<T>) -> (Self, EncodingError) {
+        let c = lrim.to_f64().canonical();
+        let fixed = FixedPoint;
+        let columns = |m: &DMatrix<f64>| m.column_iter().map(|col| col.iter().map(|&x| fixed.encode(x)).collect()).collect();
+        let factors = Self { sigma: c.sigma.iter().map(|&x| fixed.encode(x)).collect(), u: columns(&c.u), v: columns(&c.v) };
+        let decoded = factors.decode();
+        let max_entry = [(&c.u, &decoded.u), (&c.v, &decoded.v)]
+            .into_iter()
+            .map(|(a, b)| (a - b).amax())
+            .chain([(&c.sigma - &decoded.sigma).amax()])
+            .fold(0.0, f64::max);
+        let k = c.reconstruct();
+        let reconstruction = (&k - decoded.reconstruct()).norm();
+        let norm = k.norm();
+        let relative = if norm > 0.0 { reconstruction / norm } else { reconstruction };
+        (factors, EncodingError { max_entry, reconstruction, relative })
+    }
+
+    pub fn rank(&self) -> usize {
+        self.sigma.len()
+    }
+
+    /// Back to real factors
+    pub fn decode(&self) -> LowRankIdentity {
+        let matrix = |columns: &[Vec<i64>]| {
+            let rows = columns.first().map_or(0, Vec::len);
+            DMatrix::from_fn(rows, columns.len(), |i, j| FixedPoint.decode(columns[j][i]))
+        };
+        let sigma = DVector::from_iterator(self.rank(), self.sigma.iter().map(|&x| FixedPoint.decode(x)));
+        LowRankIdentity::new(matrix(&self.u), sigma, matrix(&self.v))
+    }
+
+    /// Allocate as witnesses, range-checking σ and the U and V entries to
+    /// the given signed widths where given
+    pub fn allocate(
+        &self,
+        cs: &ConstraintSystemRef<Fr>,
+        sigma_bits: Option<u32>,
+        unit_bits: Option<u32>,
+    ) -> Result<FactorWires, SynthesisError> {
+        let wires = |xs: &[i64], bits: Option<u32>| {
+            xs.iter()
+                .map(|&x| match bits {
+                    Some(bits) => Wire::witness_signed(cs, x, bits),
+                    None => Wire::witness(cs, Fr::from(x)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(FactorWires {
+            sigma: wires(&self.sigma, sigma_bits)?,
+            u: self.u.iter().map(|col| wires(col, unit_bits)).collect::<Result<_, _>>()?,
+            v: self.v.iter().map(|col| wires(col, unit_bits)).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    #[test]
+    fn test_fixed_point_gadgets() {
+        let fixed = FixedPoint;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let input = |x: f64| Wire::input(&cs, fixed.to_field(x)).unwrap();
+        let (a, b) = (input(-1.5), input(2.25));
+
+        // Products double the scale; rescaling brings them back
+        let product = a.fixed_mul(&cs, &b, VALUE_BITS).unwrap();
+        assert_eq!(fixed.decode_field(product.value, 1), -3.375);
+        let dot = Wire::dot(&cs, &[a.clone(), b.clone()], &[b.clone(), b.clone()]).unwrap();
+        assert_eq!(fixed.decode_field(dot.value, 2), -1.5 * 2.25 + 2.25 * 2.25);
+        let rows = vec![vec![a.clone(), b.clone()], vec![b.clone(), a.clone()]];
+        let y = Wire::mat_vec(&cs, &rows, &[a.clone(), a.clone()]).unwrap();
+        assert_eq!(fixed.decode_field(y[1].value, 2), 2.25 * -1.5 + 1.5 * 1.5);
+
+        // Comparisons
+        assert_eq!(a.is_geq(&cs, &b, 40).unwrap().value, Fr::ZERO);
+        assert_eq!(b.is_geq(&cs, &a, 40).unwrap().value, Fr::ONE);
+        a.enforce_abs_leq(&cs, &input(1.5), 40).unwrap();
+        a.enforce_abs_geq(&cs, &input(1.0), 40).unwrap();
+        a.enforce_leq(&cs, &b, 40).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        b.enforce_leq(&cs, &a, 40).unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        // Encoding a seed costs at most half a unit per entry
+        let lrim = LowRankIdentity::from_matrix(&DMatrix::new_random(5, 4), 2);
+        let (factors, error) = FixedFactors::encode(&lrim);
+        assert!(error.max_entry <= fixed.max_error());
+        assert!(error.relative < 1e-3);
+        let (exact, none) = FixedFactors::encode(&factors.decode());
+        assert_eq!(exact, factors);
+        assert_eq!(none.max_entry, 0.0);
+    }
+}
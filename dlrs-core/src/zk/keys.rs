@@ -0,0 +1,425 @@
+//! Circuit key management: setup, ceremonies, key files and a key cache
+//!
+//! * Setup: `CircuitKeys::setup` draws fresh randomness;
+//!   `CircuitKeys::setup_seeded` is reproducible, for tests only.
+//! * Ceremony: Groth16 phase-two contributions, simulated locally. Each
+//!   participant multiplies δ by a secret s and divides the H and L queries
+//!   by it, then forgets s. The keys are sound if any one participant was
+//!   honest about δ; α, β and τ still come from the initial setup, which
+//!   in production must itself come from a powers-of-tau ceremony.
+//! * Key files: JSON, versioned, one per backend, circuit shape and
+//!   fixed-point precision, carrying the verifying key's fingerprint.
+//! * Cache: keys by shape, loaded from a directory on first use. Pinned
+//!   fingerprints make it refuse any other key for that shape.
+//!
+//! A fingerprint is SHA-256 over a tag, the backend, the shape, the
+//! fixed-point precision and the encoded verifying key. Proofs record the
+//! fingerprint of the key they were made for, and verification under a key
+//! with another fingerprint fails.
+
+use super::backend::{Backend, Groth16Backend, ProofSystem};
+use super::circuit::{CircuitKeys, CircuitShape, CircuitVerifyingKey};
+use super::pedersen::{decode_hex, encode_hex};
+use super::transcript::Transcript;
+use crate::seed::FIXED_POINT_FRAC_BITS;
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, UniformRand};
+use ark_groth16::ProvingKey;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const KEY_FINGERPRINT_TAG: &[u8] = b"DLRS/CIRCUIT_KEY/v1\0";
+pub const KEY_FILE_VERSION: u32 = 1;
+const CEREMONY_PROTOCOL: &[u8] = b"DLRS/GROTH16_CEREMONY/v1";
+
+impl<S: ProofSystem> CircuitKeys<S> {
+    /// Reproducible setup from `seed`; anyone who knows the seed can forge
+    /// proofs, so this is for tests
+    pub fn setup_seeded(shape: CircuitShape, seed: u64) -> Option<Self> {
+        let proving_key = S::setup_with_rng(shape, &mut rand::rngs::StdRng::seed_from_u64(seed))?;
+        Some(Self { shape, proving_key })
+    }
+}
+
+impl<S: ProofSystem> CircuitVerifyingKey<S> {
+    pub fn fingerprint(&self) -> String {
+        let digest = Sha256::new()
+            .chain_update(KEY_FINGERPRINT_TAG)
+            .chain_update(serde_json::to_vec(&S::BACKEND).expect("backend serialises"))
+            .chain_update(serde_json::to_vec(&self.shape).expect("shape serialises"))
+            .chain_update(FIXED_POINT_FRAC_BITS.to_le_bytes())
+            .chain_update(S::encode_verifying_key(&self.key))
+            .finalize();
+        hex::encode(digest)
+    }
+}
+
+/// One participant's public record of a ceremony turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contribution {
+    pub participant: String,
+    /// δ·G1 and δ·G2 after this turn, hex
+    pub delta_g1: String,
+    pub delta_g2: String,
+    /// s·P for the transcript point P, showing the G2 update used s
+    pub ratio: String,
+}
+
+/// The point P a contribution's ratio is taken on, fixed by everything
+/// before the turn
+fn ratio_base(shape: &CircuitShape, participant: &str, delta: (&G1Affine, &G2Affine)) -> G1Affine {
+    let mut transcript = Transcript::new(CEREMONY_PROTOCOL);
+    transcript.append_json(b"shape", shape);
+    transcript.append_message(b"participant", participant.as_bytes());
+    transcript.append_serialized(b"delta_g1", delta.0);
+    transcript.append_serialized(b"delta_g2", delta.1);
+    (G1Affine::generator() * transcript.challenge_scalar(b"base")).into_affine()
+}
+
+/// Σ ρʲ·pⱼ, pairing-checked against δ to show a query was scaled by 1/s
+fn combine(points: &[G1Affine], rho: Fr) -> G1Projective {
+    let powers: Vec<Fr> = std::iter::successors(Some(Fr::ONE), |p| Some(*p * rho)).take(points.len()).collect();
+    G1Projective::msm_unchecked(points, &powers)
+}
+
+/// A locally simulated Groth16 phase-two ceremony for one shape
+#[derive(Debug, Clone)]
+pub struct Ceremony {
+    initial: CircuitKeys<Groth16Backend>,
+    keys: CircuitKeys<Groth16Backend>,
+    contributions: Vec<Contribution>,
+}
+
+impl Ceremony {
+    /// Start from the coordinator's setup
+    pub fn new(keys: CircuitKeys<Groth16Backend>) -> Self {
+        Self { initial: keys.clone(), keys, contributions: Vec::new() }
+    }
+
+    /// Start from a fresh setup and let each participant contribute in turn
+    pub fn simulate(shape: CircuitShape, participants: &[&str]) -> Option<Self> {
+        let mut ceremony = Self::new(CircuitKeys::setup(shape)?);
+        for participant in participants {
+            ceremony.contribute(participant, &mut rand::thread_rng());
+        }
+        Some(ceremony)
+    }
+
+    /// One turn: re-randomise δ with a secret drawn from `rng`, which is
+    /// dropped on return
+    pub fn contribute(&mut self, participant: &str, rng: &mut impl rand::Rng) -> &Contribution {
+        let pk = &mut self.keys.proving_key;
+        let secret = loop {
+            let s = Fr::rand(rng);
+            if let Some(inverse) = s.inverse() { break (s, inverse); }
+        };
+        let base = ratio_base(&self.keys.shape, participant, (&pk.delta_g1, &pk.vk.delta_g2));
+        pk.delta_g1 = (pk.delta_g1 * secret.0).into_affine();
+        pk.vk.delta_g2 = (pk.vk.delta_g2 * secret.0).into_affine();
+        let scale = |points: &[G1Affine]| G1Projective::normalize_batch(&points.iter().map(|p| *p * secret.1).collect::<Vec<_>>());
+        pk.h_query = scale(&pk.h_query);
+        pk.l_query = scale(&pk.l_query);
+        self.contributions.push(Contribution {
+            participant: participant.to_string(),
+            delta_g1: encode_hex(&pk.delta_g1),
+            delta_g2: encode_hex(&pk.vk.delta_g2),
+            ratio: encode_hex(&(base * secret.0).into_affine()),
+        });
+        self.contributions.last().expect("just pushed")
+    }
+
+    pub fn contributions(&self) -> &[Contribution] {
+        &self.contributions
+    }
+
+    /// The current keys follow from the initial ones by the recorded turns
+    pub fn verify(&self) -> bool {
+        verify_contributions(&self.initial.proving_key, &self.contributions, &self.keys)
+    }
+
+    pub fn finish(self) -> (CircuitKeys<Groth16Backend>, Vec<Contribution>) {
+        (self.keys, self.contributions)
+    }
+}
+
+/// Check a ceremony transcript: every turn scaled δ consistently in G1 and
+/// G2 by the secret behind its ratio, the final δ is the last turn's, the
+/// H and L queries were scaled by the inverse, and nothing else changed
+pub fn verify_contributions(
+    initial: &ProvingKey<Bn254>,
+    contributions: &[Contribution],
+    keys: &CircuitKeys<Groth16Backend>,
+) -> bool {
+    let mut delta = (initial.delta_g1, initial.vk.delta_g2);
+    for turn in contributions {
+        let (Some(delta_g1), Some(delta_g2), Some(ratio)) = (
+            decode_hex::<G1Affine>(&turn.delta_g1),
+            decode_hex::<G2Affine>(&turn.delta_g2),
+            decode_hex::<G1Affine>(&turn.ratio),
+        ) else {
+            return false;
+        };
+        let base = ratio_base(&keys.shape, &turn.participant, (&delta.0, &delta.1));
+        // Same factor in G1 and G2 (the setup's generators are random, so
+        // compare against the previous δ), and that factor is s
+        if delta_g1.is_zero() || Bn254::pairing(delta.0, delta_g2) != Bn254::pairing(delta_g1, delta.1)
+            || Bn254::pairing(base, delta_g2) != Bn254::pairing(ratio, delta.1)
+        {
+            return false;
+        }
+        delta = (delta_g1, delta_g2);
+    }
+
+    let pk = &keys.proving_key;
+    let unchanged = pk.vk.alpha_g1 == initial.vk.alpha_g1
+        && pk.vk.beta_g2 == initial.vk.beta_g2
+        && pk.vk.gamma_g2 == initial.vk.gamma_g2
+        && pk.vk.gamma_abc_g1 == initial.vk.gamma_abc_g1
+        && pk.beta_g1 == initial.beta_g1
+        && pk.a_query == initial.a_query
+        && pk.b_g1_query == initial.b_g1_query
+        && pk.b_g2_query == initial.b_g2_query
+        && pk.h_query.len() == initial.h_query.len()
+        && pk.l_query.len() == initial.l_query.len();
+    if !unchanged || (pk.delta_g1, pk.vk.delta_g2) != delta { return false; }
+
+    // ρ depends on the queries it checks, so no one can pick them to cancel
+    let mut transcript = Transcript::new(CEREMONY_PROTOCOL);
+    transcript.append_json(b"contributions", &contributions);
+    transcript.append_serialized(b"initial_h", &initial.h_query);
+    transcript.append_serialized(b"initial_l", &initial.l_query);
+    transcript.append_serialized(b"h", &pk.h_query);
+    transcript.append_serialized(b"l", &pk.l_query);
+    transcript.append_serialized(b"delta_g1", &pk.delta_g1);
+    transcript.append_serialized(b"delta_g2", &pk.vk.delta_g2);
+    let rho = transcript.challenge_scalar(b"rho");
+    let scaled = |before: &[G1Affine], after: &[G1Affine]| {
+        Bn254::pairing(combine(after, rho), G2Projective::from(delta.1))
+            == Bn254::pairing(combine(before, rho), G2Projective::from(initial.vk.delta_g2))
+    };
+    scaled(&initial.h_query, &pk.h_query) && scaled(&initial.l_query, &pk.l_query)
+}
+
+/// A proving or verifying key on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub version: u32,
+    pub backend: Backend,
+    pub shape: CircuitShape,
+    pub frac_bits: u32,
+    /// Of the verifying key
+    pub fingerprint: String,
+    /// Hex of the backend's encoding; proving files only
+    pub proving_key: Option<String>,
+    pub verifying_key: String,
+    /// How the keys came about, if by ceremony
+    #[serde(default)]
+    pub contributions: Vec<Contribution>,
+}
+
+impl KeyFile {
+    /// e.g. `groth16bn254-rankbound-m6-n4-r2-factored-q32.vk.json`
+    pub fn file_name(backend: Backend, shape: &CircuitShape, proving: bool) -> String {
+        let shape = match *shape {
+            CircuitShape::Capability { n, rank } => format!("capability-n{n}-r{rank}"),
+            CircuitShape::RankBound { m, n, rank, dense } => {
+                format!("rankbound-m{m}-n{n}-r{rank}-{}", if dense { "dense" } else { "factored" })
+            }
+            CircuitShape::Challenge { m, n, rank, count } => format!("challenge-m{m}-n{n}-r{rank}-c{count}"),
+            CircuitShape::Reconstruction { m, n, rank, count } => format!("reconstruction-m{m}-n{n}-r{rank}-c{count}"),
+            CircuitShape::Consistency { m, n, rank } => format!("consistency-m{m}-n{n}-r{rank}"),
+        };
+        let backend = format!("{backend:?}").to_lowercase();
+        let kind = if proving { "pk" } else { "vk" };
+        format!("{backend}-{shape}-q{FIXED_POINT_FRAC_BITS}.{kind}.json")
+    }
+
+    pub fn proving<S: ProofSystem>(keys: &CircuitKeys<S>) -> Self {
+        let mut file = Self::verifying(&keys.verifying_key());
+        file.proving_key = Some(hex::encode(S::encode_proving_key(&keys.proving_key)));
+        file
+    }
+
+    pub fn verifying<S: ProofSystem>(key: &CircuitVerifyingKey<S>) -> Self {
+        Self {
+            version: KEY_FILE_VERSION,
+            backend: S::BACKEND,
+            shape: key.shape,
+            frac_bits: FIXED_POINT_FRAC_BITS,
+            fingerprint: key.fingerprint(),
+            proving_key: None,
+            verifying_key: hex::encode(S::encode_verifying_key(&key.key)),
+            contributions: Vec::new(),
+        }
+    }
+
+    /// `None` unless the file is this version, backend and precision and
+    /// its key matches its fingerprint
+    pub fn verifying_key<S: ProofSystem>(&self) -> Option<CircuitVerifyingKey<S>> {
+        if self.version != KEY_FILE_VERSION || self.backend != S::BACKEND || self.frac_bits != FIXED_POINT_FRAC_BITS {
+            return None;
+        }
+        let key = S::decode_verifying_key(&hex::decode(&self.verifying_key).ok()?)?;
+        let key = CircuitVerifyingKey { shape: self.shape, key };
+        (key.fingerprint() == self.fingerprint).then_some(key)
+    }
+
+    /// As `verifying_key`, and the proving key must contain that key
+    pub fn proving_keys<S: ProofSystem>(&self) -> Option<CircuitKeys<S>> {
+        let verifying = self.verifying_key::<S>()?;
+        let proving_key = S::decode_proving_key(&hex::decode(self.proving_key.as_ref()?).ok()?)?;
+        let keys = CircuitKeys { shape: self.shape, proving_key };
+        (keys.verifying_key().fingerprint() == verifying.fingerprint()).then_some(keys)
+    }
+
+    pub fn save(&self, dir: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = dir.as_ref().join(Self::file_name(self.backend, &self.shape, self.proving_key.is_some()));
+        std::fs::write(&path, serde_json::to_vec(self)?)?;
+        Ok(path)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+}
+
+/// Keys by shape for one backend, backed by a directory of key files
+pub struct KeyCache<S: ProofSystem = Groth16Backend> {
+    dir: PathBuf,
+    proving: HashMap<CircuitShape, CircuitKeys<S>>,
+    verifying: HashMap<CircuitShape, CircuitVerifyingKey<S>>,
+    /// Fingerprints keys for a shape must have
+    pinned: HashMap<CircuitShape, String>,
+}
+
+impl<S: ProofSystem> KeyCache<S> {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        Self { dir: dir.as_ref().to_path_buf(), proving: HashMap::new(), verifying: HashMap::new(), pinned: HashMap::new() }
+    }
+
+    /// Accept only the key with `fingerprint` for `shape`, dropping any
+    /// other already cached
+    pub fn pin(&mut self, shape: CircuitShape, fingerprint: &str) {
+        if self.verifying.get(&shape).is_some_and(|key| key.fingerprint() != fingerprint) {
+            self.verifying.remove(&shape);
+            self.proving.remove(&shape);
+        }
+        self.pinned.insert(shape, fingerprint.to_string());
+    }
+
+    fn admits(&self, key: &CircuitVerifyingKey<S>) -> bool {
+        self.pinned.get(&key.shape).is_none_or(|pinned| *pinned == key.fingerprint())
+    }
+
+    /// Cache `keys` and write their proving and verifying files; `false`
+    /// if a pinned fingerprint refuses them
+    pub fn insert(&mut self, keys: CircuitKeys<S>, contributions: &[Contribution]) -> io::Result<bool> {
+        let verifying = keys.verifying_key();
+        if !self.admits(&verifying) { return Ok(false); }
+        std::fs::create_dir_all(&self.dir)?;
+        for mut file in [KeyFile::proving(&keys), KeyFile::verifying(&verifying)] {
+            file.contributions = contributions.to_vec();
+            file.save(&self.dir)?;
+        }
+        self.verifying.insert(keys.shape, verifying);
+        self.proving.insert(keys.shape, keys);
+        Ok(true)
+    }
+
+    fn load(&self, shape: &CircuitShape, proving: bool) -> Option<KeyFile> {
+        KeyFile::load(self.dir.join(KeyFile::file_name(S::BACKEND, shape, proving))).ok()
+    }
+
+    pub fn proving_keys(&mut self, shape: CircuitShape) -> Option<&CircuitKeys<S>> {
+        if !self.proving.contains_key(&shape) {
+            let keys = self.load(&shape, true)?.proving_keys::<S>().filter(|k| self.admits(&k.verifying_key()))?;
+            self.proving.insert(shape, keys);
+        }
+        self.proving.get(&shape)
+    }
+
+    pub fn verifying_key(&mut self, shape: CircuitShape) -> Option<&CircuitVerifyingKey<S>> {
+        if !self.verifying.contains_key(&shape) {
+            let key = match self.proving.get(&shape) {
+                Some(keys) => keys.verifying_key(),
+                None => self.load(&shape, false)?.verifying_key::<S>()?,
+            };
+            if !self.admits(&key) { return None; }
+            self.verifying.insert(shape, key);
+        }
+        self.verifying.get(&shape)
+    }
+
+    /// Cached keys for `shape`, set up and saved if there are none
+    pub fn get_or_setup(&mut self, shape: CircuitShape) -> Option<&CircuitKeys<S>> {
+        if self.proving_keys(shape).is_none() {
+            let keys = CircuitKeys::setup(shape)?;
+            if !self.insert(keys, &[]).ok()? { return None; }
+        }
+        self.proving.get(&shape)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::fixture::Prover;
+    use crate::zk::CapabilityProof;
+
+    #[test]
+    fn test_key_management() {
+        let shape = CircuitShape::Capability { n: 2, rank: 1 };
+        let a = CircuitKeys::<Groth16Backend>::setup_seeded(shape, 7).unwrap();
+        let b = CircuitKeys::<Groth16Backend>::setup_seeded(shape, 7).unwrap();
+        assert_eq!(a.verifying_key().fingerprint(), b.verifying_key().fingerprint());
+
+        // Two contributions move δ away from the seeded setup
+        let mut ceremony = Ceremony::new(a.clone());
+        ceremony.contribute("alice", &mut rand::thread_rng());
+        ceremony.contribute("bob", &mut rand::thread_rng());
+        assert!(ceremony.verify());
+        let mut forged = ceremony.clone();
+        forged.contributions[0].participant = "mallory".into();
+        assert!(!forged.verify());
+        // Shifts that cancel under a ρ drawn from the contributions alone
+        let mut shifted = ceremony.clone();
+        let mut early = Transcript::new(CEREMONY_PROTOCOL);
+        early.append_json(b"contributions", &shifted.contributions);
+        let rho = early.challenge_scalar(b"rho");
+        let x = G1Projective::rand(&mut rand::thread_rng());
+        let h = &mut shifted.keys.proving_key.h_query;
+        h[0] = (h[0] + x).into_affine();
+        h[1] = (h[1] - x * rho.inverse().unwrap()).into_affine();
+        assert!(!shifted.verify());
+        let (keys, contributions) = ceremony.finish();
+        assert_ne!(keys.verifying_key().fingerprint(), a.verifying_key().fingerprint());
+
+        // Proofs made with the ceremony keys verify through the cache
+        let dir = std::env::temp_dir().join(format!("dlrs-keys-{}", uuid::Uuid::new_v4()));
+        let mut cache = KeyCache::<Groth16Backend>::new(&dir);
+        assert!(cache.insert(keys.clone(), &contributions).unwrap());
+        let Prover { seed, opening, d, score, .. } = Prover::new(3, 2, 1);
+        let mut fresh = KeyCache::<Groth16Backend>::new(&dir);
+        let proving = fresh.proving_keys(shape).unwrap();
+        let proof = CapabilityProof::prove_capability(proving, &seed.lrim, &opening, &d, "test", score * 0.9).unwrap();
+        assert!(fresh.verifying_key(shape).is_some_and(|key| proof.verify_capability(&seed.commitment, key, &d)));
+        let file = KeyFile::load(dir.join(KeyFile::file_name(Backend::Groth16Bn254, &shape, false))).unwrap();
+        assert_eq!(file.contributions, contributions);
+
+        // Keys with another fingerprint are refused
+        assert!(!proof.verify_capability(&seed.commitment, &a.verifying_key(), &d));
+        let mut pinned = KeyCache::<Groth16Backend>::new(&dir);
+        pinned.pin(shape, &a.verifying_key().fingerprint());
+        assert!(pinned.verifying_key(shape).is_none());
+        let mut tampered = file.clone();
+        tampered.verifying_key = KeyFile::verifying(&a.verifying_key()).verifying_key;
+        assert!(tampered.verifying_key::<Groth16Backend>().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,10 +1,75 @@
 //! Zero-Knowledge layer for DLRS
 //!
 //! Enables proving properties of low-rank matrices without revealing them.
-//! Uses Pedersen commitments for matrix binding and Groth16 for capability proofs.
+//! Uses Pedersen commitments for matrix binding, a pluggable proof system
+//! (Groth16 over BN254, or a mock for tests) for capability proofs and
+//! Fiat–Shamir transcripts to make SNARK and challenge proofs
+//! non-interactive. Compatibility is interactive; its proofs are signed
+//! transcripts of a finished run.
 
+mod backend;
+mod challenge;
+mod circuit;
 mod commitment;
+mod compatibility;
+mod consistency;
+mod gadgets;
+mod keys;
+mod pedersen;
 mod proof;
+mod rank;
+mod reconstruction;
+mod transcript;
 
+pub use backend::{Backend, Groth16Backend, MockBackend, ProofSystem};
+pub use challenge::{
+    sample_challenges, Challenge, ChallengeCircuit, ChallengeMessage, ChallengeProver, ChallengeStatement,
+    ChallengeVerifier, PublicChallenge,
+};
+pub use circuit::{
+    factor_digest, mimc_hash, CapabilityCircuit, Circuit, CircuitKeys, CircuitShape, CircuitVerifyingKey, MIMC_ROUNDS,
+};
 pub use commitment::ZkCommitment;
-pub use proof::CapabilityProof;
+pub use consistency::{ConsistencyCircuit, ConsistencyStatement, MaskedCombination};
+pub use compatibility::{
+    deal, run_compatibility, CompatibilityMode, CompatibilityOutcome, CompatibilityParty, CompatibilityTranscript,
+    DealerCommitments, DealerShare, ProtocolMessage, Role,
+};
+pub use gadgets::{EncodingError, FactorWires, FixedFactors, FixedPoint, Wire, VALUE_BITS};
+pub use keys::{verify_contributions, Ceremony, Contribution, KeyCache, KeyFile, KEY_FILE_VERSION, KEY_FINGERPRINT_TAG};
+pub use pedersen::{
+    blinding_generator, commit_field_vector, commit_vector, committed_vectors, value_generators, ColumnOpening,
+    CommittedVector, OpeningKey, OpeningSignature, PedersenCommitment, PedersenOpening, PEDERSEN_TAG,
+};
+pub use proof::{CapabilityProof, ProofType, SnarkResponse, MAX_RANK_TOLERANCE};
+pub use rank::{dense_digest, RankBoundCircuit};
+pub use reconstruction::{Benchmark, ReconstructionCircuit, ReconstructionStatement};
+pub use transcript::{Transcript, TRANSCRIPT_TAG};
+
+#[cfg(test)]
+pub(crate) mod fixture {
+    use super::PedersenOpening;
+    use crate::seed::DnaSeed;
+    use nalgebra::{DMatrix, DVector};
+
+    /// A seed factoring a random K, with its opening, a random unit domain
+    /// vector d and the seed's capability score in d
+    pub struct Prover {
+        pub k: DMatrix<f64>,
+        pub seed: DnaSeed,
+        pub opening: PedersenOpening,
+        pub d: DVector<f64>,
+        pub score: f64,
+    }
+
+    impl Prover {
+        pub fn new(m: usize, n: usize, rank: usize) -> Self {
+            let k = DMatrix::new_random(m, n);
+            let seed = DnaSeed::new("prover", &k, rank, vec!["test".into()]);
+            let opening = seed.opening.clone().unwrap();
+            let d = DVector::<f64>::new_random(n).normalize();
+            let score = seed.lrim.canonical().capability_in_domain(&d);
+            Self { k, seed, opening, d, score }
+        }
+    }
+}
@@ -0,0 +1,356 @@
+//! Pedersen vector commitments over BN254 G1
+//!
+//! Each column of U and V, and the vector Σ, is committed separately as
+//! C = Σₖ xₖ·Gₖ + ρ·H, where the xₖ are the Q32.32 fixed-point values of the
+//! canonical factorization (see `seed::encoding`) mapped into the scalar
+//! field, and ρ is a random blinding. Commitments are perfectly hiding and
+//! computationally binding, and single columns can be opened on their own.
+//!
+//! The generators are derived by try-and-increment hashing to the curve,
+//! so nobody knows a discrete-log relation between them. BN254 G1 has
+//! cofactor 1, so every point found lies in the prime-order group.
+//!
+//! An opening doubles as a signing key. The sum of all column points
+//! commits to the zero-padded sum of the columns under the sum of the
+//! blindings, and a Schnorr proof of knowledge of that opening, with the
+//! challenge drawn over a message, signs the message. Opening single
+//! columns does not give the key away.
+
+use super::transcript::Transcript;
+use crate::seed::{LowRankIdentity, LrimScalar, FIXED_POINT_FRAC_BITS, to_fixed};
+use ark_bn254::{Fq, Fr, G1Affine, G1Projective};
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{AdditiveGroup, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::sync::{Mutex, OnceLock};
+
+/// Domain-separation tag for generator derivation and commitment digests
+pub const PEDERSEN_TAG: &[u8] = b"DLRS/PEDERSEN/BN254/v1\0";
+
+const SIGNATURE_PROTOCOL: &[u8] = b"DLRS/OPENING_SIGNATURE/v1";
+
+/// `index`-th point for `label`, by hashing to an x-coordinate until one lies on the curve
+fn hash_to_curve(label: &[u8], index: u64) -> G1Affine {
+    (0u32..)
+        .find_map(|counter| {
+            let digest = Sha256::new()
+                .chain_update(PEDERSEN_TAG)
+                .chain_update(label)
+                .chain_update(index.to_le_bytes())
+                .chain_update(counter.to_le_bytes())
+                .finalize();
+            let x = Fq::from_le_bytes_mod_order(&digest);
+            G1Affine::get_point_from_x_unchecked(x, digest[31] & 1 == 1)
+        })
+        .expect("half of all x-coordinates lie on the curve")
+}
+
+/// The first `len` value generators G₀, G₁, …, derived once and cached
+pub fn value_generators(len: usize) -> Vec<G1Affine> {
+    static CACHE: OnceLock<Mutex<Vec<G1Affine>>> = OnceLock::new();
+    let mut cache = CACHE.get_or_init(|| Mutex::new(Vec::new())).lock().expect("generator cache poisoned");
+    while cache.len() < len {
+        let next = hash_to_curve(b"G", cache.len() as u64);
+        cache.push(next);
+    }
+    cache[..len].to_vec()
+}
+
+/// The blinding generator H
+pub fn blinding_generator() -> G1Affine {
+    static H: OnceLock<G1Affine> = OnceLock::new();
+    *H.get_or_init(|| hash_to_curve(b"H", 0))
+}
+
+/// Σₖ xₖ·Gₖ + ρ·H
+pub fn commit_vector(values: &[i64], blinding: &Fr) -> G1Affine {
+    let scalars: Vec<Fr> = values.iter().map(|&x| Fr::from(x)).collect();
+    commit_field_vector(&scalars, blinding)
+}
+
+/// `commit_vector` for arbitrary field elements
+pub fn commit_field_vector(values: &[Fr], blinding: &Fr) -> G1Affine {
+    let bases = value_generators(values.len());
+    let sum = G1Projective::msm(&bases, values).expect("one base per scalar");
+    (sum + blinding_generator() * blinding).into_affine()
+}
+
+/// Uniform scalar: 512 random bits reduced mod r (negligible bias)
+pub(crate) fn random_scalar() -> Fr {
+    let mut bytes = [0u8; 64];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    Fr::from_le_bytes_mod_order(&bytes)
+}
+
+pub(crate) fn encode_hex(value: &impl CanonicalSerialize) -> String {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes).expect("writing to a Vec cannot fail");
+    hex::encode(bytes)
+}
+
+pub(crate) fn decode_hex<V: CanonicalDeserialize>(text: &str) -> Option<V> {
+    V::deserialize_compressed(hex::decode(text).ok()?.as_slice()).ok()
+}
+
+/// Which committed vector a column commitment or opening refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CommittedVector {
+    Sigma,
+    U(usize),
+    V(usize),
+}
+
+/// Fixed-point values of every committed vector of the canonical factorization
+pub fn committed_vectors<T: LrimScalar>(lrim: &LowRankIdentity<T>) -> Vec<(CommittedVector, Vec<i64>)> {
+    let c = lrim.to_f64().canonical();
+    let fixed = |xs: &[f64]| xs.iter().map(|&x| to_fixed(x, FIXED_POINT_FRAC_BITS)).collect();
+    let mut vectors = vec![(CommittedVector::Sigma, fixed(c.sigma.as_slice()))];
+    for i in 0..c.rank {
+        vectors.push((CommittedVector::U(i), fixed(c.u.column(i).as_slice())));
+    }
+    for i in 0..c.rank {
+        vectors.push((CommittedVector::V(i), fixed(c.v.column(i).as_slice())));
+    }
+    vectors
+}
+
+/// Per-column commitments, as hex-encoded compressed G1 points
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PedersenCommitment {
+    pub sigma: String,
+    pub u: Vec<String>,
+    pub v: Vec<String>,
+}
+
+/// The blindings of a `PedersenCommitment` (hex-encoded scalars). Private to
+/// the committer: together with the factors it opens every column.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct PedersenOpening {
+    pub sigma: String,
+    pub u: Vec<String>,
+    pub v: Vec<String>,
+    /// Blinding of the circuit digest (see `zk::circuit`), if there is one
+    #[serde(default)]
+    pub digest: String,
+}
+
+impl fmt::Debug for PedersenOpening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digest = usize::from(!self.digest.is_empty());
+        write!(f, "PedersenOpening {{ {} blindings }}", 1 + self.u.len() + self.v.len() + digest)
+    }
+}
+
+/// Schnorr signature under the key an opening gives (see the module
+/// docs), as hex-encoded points and scalars
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpeningSignature {
+    /// T = Σₖ aₖ·Gₖ + b·H for fresh a, b
+    pub nonce: String,
+    /// aₖ + c·xₖ
+    pub values: Vec<String>,
+    /// b + c·ρ
+    pub blinding: String,
+}
+
+/// The summed opening of a commitment, ready to sign with. Private to the
+/// committer.
+#[derive(Clone)]
+pub struct OpeningKey {
+    point: G1Affine,
+    values: Vec<Fr>,
+    blinding: Fr,
+}
+
+fn signature_challenge(point: &G1Affine, nonce: &G1Affine, message: &[u8]) -> Fr {
+    let mut transcript = Transcript::new(SIGNATURE_PROTOCOL);
+    transcript.append_serialized(b"key", point);
+    transcript.append_serialized(b"nonce", nonce);
+    transcript.append_message(b"message", message);
+    transcript.challenge_scalar(b"challenge")
+}
+
+impl OpeningKey {
+    pub fn sign(&self, message: &[u8]) -> OpeningSignature {
+        let nonces: Vec<Fr> = self.values.iter().map(|_| random_scalar()).collect();
+        let nonce_blinding = random_scalar();
+        let nonce = commit_field_vector(&nonces, &nonce_blinding);
+        let c = signature_challenge(&self.point, &nonce, message);
+        OpeningSignature {
+            nonce: encode_hex(&nonce),
+            values: nonces.iter().zip(&self.values).map(|(a, x)| encode_hex(&(*a + c * x))).collect(),
+            blinding: encode_hex(&(nonce_blinding + c * self.blinding)),
+        }
+    }
+}
+
+/// One committed vector revealed together with its blinding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnOpening {
+    pub vector: CommittedVector,
+    /// Fixed-point values (Q32.32)
+    pub values: Vec<i64>,
+    pub blinding: String,
+}
+
+impl PedersenCommitment {
+    /// Commit to every column with fresh blindings
+    pub fn commit<T: LrimScalar>(lrim: &LowRankIdentity<T>) -> (Self, PedersenOpening) {
+        let mut commitment = Self { sigma: String::new(), u: Vec::new(), v: Vec::new() };
+        let mut opening = PedersenOpening { sigma: String::new(), u: Vec::new(), v: Vec::new(), digest: String::new() };
+        for (vector, values) in committed_vectors(lrim) {
+            let blinding = random_scalar();
+            let point = encode_hex(&commit_vector(&values, &blinding));
+            let blinding = encode_hex(&blinding);
+            match vector {
+                CommittedVector::Sigma => {
+                    commitment.sigma = point;
+                    opening.sigma = blinding;
+                }
+                CommittedVector::U(_) => {
+                    commitment.u.push(point);
+                    opening.u.push(blinding);
+                }
+                CommittedVector::V(_) => {
+                    commitment.v.push(point);
+                    opening.v.push(blinding);
+                }
+            }
+        }
+        (commitment, opening)
+    }
+
+    pub fn point(&self, vector: CommittedVector) -> Option<G1Affine> {
+        let hex = match vector {
+            CommittedVector::Sigma => Some(&self.sigma),
+            CommittedVector::U(i) => self.u.get(i),
+            CommittedVector::V(i) => self.v.get(i),
+        }?;
+        decode_hex(hex)
+    }
+
+    /// Sum of all column points
+    fn aggregate(&self) -> Option<G1Affine> {
+        let points = std::iter::once(&self.sigma).chain(&self.u).chain(&self.v);
+        let sum = points.map(|p| decode_hex::<G1Affine>(p).map(G1Projective::from)).sum::<Option<G1Projective>>()?;
+        Some(sum.into_affine())
+    }
+
+    /// `signature` was made over `message` with this commitment's opening
+    pub fn verify_signature(&self, message: &[u8], signature: &OpeningSignature) -> bool {
+        let (Some(point), Some(nonce), Some(blinding)) =
+            (self.aggregate(), decode_hex::<G1Affine>(&signature.nonce), decode_hex::<Fr>(&signature.blinding))
+        else {
+            return false;
+        };
+        let Some(values) = signature.values.iter().map(|v| decode_hex(v)).collect::<Option<Vec<Fr>>>() else { return false };
+        let c = signature_challenge(&point, &nonce, message);
+        commit_field_vector(&values, &blinding) == (nonce + point * c).into_affine()
+    }
+
+    /// SHA256 over all points, in order: a short, hiding handle
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(PEDERSEN_TAG);
+        hasher.update((self.u.len() as u64).to_le_bytes());
+        for point in std::iter::once(&self.sigma).chain(&self.u).chain(&self.v) {
+            hasher.update(point.as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    pub fn verify_column(&self, opening: &ColumnOpening) -> bool {
+        let Some(point) = self.point(opening.vector) else { return false };
+        let Some(blinding) = decode_hex::<Fr>(&opening.blinding) else { return false };
+        commit_vector(&opening.values, &blinding) == point
+    }
+
+    /// Every column matches the factors under the given blindings
+    pub fn verify_opening<T: LrimScalar>(&self, lrim: &LowRankIdentity<T>, opening: &PedersenOpening) -> bool {
+        if self.u.len() != lrim.rank || self.v.len() != lrim.rank { return false; }
+        committed_vectors(lrim).into_iter().all(|(vector, values)| {
+            opening.blinding(vector).is_some_and(|blinding| {
+                self.verify_column(&ColumnOpening { vector, values, blinding: blinding.clone() })
+            })
+        })
+    }
+}
+
+impl PedersenOpening {
+    pub fn blinding(&self, vector: CommittedVector) -> Option<&String> {
+        match vector {
+            CommittedVector::Sigma => Some(&self.sigma),
+            CommittedVector::U(i) => self.u.get(i),
+            CommittedVector::V(i) => self.v.get(i),
+        }
+    }
+
+    /// The key to sign with for `commitment`; `None` unless this opens it
+    /// to `lrim`
+    pub fn signing_key<T: LrimScalar>(&self, commitment: &PedersenCommitment, lrim: &LowRankIdentity<T>) -> Option<OpeningKey> {
+        if !commitment.verify_opening(lrim, self) { return None; }
+        let vectors = committed_vectors(lrim);
+        let mut values = vec![Fr::ZERO; vectors.iter().map(|(_, xs)| xs.len()).max().unwrap_or(0)];
+        let mut blinding = Fr::ZERO;
+        for (vector, xs) in vectors {
+            for (sum, &x) in values.iter_mut().zip(&xs) {
+                *sum += Fr::from(x);
+            }
+            blinding += decode_hex::<Fr>(self.blinding(vector)?)?;
+        }
+        Some(OpeningKey { point: commitment.aggregate()?, values, blinding })
+    }
+
+    /// Reveal one committed vector of `lrim`
+    pub fn open<T: LrimScalar>(&self, lrim: &LowRankIdentity<T>, vector: CommittedVector) -> Option<ColumnOpening> {
+        let blinding = self.blinding(vector)?.clone();
+        let (_, values) = committed_vectors(lrim).into_iter().find(|(v, _)| *v == vector)?;
+        Some(ColumnOpening { vector, values, blinding })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::DMatrix;
+
+    #[test]
+    fn test_pedersen_open_and_verify() {
+        let lrim = LowRankIdentity::from_matrix(&DMatrix::new_random(7, 5), 3);
+        let (commitment, opening) = PedersenCommitment::commit(&lrim);
+        assert!(commitment.verify_opening(&lrim, &opening));
+
+        // Hiding: a second commitment to the same factors looks unrelated
+        let (again, other_opening) = PedersenCommitment::commit(&lrim);
+        assert_ne!(commitment.digest(), again.digest());
+        assert!(!commitment.verify_opening(&lrim, &other_opening));
+
+        // Sign conventions do not matter, values do
+        let mut flipped = lrim.clone();
+        flipped.u.column_mut(1).neg_mut();
+        flipped.v.column_mut(1).neg_mut();
+        assert!(commitment.verify_opening(&flipped, &opening));
+        let mut changed = lrim.clone();
+        changed.sigma[2] *= 1.001;
+        assert!(!commitment.verify_opening(&changed, &opening));
+
+        let column = opening.open(&lrim, CommittedVector::V(1)).unwrap();
+        assert!(commitment.verify_column(&column));
+        let mut forged = column.clone();
+        forged.values[0] += 1;
+        assert!(!commitment.verify_column(&forged));
+        assert!(!commitment.verify_column(&ColumnOpening { vector: CommittedVector::U(1), ..column }));
+
+        // Openings sign messages
+        let key = opening.signing_key(&commitment, &lrim).unwrap();
+        let signature = key.sign(b"message");
+        assert!(commitment.verify_signature(b"message", &signature));
+        assert!(!commitment.verify_signature(b"other", &signature));
+        assert!(!again.verify_signature(b"message", &signature));
+        assert!(other_opening.signing_key(&commitment, &lrim).is_none());
+    }
+}
@@ -1,72 +1,596 @@
 //! Capability Proof — prove knowledge properties without revealing knowledge
 //!
-//! Three proof types:
+//! Six proof types:
 //! 1. Capability: "I can solve problems in domain D with accuracy ≥ α"
 //! 2. Compatibility: "Our matrices are complementary"
 //! 3. Rank bound: "My knowledge has rank ≤ r"
+//! 4. Challenge response: "These are my answers to your problems, and they
+//!    are close to the ones you expected" (see `zk::challenge`)
+//! 5. Reconstruction error: "My knowledge reproduces this benchmark to
+//!    within ε" (see `zk::reconstruction`)
+//! 6. Consistency: "My Pedersen points and circuit digest commit to the
+//!    same factors" (see `zk::consistency`)
+//!
+//! All but compatibility proofs are SNARKs (see `zk::circuit`, `zk::rank`,
+//! `zk::challenge`, `zk::reconstruction` and `zk::consistency`) against the circuit
+//! digest in the prover's commitment, made with any `ProofSystem` and
+//! tagged with its backend. Their `proof_hash` is a Fiat–Shamir
+//! transcript over that digest and everything the proof states, so a
+//! serialised proof verifies on its own, with no live verifier.
+//!
+//! Compatibility proofs are not Fiat–Shamir proofs: they publish the
+//! signed transcript of a finished two-party run (see `zk::compatibility`),
+//! with its hash as `proof_hash`. They show the outcome follows from what
+//! both parties sent, and are only as sound as the parties were honest
+//! after round 0.
 
-use crate::seed::LowRankIdentity;
+use super::backend::{Backend, ProofSystem};
+use super::challenge::{ChallengeCircuit, ChallengeStatement, PublicChallenge};
+use super::compatibility::{CompatibilityOutcome, CompatibilityTranscript};
+use super::consistency::{ConsistencyCircuit, ConsistencyStatement};
+use super::circuit::{CapabilityCircuit, CircuitKeys, CircuitShape, CircuitVerifyingKey};
+use super::keys::KeyCache;
+use super::rank::RankBoundCircuit;
+use super::reconstruction::{Benchmark, ReconstructionCircuit, ReconstructionStatement};
+use super::transcript::Transcript;
+use super::pedersen::{decode_hex, PedersenOpening};
+use super::ZkCommitment;
+use crate::seed::{from_fixed, to_fixed, LowRankIdentity, FIXED_POINT_FRAC_BITS};
+use ark_bn254::Fr;
+use nalgebra::DMatrix;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+
+const SNARK_PROTOCOL: &[u8] = b"DLRS/SNARK_PROOF/v1";
+
+/// Largest per-entry tolerance `verify_against_commitment` accepts in a
+/// dense rank bound; see `verify_rank_bound` for another policy
+pub const MAX_RANK_TOLERANCE: f64 = 1e-6;
+
+/// How far from 1 the norm of a capability proof's domain vector may be
+const DOMAIN_NORM_TOLERANCE: f64 = 1e-6;
+
+fn is_unit(domain: &nalgebra::DVector<f64>) -> bool {
+    (domain.norm() - 1.0).abs() <= DOMAIN_NORM_TOLERANCE
+}
+
+fn fixed_domain(domain: &nalgebra::DVector<f64>) -> Vec<i64> {
+    domain.iter().map(|&x| to_fixed(x, FIXED_POINT_FRAC_BITS)).collect()
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapabilityProof {
     pub proof_type: ProofType,
     pub claim: String,
     pub proof_hash: String,
+    /// Session nonce of a challenge-response proof
     pub verifier_challenge: Option<String>,
+    /// The answers of a challenge-response proof, row by row
     pub response: Option<Vec<f64>>,
+    #[serde(default)]
+    pub snark: Option<SnarkResponse>,
+    #[serde(default)]
+    pub compatibility: Option<CompatibilityTranscript>,
+    /// The masked combinations of a consistency proof
+    #[serde(default)]
+    pub consistency: Option<ConsistencyStatement>,
+}
+
+/// A SNARK together with the public inputs that are not implied by the
+/// claim or the commitment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnarkResponse {
+    #[serde(default)]
+    pub backend: Backend,
+    /// Of the verifying key the proof was made for
+    #[serde(default)]
+    pub key_fingerprint: Option<String>,
+    /// Hex-encoded, as the backend writes it
+    pub proof: String,
+    /// Q32.32: the domain vector for capability proofs,
+    /// `ChallengeStatement::flatten` for challenge responses,
+    /// `ReconstructionStatement::flatten` for reconstruction errors
+    pub inputs: Vec<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ProofType {
     Capability { domain: String, min_accuracy: f64 },
     Compatibility { other_commitment: String, subspace: String },
-    RankBound { max_rank: usize },
+    /// `tolerance` is the per-entry slack of a bound on a dense commitment
+    RankBound {
+        max_rank: usize,
+        #[serde(default)]
+        tolerance: Option<f64>,
+    },
+    ChallengeResponse { domain: String, quality_bound: f64 },
+    /// ‖UΣVᵀX − Y‖_F ≤ `max_error`, times ‖Y‖_F if `relative`
+    ReconstructionError { benchmark: String, max_error: f64, relative: bool },
+    /// The commitment's Pedersen points and circuit digest agree
+    Consistency,
+}
+
+impl ProofType {
+    /// Reals in Q32.32, so that a JSON round trip cannot change the binding
+    fn append_to(&self, transcript: &mut Transcript) {
+        let fixed = |x: f64| to_fixed(x, FIXED_POINT_FRAC_BITS);
+        match self {
+            ProofType::Capability { domain, min_accuracy } => {
+                transcript.append_message(b"capability", domain.as_bytes());
+                transcript.append_i64s(b"min_accuracy", &[fixed(*min_accuracy)]);
+            }
+            ProofType::Compatibility { other_commitment, subspace } => {
+                transcript.append_message(b"compatibility", other_commitment.as_bytes());
+                transcript.append_message(b"subspace", subspace.as_bytes());
+            }
+            ProofType::RankBound { max_rank, tolerance } => {
+                transcript.append_u64(b"rank_bound", *max_rank as u64);
+                transcript.append_i64s(b"tolerance", &tolerance.map(fixed).into_iter().collect::<Vec<_>>());
+            }
+            ProofType::ChallengeResponse { domain, quality_bound } => {
+                transcript.append_message(b"challenge_response", domain.as_bytes());
+                transcript.append_i64s(b"quality_bound", &[fixed(*quality_bound)]);
+            }
+            ProofType::ReconstructionError { benchmark, max_error, relative } => {
+                transcript.append_message(b"reconstruction_error", benchmark.as_bytes());
+                transcript.append_i64s(b"max_error", &[fixed(*max_error), i64::from(*relative)]);
+            }
+            ProofType::Consistency => transcript.append_message(b"consistency", &[]),
+        }
+    }
 }
 
 impl CapabilityProof {
-    pub fn prove_capability(
+    fn with_snark<S: ProofSystem>(
+        proof_type: ProofType,
+        claim: String,
+        keys: &CircuitKeys<S>,
+        proof: &S::Proof,
+        inputs: Vec<i64>,
+    ) -> Self {
+        let key_fingerprint = Some(keys.verifying_key().fingerprint());
+        Self {
+            proof_type,
+            claim,
+            proof_hash: String::new(),
+            verifier_challenge: None,
+            response: None,
+            snark: Some(SnarkResponse { backend: S::BACKEND, key_fingerprint, proof: S::encode_proof(proof), inputs }),
+            compatibility: None,
+            consistency: None,
+        }
+    }
+
+    /// Fiat–Shamir binding of the SNARK to the circuit digest it is against
+    /// and to everything the proof states
+    fn snark_binding(&self, digest: &Fr) -> Option<String> {
+        let snark = self.snark.as_ref()?;
+        let mut transcript = Transcript::new(SNARK_PROTOCOL);
+        transcript.append_serialized(b"digest", digest);
+        transcript.append_json(b"backend", &snark.backend);
+        transcript.append_json(b"key_fingerprint", &snark.key_fingerprint);
+        self.proof_type.append_to(&mut transcript);
+        transcript.append_message(b"claim", self.claim.as_bytes());
+        transcript.append_json(b"verifier_challenge", &self.verifier_challenge);
+        let response = self.response.iter().flatten().map(|&y| to_fixed(y, FIXED_POINT_FRAC_BITS)).collect::<Vec<_>>();
+        transcript.append_i64s(b"response", &response);
+        transcript.append_i64s(b"inputs", &snark.inputs);
+        transcript.append_json(b"consistency", &self.consistency);
+        transcript.append_message(b"proof", snark.proof.as_bytes());
+        Some(hex::encode(transcript.challenge_bytes(b"proof_hash")))
+    }
+
+    fn sealed(mut self, digest: &Fr) -> Self {
+        self.proof_hash = self.snark_binding(digest).unwrap_or_default();
+        self
+    }
+
+    /// Prove |Σᵢ σᵢ(vᵢ·d)| ≥ `threshold` for the canonical factors of
+    /// `lrim`, as committed with `opening`. `None` if the claim is false,
+    /// `domain` is not a unit vector or the keys are for another shape.
+    pub fn prove_capability<S: ProofSystem>(
+        keys: &CircuitKeys<S>,
         lrim: &LowRankIdentity,
+        opening: &PedersenOpening,
         domain: &nalgebra::DVector<f64>,
         domain_name: &str,
         threshold: f64,
     ) -> Option<Self> {
-        let capability = lrim.capability_in_domain(domain);
-        if capability < threshold { return None; }
-        let mut hasher = Sha256::new();
-        hasher.update(lrim.fingerprint().as_bytes());
-        hasher.update(domain_name.as_bytes());
-        hasher.update(threshold.to_le_bytes());
-        let proof_hash = hex::encode(hasher.finalize());
-        Some(Self {
-            proof_type: ProofType::Capability {
-                domain: domain_name.to_string(),
-                min_accuracy: threshold,
-            },
-            claim: format!("Entity has capability ≥ {:.3} in domain '{}'", threshold, domain_name),
-            proof_hash,
-            verifier_challenge: None,
-            response: None,
-        })
+        if !is_unit(domain) { return None; }
+        let blinding: Fr = decode_hex(&opening.digest)?;
+        let domain = fixed_domain(domain);
+        let circuit = CapabilityCircuit::new(lrim, blinding, domain.clone(), to_fixed(threshold, FIXED_POINT_FRAC_BITS));
+        let digest = circuit.digest;
+        let proof = keys.prove(circuit)?;
+        let proof = Self::with_snark::<S>(
+            ProofType::Capability { domain: domain_name.to_string(), min_accuracy: threshold },
+            format!("Entity has capability ≥ {:.3} in domain '{}'", threshold, domain_name),
+            keys,
+            &proof,
+            domain,
+        );
+        Some(proof.sealed(&digest))
     }
 
-    pub fn prove_rank_bound(lrim: &LowRankIdentity, claimed_max_rank: usize) -> Option<Self> {
+    /// Prove that the factored commitment to `lrim` opens to at most
+    /// `claimed_max_rank` columns
+    pub fn prove_rank_bound<S: ProofSystem>(
+        keys: &CircuitKeys<S>,
+        lrim: &LowRankIdentity,
+        opening: &PedersenOpening,
+        claimed_max_rank: usize,
+    ) -> Option<Self> {
         if lrim.rank > claimed_max_rank { return None; }
-        let mut hasher = Sha256::new();
-        hasher.update(lrim.fingerprint().as_bytes());
-        hasher.update(claimed_max_rank.to_le_bytes());
-        let proof_hash = hex::encode(hasher.finalize());
+        let circuit = RankBoundCircuit::factored(lrim, decode_hex(&opening.digest)?);
+        let digest = circuit.digest;
+        let proof = keys.prove(circuit)?;
+        let claim = format!("Knowledge has rank ≤ {}", claimed_max_rank);
+        let proof_type = ProofType::RankBound { max_rank: claimed_max_rank, tolerance: None };
+        let proof = Self::with_snark::<S>(proof_type, claim, keys, &proof, Vec::new());
+        Some(proof.sealed(&digest))
+    }
+
+    /// Prove that the dense commitment to `k` is reproduced, entry by entry
+    /// to within `tolerance`, by `factors` of rank at most `claimed_max_rank`
+    pub fn prove_dense_rank_bound<S: ProofSystem>(
+        keys: &CircuitKeys<S>,
+        k: &DMatrix<f64>,
+        opening: &PedersenOpening,
+        factors: &LowRankIdentity,
+        claimed_max_rank: usize,
+        tolerance: f64,
+    ) -> Option<Self> {
+        if factors.rank > claimed_max_rank || !(0.0..=f64::MAX).contains(&tolerance) { return None; }
+        let circuit = RankBoundCircuit::dense(k, decode_hex(&opening.digest)?, factors, tolerance);
+        let digest = circuit.digest;
+        let proof = keys.prove(circuit)?;
+        let claim = format!("Knowledge has rank ≤ {} (to within {:e} per entry)", claimed_max_rank, tolerance);
+        let proof_type = ProofType::RankBound { max_rank: claimed_max_rank, tolerance: Some(tolerance) };
+        let proof = Self::with_snark::<S>(proof_type, claim, keys, &proof, Vec::new());
+        Some(proof.sealed(&digest))
+    }
+
+    /// Prove that `statement.answers` are what the committed `lrim` gives
+    /// on `statement.inputs` and are within the quality bound of
+    /// `statement.expected`
+    pub fn prove_challenge_response<S: ProofSystem>(
+        keys: &CircuitKeys<S>,
+        lrim: &LowRankIdentity,
+        opening: &PedersenOpening,
+        domain_name: &str,
+        statement: &ChallengeStatement,
+    ) -> Option<Self> {
+        let circuit = ChallengeCircuit::new(lrim, decode_hex(&opening.digest)?, statement.clone());
+        let digest = circuit.digest;
+        let proof = keys.prove(circuit)?;
+        let quality_bound = from_fixed(statement.quality_bound, FIXED_POINT_FRAC_BITS);
+        let claim = format!(
+            "Entity answered {} challenges in domain '{}' to within {:.3} of the expected answers",
+            statement.inputs.len(),
+            domain_name,
+            quality_bound
+        );
+        let proof_type = ProofType::ChallengeResponse { domain: domain_name.to_string(), quality_bound };
+        let mut proof = Self::with_snark::<S>(proof_type, claim, keys, &proof, statement.flatten());
+        proof.verifier_challenge = Some(statement.nonce.clone());
+        let answers = statement.answers.iter().flatten();
+        proof.response = Some(answers.map(|&y| from_fixed(y, FIXED_POINT_FRAC_BITS)).collect());
+        Some(proof.sealed(&digest))
+    }
+
+    /// The statement a challenge-response proof about an m × n seed makes;
+    /// `None` for other proofs, or if `response` disagrees with it
+    pub fn challenge_statement(&self, dims: (usize, usize)) -> Option<ChallengeStatement> {
+        let ProofType::ChallengeResponse { quality_bound, .. } = &self.proof_type else { return None };
+        let nonce = self.verifier_challenge.as_deref()?;
+        let bound = to_fixed(*quality_bound, FIXED_POINT_FRAC_BITS);
+        let statement = ChallengeStatement::unflatten(nonce, &self.snark.as_ref()?.inputs, dims, bound)?;
+        let response = self.response.as_ref()?;
+        let answers = statement.answers.iter().flatten();
+        let agrees = response.len() == answers.clone().count()
+            && response.iter().zip(answers).all(|(&y, &a)| to_fixed(y, FIXED_POINT_FRAC_BITS) == a);
+        agrees.then_some(statement)
+    }
+
+    /// Check a non-interactive challenge-response proof: its challenges
+    /// are the ones `challenge` derives for `commitment`, and the SNARK
+    /// verifies against it
+    pub fn verify_public_challenge<S: ProofSystem>(
+        &self,
+        commitment: &ZkCommitment,
+        key: &CircuitVerifyingKey<S>,
+        challenge: &PublicChallenge,
+    ) -> bool {
+        let ProofType::ChallengeResponse { domain, .. } = &self.proof_type else { return false };
+        let (Some(stated), Some(derived)) =
+            (self.challenge_statement(commitment.committed_dims), challenge.statement(commitment))
+        else {
+            return false;
+        };
+        *domain == challenge.domain
+            && ChallengeStatement { answers: Vec::new(), ..stated } == derived
+            && self.verify_against_commitment(commitment, key)
+    }
+
+    /// Prove that the canonical factors of `lrim` reproduce `benchmark`
+    /// to within `max_error`, relative to ‖Y‖_F if `relative`. `None` if
+    /// they do not or the keys are for another shape.
+    pub fn prove_reconstruction<S: ProofSystem>(
+        keys: &CircuitKeys<S>,
+        lrim: &LowRankIdentity,
+        opening: &PedersenOpening,
+        benchmark: &Benchmark,
+        max_error: f64,
+        relative: bool,
+    ) -> Option<Self> {
+        let statement = benchmark.statement(max_error, relative)?;
+        let circuit = ReconstructionCircuit::new(lrim, decode_hex(&opening.digest)?, statement.clone());
+        let digest = circuit.digest;
+        let proof = keys.prove(circuit)?;
+        let claim = if relative {
+            format!("Knowledge reproduces benchmark '{}' with relative error ≤ {:e}", benchmark.name, max_error)
+        } else {
+            format!("Knowledge reproduces benchmark '{}' with error ≤ {:e}", benchmark.name, max_error)
+        };
+        let proof_type = ProofType::ReconstructionError { benchmark: benchmark.name.clone(), max_error, relative };
+        let proof = Self::with_snark::<S>(proof_type, claim, keys, &proof, statement.flatten());
+        Some(proof.sealed(&digest))
+    }
+
+    /// The statement a reconstruction-error proof about an m × n seed
+    /// makes; `None` for other proofs
+    pub fn reconstruction_statement(&self, dims: (usize, usize)) -> Option<ReconstructionStatement> {
+        let ProofType::ReconstructionError { max_error, relative, .. } = &self.proof_type else { return None };
+        ReconstructionStatement::unflatten(&self.snark.as_ref()?.inputs, dims, *max_error, *relative)
+    }
+
+    /// Check a reconstruction-error proof: it is about `benchmark`, and
+    /// the SNARK verifies against `commitment`
+    pub fn verify_benchmark<S: ProofSystem>(
+        &self,
+        commitment: &ZkCommitment,
+        key: &CircuitVerifyingKey<S>,
+        benchmark: &Benchmark,
+    ) -> bool {
+        let ProofType::ReconstructionError { benchmark: name, max_error, relative } = &self.proof_type else {
+            return false;
+        };
+        let (Some(stated), Some(expected)) =
+            (self.reconstruction_statement(commitment.committed_dims), benchmark.statement(*max_error, *relative))
+        else {
+            return false;
+        };
+        *name == benchmark.name && stated == expected && self.verify_against_commitment(commitment, key)
+    }
+
+    /// Prove that the Pedersen points and the circuit digest of
+    /// `commitment` open to the same factors, those of `lrim`. `None` if
+    /// `opening` does not open it or the keys are for another shape.
+    pub fn prove_consistency<S: ProofSystem>(
+        keys: &CircuitKeys<S>,
+        commitment: &ZkCommitment,
+        lrim: &LowRankIdentity,
+        opening: &PedersenOpening,
+    ) -> Option<Self> {
+        let (circuit, statement) = ConsistencyCircuit::new(commitment, lrim, opening)?;
+        let digest = circuit.inputs[0];
+        let proof = keys.prove(circuit)?;
+        let claim = "Pedersen points and circuit digest commit to the same factors".to_string();
+        let mut proof = Self::with_snark::<S>(ProofType::Consistency, claim, keys, &proof, Vec::new());
+        proof.consistency = Some(statement);
+        Some(proof.sealed(&digest))
+    }
+
+    /// Publish the transcript of a finished compatibility run as seen by
+    /// the party holding `own`; `None` if the run has no outcome or did not
+    /// involve `own`
+    pub fn from_compatibility(transcript: CompatibilityTranscript, own: &ZkCommitment) -> Option<Self> {
+        let other = if transcript.alice == own.matrix_hash {
+            transcript.bob.clone()
+        } else if transcript.bob == own.matrix_hash {
+            transcript.alice.clone()
+        } else {
+            return None;
+        };
+        let short = other.get(..16).unwrap_or(&other);
+        let claim = match transcript.outcome? {
+            CompatibilityOutcome::Score(score) => format!("V-subspace overlap with {}… is {:.3}", short, score),
+            CompatibilityOutcome::Threshold { threshold, reached } => {
+                let relation = if reached { "≥" } else { "<" };
+                format!("V-subspace overlap with {}… is {} {:.3}", short, relation, threshold)
+            }
+        };
         Some(Self {
-            proof_type: ProofType::RankBound { max_rank: claimed_max_rank },
-            claim: format!("Knowledge has rank ≤ {}", claimed_max_rank),
-            proof_hash,
+            proof_type: ProofType::Compatibility { other_commitment: other, subspace: "V".into() },
+            claim,
+            proof_hash: transcript.digest(),
             verifier_challenge: None,
             response: None,
+            snark: None,
+            compatibility: Some(transcript),
+            consistency: None,
         })
     }
 
-    pub fn verify_against_commitment(&self, _commitment: &super::ZkCommitment) -> bool {
-        !self.proof_hash.is_empty()
+    /// Check a compatibility proof against both parties' commitments, in
+    /// either order; every round must be signed by its sender (see
+    /// `CompatibilityTranscript::verify`)
+    pub fn verify_compatibility(&self, own: &ZkCommitment, other: &ZkCommitment) -> bool {
+        let (ProofType::Compatibility { other_commitment, .. }, Some(transcript)) = (&self.proof_type, &self.compatibility)
+        else {
+            return false;
+        };
+        let (alice, bob) = if transcript.alice == own.matrix_hash { (own, other) } else { (other, own) };
+        *other_commitment == other.matrix_hash
+            && transcript.digest() == self.proof_hash
+            && transcript.verify(alice, bob)
+    }
+
+    /// Check the proof against the prover's commitment with the verifying
+    /// key for the commitment's shape. The commitment fixes the dimensions
+    /// and rank the proof must be about. Dense rank bounds are held to
+    /// `MAX_RANK_TOLERANCE`. Capability proofs are only checked by
+    /// `verify_capability`, against the verifier's own domain vector.
+    pub fn verify_against_commitment<S: ProofSystem>(&self, commitment: &ZkCommitment, key: &CircuitVerifyingKey<S>) -> bool {
+        !matches!(self.proof_type, ProofType::Capability { .. }) && self.verify_within(commitment, key, MAX_RANK_TOLERANCE)
+    }
+
+    /// Check a capability proof: it is about the unit vector `domain`, and
+    /// the SNARK verifies against `commitment`
+    pub fn verify_capability<S: ProofSystem>(
+        &self,
+        commitment: &ZkCommitment,
+        key: &CircuitVerifyingKey<S>,
+        domain: &nalgebra::DVector<f64>,
+    ) -> bool {
+        matches!(self.proof_type, ProofType::Capability { .. })
+            && is_unit(domain)
+            && self.snark.as_ref().is_some_and(|snark| snark.inputs == fixed_domain(domain))
+            && self.verify_within(commitment, key, MAX_RANK_TOLERANCE)
+    }
+
+    /// Check a rank-bound proof as `verify_against_commitment` does, with
+    /// the largest per-entry tolerance this verifier accepts for a dense
+    /// commitment
+    pub fn verify_rank_bound<S: ProofSystem>(
+        &self,
+        commitment: &ZkCommitment,
+        key: &CircuitVerifyingKey<S>,
+        max_tolerance: f64,
+    ) -> bool {
+        matches!(self.proof_type, ProofType::RankBound { .. }) && self.verify_within(commitment, key, max_tolerance)
+    }
+
+    fn verify_within<S: ProofSystem>(&self, commitment: &ZkCommitment, key: &CircuitVerifyingKey<S>, max_tolerance: f64) -> bool {
+        if let ProofType::RankBound { tolerance: Some(tolerance), .. } = self.proof_type {
+            if !(0.0..=max_tolerance).contains(&tolerance) { return false; }
+        }
+        let Some(snark) = self.snark.as_ref().filter(|snark| snark.backend == S::BACKEND) else { return false };
+        if snark.key_fingerprint.as_ref().is_some_and(|f| *f != key.fingerprint()) { return false; }
+        let Some(digest) = commitment.circuit_digest.as_deref().and_then(decode_hex::<Fr>) else { return false };
+        let Some(proof) = S::decode_proof(&snark.proof) else { return false };
+        if self.snark_binding(&digest).as_deref() != Some(self.proof_hash.as_str()) { return false; }
+        let Some((shape, inputs)) = self.public_statement(commitment, digest) else { return false };
+        key.shape == shape && key.verify(&inputs, &proof)
+    }
+
+    /// As `verify_against_commitment`, with the key for the commitment's
+    /// shape taken from `cache`
+    pub fn verify_cached<S: ProofSystem>(&self, commitment: &ZkCommitment, cache: &mut KeyCache<S>) -> bool {
+        let Some(digest) = commitment.circuit_digest.as_deref().and_then(decode_hex::<Fr>) else { return false };
+        let Some((shape, _)) = self.public_statement(commitment, digest) else { return false };
+        cache.verifying_key(shape).is_some_and(|key| self.verify_against_commitment(commitment, key))
+    }
+
+    /// The circuit shape the commitment fixes for this proof, and the
+    /// public inputs it is checked with
+    fn public_statement(&self, commitment: &ZkCommitment, digest: Fr) -> Option<(CircuitShape, Vec<Fr>)> {
+        let snark = self.snark.as_ref()?;
+        let (m, n) = commitment.committed_dims;
+        let rank = commitment.committed_rank;
+        match &self.proof_type {
+            ProofType::Capability { min_accuracy, .. } => {
+                if commitment.is_dense() { return None; }
+                let threshold = to_fixed(*min_accuracy, FIXED_POINT_FRAC_BITS);
+                let inputs = CapabilityCircuit::public_inputs(digest, &snark.inputs, threshold);
+                Some((CircuitShape::Capability { n, rank }, inputs))
+            }
+            ProofType::RankBound { max_rank, tolerance } => {
+                let dense = commitment.is_dense();
+                if rank > *max_rank || tolerance.is_some() != dense || !snark.inputs.is_empty() { return None; }
+                let tolerance = tolerance.map(|t| to_fixed(t, FIXED_POINT_FRAC_BITS));
+                let inputs = RankBoundCircuit::public_inputs(digest, tolerance);
+                Some((CircuitShape::RankBound { m, n, rank, dense }, inputs))
+            }
+            ProofType::ChallengeResponse { .. } => {
+                if commitment.is_dense() { return None; }
+                let statement = self.challenge_statement((m, n))?;
+                let inputs = ChallengeCircuit::public_inputs(digest, &statement)?;
+                Some((CircuitShape::Challenge { m, n, rank, count: statement.inputs.len() }, inputs))
+            }
+            ProofType::ReconstructionError { .. } => {
+                if commitment.is_dense() { return None; }
+                let statement = self.reconstruction_statement((m, n))?;
+                let inputs = ReconstructionCircuit::public_inputs(digest, &statement);
+                Some((CircuitShape::Reconstruction { m, n, rank, count: statement.inputs.len() }, inputs))
+            }
+            ProofType::Consistency => {
+                if !snark.inputs.is_empty() { return None; }
+                let inputs = self.consistency.as_ref()?.public_inputs(commitment)?;
+                Some((CircuitShape::Consistency { m, n, rank }, inputs))
+            }
+            // Involves two commitments: see `verify_compatibility`
+            ProofType::Compatibility { .. } => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::fixture::Prover;
+    use crate::zk::MockBackend;
+    use nalgebra::{DMatrix, DVector};
+
+    #[test]
+    fn test_capability_proof_against_commitment() {
+        let Prover { seed, opening, d, score, .. } = Prover::new(6, 4, 2);
+        let keys: CircuitKeys = CircuitKeys::setup(CircuitShape::Capability { n: 4, rank: 2 }).unwrap();
+
+        let vk = keys.verifying_key();
+        let proof = CapabilityProof::prove_capability(&keys, &seed.lrim, &opening, &d, "test", score * 0.9).unwrap();
+        assert!(proof.verify_capability(&seed.commitment, &vk, &d));
+        assert!(!proof.verify_against_commitment(&seed.commitment, &vk));
+        assert!(CapabilityProof::prove_capability(&keys, &seed.lrim, &opening, &d, "test", score * 1.1).is_none());
+
+        // Only for the verifier's own unit domain vector
+        let scaled = &d * 1000.0;
+        assert!(CapabilityProof::prove_capability(&keys, &seed.lrim, &opening, &scaled, "test", score * 500.0).is_none());
+        assert!(!proof.verify_capability(&seed.commitment, &vk, &scaled));
+        let elsewhere = DVector::<f64>::new_random(4).normalize();
+        assert!(!proof.verify_capability(&seed.commitment, &vk, &elsewhere));
+        let blinding = decode_hex(&opening.digest).unwrap();
+        let threshold = score * 500.0;
+        let circuit = CapabilityCircuit::new(&seed.lrim, blinding, fixed_domain(&scaled), to_fixed(threshold, FIXED_POINT_FRAC_BITS));
+        let digest = circuit.digest;
+        let snark = keys.prove(circuit).unwrap();
+        let proof_type = ProofType::Capability { domain: "test".into(), min_accuracy: threshold };
+        let forged = CapabilityProof::with_snark(proof_type, String::new(), &keys, &snark, fixed_domain(&scaled)).sealed(&digest);
+        assert!(!forged.verify_capability(&seed.commitment, &vk, &d));
+
+        // Bound to this commitment and this claim
+        let other = Prover::new(6, 4, 2).seed;
+        assert!(!proof.verify_capability(&other.commitment, &vk, &d));
+        let mut inflated = proof.clone();
+        inflated.proof_type = ProofType::Capability { domain: "test".into(), min_accuracy: score * 2.0 };
+        assert!(!inflated.verify_capability(&seed.commitment, &vk, &d));
+
+        // Rank bounds are checked against the committed rank and shape
+        let keys: CircuitKeys = CircuitKeys::setup(CircuitShape::RankBound { m: 6, n: 4, rank: 2, dense: false }).unwrap();
+        let proof = CapabilityProof::prove_rank_bound(&keys, &seed.lrim, &opening, 3).unwrap();
+        assert!(proof.verify_against_commitment(&seed.commitment, &keys.verifying_key()));
+        assert!(CapabilityProof::prove_rank_bound(&keys, &seed.lrim, &opening, 1).is_none());
+        assert!(!proof.verify_against_commitment(&other.commitment, &keys.verifying_key()));
+        let mut understated = proof.clone();
+        understated.proof_type = ProofType::RankBound { max_rank: 1, tolerance: None };
+        assert!(!understated.verify_against_commitment(&seed.commitment, &keys.verifying_key()));
+
+        // A dense bound states its tolerance, and verifiers cap it
+        let full = DMatrix::<f64>::new_random(6, 4);
+        let (dense, dense_opening) = ZkCommitment::commit_dense(&full, 2);
+        let factors = LowRankIdentity::from_matrix(&full, 2);
+        let keys = CircuitKeys::<MockBackend>::setup(CircuitShape::RankBound { m: 6, n: 4, rank: 2, dense: true }).unwrap();
+        let vk = keys.verifying_key();
+        assert!(CapabilityProof::prove_dense_rank_bound(&keys, &full, &dense_opening, &factors, 2, 1e-6).is_none());
+        let inflated = CapabilityProof::prove_dense_rank_bound(&keys, &full, &dense_opening, &factors, 2, 10.0).unwrap();
+        assert!(!inflated.verify_against_commitment(&dense, &vk));
+        assert!(!inflated.verify_rank_bound(&dense, &vk, 1.0));
+        assert!(inflated.verify_rank_bound(&dense, &vk, 10.0));
+        let mut understated = inflated.clone();
+        understated.proof_type = ProofType::RankBound { max_rank: 2, tolerance: Some(1e-6) };
+        assert!(!understated.verify_rank_bound(&dense, &vk, 10.0));
+        let k = factors.reconstruct();
+        let (exact, exact_opening) = ZkCommitment::commit_dense(&k, 2);
+        let proof = CapabilityProof::prove_dense_rank_bound(&keys, &k, &exact_opening, &factors, 2, 1e-6).unwrap();
+        assert!(proof.verify_against_commitment(&exact, &vk));
     }
 }
@@ -0,0 +1,176 @@
+//! Rank-bound circuit: the committed matrix is a product of r-column factors
+//!
+//! Two kinds of commitment are supported, each with its own keys:
+//!
+//! * Factored (`ZkCommitment::commit`): the circuit recomputes the factor
+//!   digest from private U (m × r), Σ (r) and V (n × r), so the commitment
+//!   opens to exactly r columns.
+//! * Dense (`ZkCommitment::commit_dense`): the digest covers the m × n
+//!   entries of K in Q32.32. The circuit also takes private factors and
+//!   checks every entry against Σᵢ uᵢσᵢvᵢᵀ to within a public tolerance τ,
+//!   at scale 2^96: −τ ≤ D ≤ τ for D = K·2^64 − UΣVᵀ, by showing D + τ and
+//!   τ − D both fit in 128 bits. Factor entries are range-checked to signed
+//!   64 bits, so the products never wrap.
+//!
+//! Public inputs, in order: the digest, then τ·2^64 for dense commitments.
+
+use super::circuit::{mimc_hash, mimc_hash_wires, Circuit, CircuitShape, FactorWitness};
+use super::gadgets::{Wire, VALUE_BITS};
+use crate::seed::{to_fixed, LowRankIdentity, LrimScalar, FIXED_POINT_FRAC_BITS};
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use nalgebra::DMatrix;
+
+/// Width of the two tolerance range checks; τ·2^64 < 2^127
+const TOLERANCE_BITS: u32 = 128;
+
+fn fixed_entries(k: &DMatrix<f64>) -> Vec<i64> {
+    k.row_iter().flat_map(|row| row.iter().map(|&x| to_fixed(x, FIXED_POINT_FRAC_BITS)).collect::<Vec<_>>()).collect()
+}
+
+/// MiMC digest of a dense matrix (row-major, Q32.32) under `blinding`
+pub fn dense_digest(k: &DMatrix<f64>, blinding: Fr) -> Fr {
+    let mut inputs = vec![blinding];
+    inputs.extend(fixed_entries(k).into_iter().map(Fr::from));
+    mimc_hash(&inputs)
+}
+
+/// "The committed m × n matrix has rank at most r"
+#[derive(Debug, Clone)]
+pub struct RankBoundCircuit {
+    pub m: usize,
+    pub n: usize,
+    pub digest: Fr,
+    /// Per-entry tolerance (Q32.32), dense commitments only
+    pub tolerance: Option<i64>,
+    /// Row-major Q32.32 entries, dense commitments only
+    matrix: Vec<i64>,
+    /// Factors; their blinding is the digest's
+    witness: FactorWitness,
+}
+
+impl RankBoundCircuit {
+    /// The statement for a factored commitment to `lrim` under `blinding`
+    pub fn factored<T: LrimScalar>(lrim: &LowRankIdentity<T>, blinding: Fr) -> Self {
+        let witness = FactorWitness::new(lrim, blinding);
+        Self { m: lrim.m, n: lrim.n, digest: witness.digest(), tolerance: None, matrix: Vec::new(), witness }
+    }
+
+    /// The statement for a dense commitment to `k` under `blinding`, with
+    /// `factors` reproducing `k` to within `tolerance` per entry
+    pub fn dense(k: &DMatrix<f64>, blinding: Fr, factors: &LowRankIdentity, tolerance: f64) -> Self {
+        Self {
+            m: k.nrows(),
+            n: k.ncols(),
+            digest: dense_digest(k, blinding),
+            tolerance: Some(to_fixed(tolerance, FIXED_POINT_FRAC_BITS).max(0)),
+            matrix: fixed_entries(k),
+            witness: FactorWitness::new(factors, blinding),
+        }
+    }
+
+    /// All-zero assignment of the right shape, for key generation
+    pub fn blank(m: usize, n: usize, rank: usize, dense: bool) -> Self {
+        Self {
+            m,
+            n,
+            digest: Fr::ZERO,
+            tolerance: dense.then_some(0),
+            matrix: if dense { vec![0; m * n] } else { Vec::new() },
+            witness: FactorWitness::blank(m, n, rank),
+        }
+    }
+
+    pub fn public_inputs(digest: Fr, tolerance: Option<i64>) -> Vec<Fr> {
+        let mut inputs = vec![digest];
+        inputs.extend(tolerance.map(|t| Fr::from(t.max(0)) * Fr::from(1u128 << 64)));
+        inputs
+    }
+}
+
+impl Circuit for RankBoundCircuit {
+    fn shape(&self) -> CircuitShape {
+        CircuitShape::RankBound { m: self.m, n: self.n, rank: self.witness.rank(), dense: self.tolerance.is_some() }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for RankBoundCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let w = &self.witness;
+        let (m, n) = (self.m, self.n);
+        if !w.has_shape(m, n) || self.tolerance.is_some() != (self.matrix.len() == m * n && m * n > 0) {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let public = Self::public_inputs(self.digest, self.tolerance);
+        let public = public.into_iter().map(|x| Wire::input(&cs, x)).collect::<Result<Vec<_>, _>>()?;
+        let blinding = Wire::witness(&cs, w.blinding)?;
+
+        let Some(tolerance) = public.get(1) else {
+            // Factored: the digest opens to r columns
+            let f = w.factors.allocate(&cs, None, None)?;
+            let u_digest = mimc_hash_wires(&cs, f.u.iter().flatten())?;
+            let inputs = std::iter::once(&blinding).chain(&f.sigma).chain(f.v.iter().flatten()).chain([&u_digest]);
+            return mimc_hash_wires(&cs, inputs)?.enforce_equal(&cs, &public[0]);
+        };
+
+        // Dense: the digest opens to K …
+        let entries = self.matrix.iter().map(|&x| Wire::witness(&cs, Fr::from(x))).collect::<Result<Vec<_>, _>>()?;
+        mimc_hash_wires(&cs, std::iter::once(&blinding).chain(&entries))?.enforce_equal(&cs, &public[0])?;
+
+        // … and K ≈ UΣVᵀ entry by entry
+        let f = w.factors.allocate(&cs, Some(VALUE_BITS), Some(VALUE_BITS))?;
+        // W = V Σ, column by column
+        let wv = f.sigma.iter().zip(&f.v)
+            .map(|(s, col)| col.iter().map(|x| x.mul(&cs, s)).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let scale = Fr::from(1u128 << 64);
+        for j in 0..m {
+            let u_row: Vec<Wire> = f.u.iter().map(|col| col[j].clone()).collect();
+            for k in 0..n {
+                let w_row: Vec<Wire> = wv.iter().map(|col| col[k].clone()).collect();
+                let product = Wire::dot(&cs, &u_row, &w_row)?;
+                let diff = entries[j * n + k].scale(scale).sub(&product);
+                diff.enforce_abs_leq(&cs, tolerance, TOLERANCE_BITS)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::CircuitKeys;
+
+    #[test]
+    fn test_rank_bound_circuits() {
+        let k = DMatrix::<f64>::new_random(4, 1) * DMatrix::<f64>::new_random(1, 3);
+        let lrim = LowRankIdentity::from_matrix(&k, 1);
+        let blinding = Fr::from(7u64);
+
+        // Factored: a commitment opens to exactly its columns
+        let factored = RankBoundCircuit::factored(&lrim, blinding);
+        assert!(factored.is_satisfied());
+        let wider = LowRankIdentity::from_matrix(&DMatrix::new_random(4, 3), 2);
+        let mut forged = RankBoundCircuit::factored(&wider, blinding);
+        forged.digest = factored.digest;
+        assert!(!forged.is_satisfied());
+
+        // Dense: K is reproduced by a rank-1 product, a rank-2 matrix is not
+        let dense = RankBoundCircuit::dense(&k, blinding, &lrim, 1e-6);
+        assert!(dense.is_satisfied());
+        let full = DMatrix::<f64>::new_random(4, 3);
+        assert!(!RankBoundCircuit::dense(&full, blinding, &LowRankIdentity::from_matrix(&full, 1), 1e-6).is_satisfied());
+        let mut tight = dense.clone();
+        tight.tolerance = Some(0);
+        assert!(!tight.is_satisfied());
+
+        let keys: CircuitKeys = CircuitKeys::setup(dense.shape()).unwrap();
+        let proof = keys.prove(dense.clone()).unwrap();
+        let vk = keys.verifying_key();
+        assert!(vk.verify(&RankBoundCircuit::public_inputs(dense.digest, dense.tolerance), &proof));
+        assert!(!vk.verify(&RankBoundCircuit::public_inputs(dense.digest, Some(1)), &proof));
+        assert!(keys.prove(factored).is_none());
+    }
+}
@@ -0,0 +1,234 @@
+//! Reconstruction error on a public test set
+//!
+//! A seed's owner proves ‖UΣVᵀX − Y‖_F ≤ ε for a published benchmark
+//! (X, Y), c test inputs and their targets, against the circuit digest in
+//! the seed's commitment. The bound is absolute, or relative to ‖Y‖_F; a
+//! relative bound is turned into an absolute one from Y as sent, so prover
+//! and verifier agree on it to the bit.
+//!
+//! In the circuit, U and V are range-checked to 34 signed bits and σ to 64
+//! as in `zk::challenge`, so UΣVᵀx at scale 2^128 never wraps. Each entry
+//! of the residual is rescaled to Q32.32, rounding toward −∞ (a shift of
+//! at most 2^−32 per entry), and range-checked to 64 signed bits; the sum
+//! of their squares is compared with ε² at scale 2^64.
+//!
+//! Public inputs, in order: the digest, X and Y row by row, ε² (scale
+//! 2^64).
+
+use super::circuit::{mimc_hash_wires, Circuit, CircuitShape, FactorWitness};
+use super::gadgets::{FixedPoint, Wire, VALUE_BITS};
+use crate::seed::{LowRankIdentity, LrimScalar};
+use ark_bn254::Fr;
+use ark_ff::AdditiveGroup;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use nalgebra::DMatrix;
+use serde::{Deserialize, Serialize};
+
+/// Canonical U and V entries lie in [−2^32, 2^32] in Q32.32
+const UNIT_BITS: u32 = 34;
+/// Width of the error check; ε² < 2^126
+const ERROR_BITS: u32 = 128;
+
+/// A published test set: inputs X (n × c) and targets Y (m × c)
+#[derive(Debug, Clone)]
+pub struct Benchmark {
+    pub name: String,
+    pub inputs: DMatrix<f64>,
+    pub targets: DMatrix<f64>,
+}
+
+impl Benchmark {
+    /// The statement "error at most `max_error`", relative to ‖Y‖_F if
+    /// `relative`; `None` if X and Y disagree on c
+    pub fn statement(&self, max_error: f64, relative: bool) -> Option<ReconstructionStatement> {
+        if self.inputs.ncols() != self.targets.ncols() { return None; }
+        let fixed = FixedPoint;
+        let rows = |x: &DMatrix<f64>| -> Vec<Vec<i64>> {
+            x.column_iter().map(|c| c.iter().map(|&v| fixed.encode(v)).collect()).collect()
+        };
+        let (inputs, targets) = (rows(&self.inputs), rows(&self.targets));
+        let bound = ReconstructionStatement::bound(&targets, max_error, relative);
+        Some(ReconstructionStatement { inputs, targets, bound })
+    }
+
+    /// ‖UΣVᵀX − Y‖_F in floating point, before any encoding
+    pub fn error<T: LrimScalar>(&self, lrim: &LowRankIdentity<T>) -> f64 {
+        let c = lrim.to_f64();
+        (&c.u * DMatrix::from_diagonal(&c.sigma) * c.v.transpose() * &self.inputs - &self.targets).norm()
+    }
+}
+
+/// Public side of a reconstruction-error proof; all values Q32.32
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReconstructionStatement {
+    /// One row (length n) per test input
+    pub inputs: Vec<Vec<i64>>,
+    /// One row (length m) per test input
+    pub targets: Vec<Vec<i64>>,
+    /// Bound on ‖UΣVᵀX − Y‖_F
+    pub bound: i64,
+}
+
+impl ReconstructionStatement {
+    /// ε in Q32.32, scaled by ‖Y‖_F if `relative`
+    pub fn bound(targets: &[Vec<i64>], max_error: f64, relative: bool) -> i64 {
+        let fixed = FixedPoint;
+        // Through Q32.32 first, so a JSON round trip of `max_error` cannot move it
+        let max_error = fixed.decode(fixed.encode(max_error));
+        let scale = if relative {
+            targets.iter().flatten().map(|&y| fixed.decode(y).powi(2)).sum::<f64>().sqrt()
+        } else {
+            1.0
+        };
+        fixed.encode(max_error * scale).max(0)
+    }
+
+    /// X then Y, row by row
+    pub fn flatten(&self) -> Vec<i64> {
+        self.inputs.iter().chain(&self.targets).flatten().copied().collect()
+    }
+
+    /// Inverse of `flatten` for an m × n seed
+    pub fn unflatten(flat: &[i64], (m, n): (usize, usize), max_error: f64, relative: bool) -> Option<Self> {
+        let count = flat.len() / (n + m).max(1);
+        if count * (n + m) != flat.len() { return None; }
+        let (inputs, targets) = flat.split_at(count * n);
+        let rows = |xs: &[i64], width: usize| xs.chunks(width.max(1)).map(<[i64]>::to_vec).collect::<Vec<_>>();
+        let targets = rows(targets, m);
+        let bound = Self::bound(&targets, max_error, relative);
+        Some(Self { inputs: rows(inputs, n), targets, bound })
+    }
+}
+
+/// "The committed factors reproduce Y from X to within ε"
+#[derive(Debug, Clone)]
+pub struct ReconstructionCircuit {
+    pub m: usize,
+    pub n: usize,
+    pub digest: Fr,
+    pub statement: ReconstructionStatement,
+    witness: FactorWitness,
+}
+
+impl ReconstructionCircuit {
+    /// The statement for `lrim`'s canonical factors, committed under `blinding`
+    pub fn new<T: LrimScalar>(lrim: &LowRankIdentity<T>, blinding: Fr, statement: ReconstructionStatement) -> Self {
+        let witness = FactorWitness::new(lrim, blinding);
+        Self { m: lrim.m, n: lrim.n, digest: witness.digest(), statement, witness }
+    }
+
+    /// All-zero assignment of the right shape, for key generation
+    pub fn blank(m: usize, n: usize, rank: usize, count: usize) -> Self {
+        let statement = ReconstructionStatement { inputs: vec![vec![0; n]; count], targets: vec![vec![0; m]; count], bound: 0 };
+        Self { m, n, digest: Fr::ZERO, statement, witness: FactorWitness::blank(m, n, rank) }
+    }
+
+    pub fn public_inputs(digest: Fr, statement: &ReconstructionStatement) -> Vec<Fr> {
+        let mut inputs = vec![digest];
+        inputs.extend(statement.flatten().into_iter().map(Fr::from));
+        let bound = statement.bound.max(0) as u128;
+        inputs.push(Fr::from(bound * bound));
+        inputs
+    }
+}
+
+impl Circuit for ReconstructionCircuit {
+    fn shape(&self) -> CircuitShape {
+        CircuitShape::Reconstruction { m: self.m, n: self.n, rank: self.witness.rank(), count: self.statement.inputs.len() }
+    }
+}
+
+impl ConstraintSynthesizer<Fr> for ReconstructionCircuit {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        let (w, s) = (&self.witness, &self.statement);
+        let (m, n, count) = (self.m, self.n, s.inputs.len());
+        if !w.has_shape(m, n)
+            || s.inputs.iter().any(|x| x.len() != n)
+            || s.targets.len() != count || s.targets.iter().any(|y| y.len() != m)
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let public = Self::public_inputs(self.digest, s);
+        let public = public.into_iter().map(|x| Wire::input(&cs, x)).collect::<Result<Vec<_>, _>>()?;
+        let digest = &public[0];
+        let inputs: Vec<&[Wire]> = public[1..1 + count * n].chunks(n.max(1)).collect();
+        let targets = &public[1 + count * n..1 + count * (n + m)];
+        let bound = &public[public.len() - 1];
+
+        let blinding = Wire::witness(&cs, w.blinding)?;
+        let f = w.factors.allocate(&cs, Some(VALUE_BITS), Some(UNIT_BITS))?;
+
+        // The factors are the committed ones
+        let u_digest = mimc_hash_wires(&cs, f.u.iter().flatten())?;
+        let committed = std::iter::once(&blinding).chain(&f.sigma).chain(f.v.iter().flatten()).chain([&u_digest]);
+        mimc_hash_wires(&cs, committed)?.enforce_equal(&cs, digest)?;
+
+        // W = V Σ, column by column
+        let wv = f.sigma.iter().zip(&f.v)
+            .map(|(s, col)| col.iter().map(|x| x.mul(&cs, s)).collect::<Result<Vec<_>, _>>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let u_rows: Vec<Vec<Wire>> = (0..m).map(|j| f.u.iter().map(|col| col[j].clone()).collect()).collect();
+        let fixed = FixedPoint;
+        let mut error = Wire::constant(Fr::ZERO);
+        for (c, x) in inputs.iter().enumerate() {
+            // t = Wᵀx, then U t against the target, back in Q32.32
+            let t = Wire::mat_vec(&cs, &wv, x)?;
+            for (j, product) in Wire::mat_vec(&cs, &u_rows, &t)?.iter().enumerate() {
+                let residual = product.sub(&targets[c * m + j].scale(fixed.scale(3)));
+                let residual = residual.rescale(&cs, 3 * FixedPoint::FRAC_BITS, VALUE_BITS)?;
+                error = error.add(&residual.mul(&cs, &residual)?);
+            }
+        }
+        error.enforce_leq(&cs, bound, ERROR_BITS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zk::fixture::Prover;
+    use crate::zk::{CapabilityProof, CircuitKeys, Groth16Backend, MockBackend, ProofType};
+
+    #[test]
+    fn test_reconstruction_error_proofs() {
+        let Prover { k, seed, opening, .. } = Prover::new(6, 4, 2);
+        let inputs = DMatrix::new_random(4, 3);
+        let benchmark = Benchmark { name: "bench".into(), targets: &k * &inputs, inputs };
+        let error = benchmark.error(&seed.lrim);
+        let shape = CircuitShape::Reconstruction { m: 6, n: 4, rank: 2, count: 3 };
+        let prove = |keys: &CircuitKeys<MockBackend>, max_error: f64, relative: bool| {
+            CapabilityProof::prove_reconstruction(keys, &seed.lrim, &opening, &benchmark, max_error, relative)
+        };
+
+        let mock = CircuitKeys::<MockBackend>::setup(shape).unwrap();
+        let vk = mock.verifying_key();
+        let proof = prove(&mock, error * 1.01 + 1e-6, false).unwrap();
+        assert!(proof.verify_benchmark(&seed.commitment, &vk, &benchmark));
+        assert!(prove(&mock, error * 0.9, false).is_none());
+
+        // Relative bounds scale with ‖Y‖_F
+        let relative = error / benchmark.targets.norm();
+        let proof = prove(&mock, relative * 1.01 + 1e-6, true).unwrap();
+        assert!(proof.verify_benchmark(&seed.commitment, &vk, &benchmark));
+        assert!(prove(&mock, relative * 0.9, true).is_none());
+
+        // Bound to the benchmark, the bound and the commitment
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: CapabilityProof = serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify_benchmark(&seed.commitment, &vk, &benchmark));
+        let mut other = benchmark.clone();
+        other.targets[(0, 0)] += 1.0;
+        assert!(!proof.verify_benchmark(&seed.commitment, &vk, &other));
+        let mut tightened = proof.clone();
+        tightened.proof_type = ProofType::ReconstructionError { benchmark: "bench".into(), max_error: relative * 0.5, relative: true };
+        assert!(!tightened.verify_against_commitment(&seed.commitment, &vk));
+        let stranger = Prover::new(6, 4, 2).seed;
+        assert!(!proof.verify_against_commitment(&stranger.commitment, &vk));
+
+        // The same statement under Groth16
+        let keys = CircuitKeys::<Groth16Backend>::setup(shape).unwrap();
+        let proof = CapabilityProof::prove_reconstruction(&keys, &seed.lrim, &opening, &benchmark, error * 1.01 + 1e-6, false).unwrap();
+        assert!(proof.verify_benchmark(&seed.commitment, &keys.verifying_key(), &benchmark));
+        assert!(!proof.verify_benchmark(&seed.commitment, &vk, &benchmark));
+    }
+}
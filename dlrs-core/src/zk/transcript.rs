@@ -0,0 +1,126 @@
+//! Fiat–Shamir transcripts
+//!
+//! A Merlin-style running hash over SHA-256. The transcript is opened with
+//! a protocol label; every message is framed by its kind, label and length
+//! and chained into the state, and so is every challenge once drawn. A
+//! challenge therefore depends on the protocol and on everything absorbed
+//! before it, in order, and two challenges never repeat.
+//!
+//! state₀ = SHA-256(tag ‖ protocol)
+//! absorb: state ← SHA-256(state ‖ kind ‖ |label| ‖ label ‖ |msg| ‖ msg)
+//! challenge: c = SHA-256(state ‖ 0xFF ‖ |label| ‖ label), then absorb c
+
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+pub const TRANSCRIPT_TAG: &[u8] = b"DLRS/TRANSCRIPT/SHA256/v1\0";
+
+const MESSAGE: u8 = 0;
+const CHALLENGE: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    state: [u8; 32],
+}
+
+impl Transcript {
+    pub fn new(protocol: &[u8]) -> Self {
+        Self { state: Sha256::new().chain_update(TRANSCRIPT_TAG).chain_update(protocol).finalize().into() }
+    }
+
+    fn absorb(&mut self, kind: u8, label: &[u8], message: &[u8]) {
+        self.state = Sha256::new()
+            .chain_update(self.state)
+            .chain_update([kind])
+            .chain_update((label.len() as u64).to_le_bytes())
+            .chain_update(label)
+            .chain_update((message.len() as u64).to_le_bytes())
+            .chain_update(message)
+            .finalize()
+            .into();
+    }
+
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        self.absorb(MESSAGE, label, message);
+    }
+
+    pub fn append_u64(&mut self, label: &[u8], x: u64) {
+        self.append_message(label, &x.to_le_bytes());
+    }
+
+    pub fn append_i64s(&mut self, label: &[u8], xs: &[i64]) {
+        let bytes: Vec<u8> = xs.iter().flat_map(|x| x.to_le_bytes()).collect();
+        self.append_message(label, &bytes);
+    }
+
+    /// Field elements and curve points, compressed
+    pub fn append_serialized(&mut self, label: &[u8], value: &impl CanonicalSerialize) {
+        let mut bytes = Vec::new();
+        value.serialize_compressed(&mut bytes).expect("writing to a Vec cannot fail");
+        self.append_message(label, &bytes);
+    }
+
+    /// Structured public data, as JSON
+    pub fn append_json(&mut self, label: &[u8], value: &impl Serialize) {
+        self.append_message(label, &serde_json::to_vec(value).expect("public data serialises"));
+    }
+
+    pub fn challenge_bytes(&mut self, label: &[u8]) -> [u8; 32] {
+        let challenge: [u8; 32] = Sha256::new()
+            .chain_update(self.state)
+            .chain_update([0xFF])
+            .chain_update((label.len() as u64).to_le_bytes())
+            .chain_update(label)
+            .finalize()
+            .into();
+        self.absorb(CHALLENGE, label, &challenge);
+        challenge
+    }
+
+    /// Uniform in Fr, from 512 bits so the bias is negligible
+    pub fn challenge_scalar(&mut self, label: &[u8]) -> Fr {
+        let mut bytes = self.challenge_bytes(label).to_vec();
+        bytes.extend(self.challenge_bytes(label));
+        Fr::from_le_bytes_mod_order(&bytes)
+    }
+
+    /// Uniform in [−1, 1) on a 2^−52 grid
+    pub fn challenge_unit(&mut self, label: &[u8]) -> f64 {
+        let bytes = self.challenge_bytes(label);
+        let x = u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes")) >> 11;
+        x as f64 / (1u64 << 52) as f64 - 1.0
+    }
+
+    /// Hex of the current state, binding everything absorbed so far
+    pub fn digest(&self) -> String {
+        hex::encode(self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcript_challenges() {
+        let run = |protocol: &[u8], label: &[u8], message: &[u8]| {
+            let mut t = Transcript::new(protocol);
+            t.append_message(label, message);
+            t.challenge_bytes(b"c")
+        };
+        let base = run(b"p", b"m", b"hello");
+        assert_eq!(base, run(b"p", b"m", b"hello"));
+        assert_ne!(base, run(b"q", b"m", b"hello"));
+        assert_ne!(base, run(b"p", b"n", b"hello"));
+        // Framing: moving bytes between label and message changes the result
+        assert_ne!(run(b"p", b"mh", b"ello"), base);
+
+        let mut t = Transcript::new(b"p");
+        let (a, b) = (t.challenge_scalar(b"x"), t.challenge_scalar(b"x"));
+        assert_ne!(a, b);
+        assert!((0..100).map(|_| t.challenge_unit(b"u")).all(|u| (-1.0..1.0).contains(&u)));
+    }
+}